@@ -35,6 +35,7 @@ use citreelo::solve::CtlModelChecker;
 mod common;
 
 use common::generators::random_total_kripke;
+use common::model::TestAtomicProp;
 use common::zoo::{chain, cycle, fanout, total_dense};
 
 fn build_sparse(c: &mut Criterion) {
@@ -54,7 +55,7 @@ fn build_sparse(c: &mut Criterion) {
         ];
         for (family, kripke) in &models {
             group.bench_with_input(BenchmarkId::new(*family, n), kripke, |b, kripke| {
-                b.iter(|| CtlModelChecker::new(black_box(kripke)));
+                b.iter(|| CtlModelChecker::<_, TestAtomicProp>::new(black_box(kripke)));
             });
         }
     }
@@ -69,7 +70,7 @@ fn build_dense(c: &mut Criterion) {
     for n in [4usize, 8, 12, 16, 32, 64] {
         let kripke = total_dense(n);
         group.bench_with_input(BenchmarkId::from_parameter(n), &kripke, |b, kripke| {
-            b.iter(|| CtlModelChecker::new(black_box(kripke)));
+            b.iter(|| CtlModelChecker::<_, TestAtomicProp>::new(black_box(kripke)));
         });
     }
     group.finish();
@@ -84,7 +85,7 @@ fn build_random(c: &mut Criterion) {
     for n in [8usize, 12, 16, 32, 64] {
         let kripke = random_total_kripke(0xB111D + n as u64, n, 3);
         group.bench_with_input(BenchmarkId::from_parameter(n), &kripke, |b, kripke| {
-            b.iter(|| CtlModelChecker::new(black_box(kripke)));
+            b.iter(|| CtlModelChecker::<_, TestAtomicProp>::new(black_box(kripke)));
         });
     }
     group.finish();
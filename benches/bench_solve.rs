@@ -16,7 +16,9 @@ limitations under the License.
 
 //! Benchmarks of [CtlModelChecker::get_sat_set] on a pre-built checker
 //! (the BDD representation is constructed outside the timed loop; see
-//! bench_build.rs for the construction cost).
+//! bench_build.rs for the construction cost). The checkers keep no
+//! satisfaction sets across queries, so that every iteration solves the
+//! formula from scratch.
 //!
 //! There is one group per cost mechanism of the solver rather than one
 //! per operator :
@@ -27,7 +29,9 @@ limitations under the License.
 //!   one state per iteration),
 //! - nested fixpoints (`AG EF`),
 //! - dense models, where the transition-relation BDD is the stress,
-//! - a seeded random batch approximating an average caller workload.
+//! - a seeded random batch approximating an average caller workload,
+//! - the naive and frontier fixpoint strategies side by side, on the
+//!   least and greatest fixpoint models where they differ.
 //!
 //! Each scaling group sweeps the model size so that criterion reports
 //! a curve per mechanism.
@@ -37,6 +41,7 @@ use std::time::Duration;
 
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 
+use citreelo::fixpoint::FixpointStrategy;
 use citreelo::kripke::KripkeStructure;
 use citreelo::solve::CtlModelChecker;
 
@@ -54,6 +59,10 @@ use common::zoo::{chain, cycle, total_dense};
 /// as comparisons only cover the sizes present in the baseline.
 const SIZES: [usize; 6] = [4, 8, 12, 16, 32, 64];
 
+/// Model sizes for the fixpoint strategy groups, large enough for the
+/// frontier to be much smaller than the accumulated set.
+const STRATEGY_SIZES: [usize; 3] = [64, 256, 1024];
+
 /// One scaling group : solves `formula_str` on `model_of(n)` for every
 /// size in [SIZES], with the checker built outside the timed loop.
 fn bench_formula_scaling(
@@ -69,7 +78,7 @@ fn bench_formula_scaling(
     group.sample_size(20);
     for n in SIZES {
         let kripke = model_of(n);
-        let checker = CtlModelChecker::new(&kripke);
+        let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
         group.bench_with_input(BenchmarkId::from_parameter(n), &checker, |b, checker| {
             b.iter(|| checker.get_sat_set(black_box(&phi)));
         });
@@ -77,6 +86,40 @@ fn bench_formula_scaling(
     group.finish();
 }
 
+/// One comparison group : solves `formula_str` on `model_of(n)` for
+/// every size in [STRATEGY_SIZES], once per [FixpointStrategy].
+fn bench_strategy_scaling(
+    c: &mut Criterion,
+    group_name: &str,
+    formula_str: &str,
+    model_of: impl Fn(usize) -> KripkeStructure<TestDomainOfAp>,
+) {
+    let phi = parse(formula_str);
+    let mut group = c.benchmark_group(group_name);
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_secs(2));
+    group.sample_size(20);
+    for n in STRATEGY_SIZES {
+        let kripke = model_of(n);
+        for (strategy_name, strategy) in [
+            ("naive", FixpointStrategy::Naive),
+            ("frontier", FixpointStrategy::Frontier),
+        ] {
+            let checker = CtlModelChecker::new(&kripke)
+                .with_cache_capacity(0)
+                .with_fixpoint_strategy(strategy);
+            group.bench_with_input(
+                BenchmarkId::new(strategy_name, n),
+                &checker,
+                |b, checker| {
+                    b.iter(|| checker.get_sat_set(black_box(&phi)));
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
 fn solve_least_fixpoints(c: &mut Criterion) {
     // on chain(n), q holds only on the last state : the fixpoint has
     // to walk the whole diameter backwards
@@ -92,6 +135,15 @@ fn solve_greatest_fixpoints(c: &mut Criterion) {
     bench_formula_scaling(c, "solve/EG_on_cycle", "EG p", cycle);
 }
 
+fn solve_fixpoint_strategies(c: &mut Criterion) {
+    // the models of the two groups above, where the frontier is a
+    // single state at every iteration ; AF, A[_ U _] and EG are
+    // iterated naively by both strategies, hence not compared
+    bench_strategy_scaling(c, "strategy/EF_on_chain", "EF q", chain);
+    bench_strategy_scaling(c, "strategy/EU_on_chain", "E[p U q]", chain);
+    bench_strategy_scaling(c, "strategy/AG_on_cycle", "AG p", cycle);
+}
+
 fn solve_nested_fixpoints(c: &mut Criterion) {
     bench_formula_scaling(c, "solve/AG_EF_on_chain", "AG EF p", chain);
 }
@@ -107,7 +159,7 @@ fn solve_dense(c: &mut Criterion) {
     group.sample_size(20);
     for n in [4usize, 8, 12, 16, 32] {
         let kripke = total_dense(n);
-        let checker = CtlModelChecker::new(&kripke);
+        let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
         group.bench_with_input(BenchmarkId::from_parameter(n), &checker, |b, checker| {
             b.iter(|| {
                 for phi in &formulas {
@@ -124,7 +176,7 @@ fn solve_random_workload(c: &mut Criterion) {
     // solved per iteration : an "average workload" smoothing out the
     // per-formula variance
     let kripke = random_total_kripke(0xC0FFEE, 12, 3);
-    let checker = CtlModelChecker::new(&kripke);
+    let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
     let formulas = random_formulas(0xFEED5EED, 20, 3);
     let mut group = c.benchmark_group("solve/random_workload");
     group.warm_up_time(Duration::from_millis(500));
@@ -146,6 +198,7 @@ criterion_group!(
     solve_greatest_fixpoints,
     solve_nested_fixpoints,
     solve_dense,
-    solve_random_workload
+    solve_random_workload,
+    solve_fixpoint_strategies
 );
 criterion_main!(benches);
@@ -0,0 +1,368 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The [AtomicPropositions](crate::AtomicPropositions) derive : reads the
+//! fields of a struct from its token trees and produces the enumeration
+//! of its propositions with their implementations.
+
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+
+use crate::ExpansionResult;
+
+/// A proposition over the domain, and the variant representing it.
+struct Proposition {
+    variant: Ident,
+    /// its name in formulae
+    name: String,
+    /// whether it holds on `domain`
+    holds: TokenStream,
+}
+
+/// A field of the domain, with the arguments of its `#[ap(..)]` attribute.
+struct Field {
+    name: Ident,
+    ty: Vec<TokenTree>,
+    arguments: Vec<TokenTree>,
+}
+
+/// The enumeration of the propositions over the struct `input`, with its
+/// implementations.
+pub(crate) fn expand(input: TokenStream) -> ExpansionResult {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let (arguments, mut position) = attributes(&tokens, 0)?;
+    let visibility_end = skip_visibility(&tokens, position);
+    let visibility: TokenStream = tokens[position..visibility_end].iter().cloned().collect();
+    position = visibility_end;
+    let not_a_struct = |span| {
+        Err((
+            span,
+            "`AtomicPropositions` can only be derived for structs with named fields".to_string(),
+        ))
+    };
+    match tokens.get(position) {
+        Some(TokenTree::Ident(ident)) if ident == "struct" => position += 1,
+        token => return not_a_struct(token.map_or(Span::call_site(), TokenTree::span)),
+    }
+    let Some(TokenTree::Ident(domain)) = tokens.get(position) else {
+        return not_a_struct(Span::call_site());
+    };
+    let fields = match tokens.get(position + 1) {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => fields(group)?,
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '<' => {
+            return Err((
+                punct.span(),
+                "generic domains are not supported".to_string(),
+            ));
+        }
+        token => return not_a_struct(token.map_or(domain.span(), TokenTree::span)),
+    };
+    let enum_name = enum_name(domain, &arguments)?;
+    let propositions = propositions(&fields)?;
+    if propositions.is_empty() {
+        return Err((
+            domain.span(),
+            "no proposition : the domain has no `bool` field and no field with \
+             `#[ap(values(..))]`"
+                .to_string(),
+        ));
+    }
+    Ok(generate(&visibility, domain, &enum_name, &propositions))
+}
+
+/// the arguments of the `#[ap(..)]` attributes from `position`, and the
+/// position after the attributes
+fn attributes(
+    tokens: &[TokenTree],
+    mut position: usize,
+) -> ExpansionResult<(Vec<TokenTree>, usize)> {
+    let mut arguments = Vec::new();
+    while let (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group))) =
+        (tokens.get(position), tokens.get(position + 1))
+        && punct.as_char() == '#'
+        && group.delimiter() == Delimiter::Bracket
+    {
+        let attribute: Vec<TokenTree> = group.stream().into_iter().collect();
+        if let [TokenTree::Ident(ident), TokenTree::Group(inner)] = attribute.as_slice()
+            && ident == "ap"
+        {
+            if inner.delimiter() != Delimiter::Parenthesis {
+                return Err((inner.span(), "expected `ap(..)`".to_string()));
+            }
+            if !arguments.is_empty() {
+                arguments.push(TokenTree::Punct(proc_macro2::Punct::new(
+                    ',',
+                    proc_macro2::Spacing::Alone,
+                )));
+            }
+            arguments.extend(inner.stream());
+        }
+        position += 2;
+    }
+    Ok((arguments, position))
+}
+
+/// the position after the visibility at `position`, if any
+fn skip_visibility(tokens: &[TokenTree], position: usize) -> usize {
+    match (tokens.get(position), tokens.get(position + 1)) {
+        (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group)))
+            if ident == "pub" && group.delimiter() == Delimiter::Parenthesis =>
+        {
+            position + 2
+        }
+        (Some(TokenTree::Ident(ident)), _) if ident == "pub" => position + 1,
+        _ => position,
+    }
+}
+
+/// `arguments` split at their commas
+fn split_arguments(arguments: &[TokenTree]) -> Vec<&[TokenTree]> {
+    arguments
+        .split(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+        .filter(|argument| !argument.is_empty())
+        .collect()
+}
+
+/// the name of the enumeration : given as `#[ap(name = ..)]` on the
+/// struct, `DomainAp` by default
+fn enum_name(domain: &Ident, arguments: &[TokenTree]) -> ExpansionResult<Ident> {
+    let mut name = format_ident!("{}Ap", domain);
+    for argument in split_arguments(arguments) {
+        match argument {
+            [
+                TokenTree::Ident(key),
+                TokenTree::Punct(equal),
+                TokenTree::Ident(value),
+            ] if key == "name" && equal.as_char() == '=' => {
+                name = value.clone();
+            }
+            _ => {
+                return Err((
+                    argument[0].span(),
+                    "expected `name = ..` on the struct".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(name)
+}
+
+/// the named fields of the struct body `group`
+fn fields(group: &Group) -> ExpansionResult<Vec<Field>> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    let mut fields = Vec::new();
+    for declaration in split_fields(&tokens) {
+        let (arguments, position) = attributes(declaration, 0)?;
+        let position = skip_visibility(declaration, position);
+        match &declaration[position..] {
+            [TokenTree::Ident(name), TokenTree::Punct(colon), ty @ ..]
+                if colon.as_char() == ':' && !ty.is_empty() =>
+            {
+                fields.push(Field {
+                    name: name.clone(),
+                    ty: ty.to_vec(),
+                    arguments,
+                });
+            }
+            _ => return Err((group.span(), "expected a named field".to_string())),
+        }
+    }
+    Ok(fields)
+}
+
+/// the field declarations in `tokens`, split at the commas which are not
+/// within the generic arguments of a type
+fn split_fields(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut declarations = Vec::new();
+    let mut start = 0;
+    let mut angle_depth = 0usize;
+    for (index, token) in tokens.iter().enumerate() {
+        let TokenTree::Punct(punct) = token else {
+            continue;
+        };
+        // the `>` of `->` closes no generic arguments
+        let after_dash = index > 0
+            && matches!(&tokens[index - 1], TokenTree::Punct(dash) if dash.as_char() == '-');
+        match punct.as_char() {
+            '<' => angle_depth += 1,
+            '>' if !after_dash => angle_depth = angle_depth.saturating_sub(1),
+            ',' if angle_depth == 0 => {
+                declarations.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    declarations.push(&tokens[start..]);
+    declarations.retain(|declaration| !declaration.is_empty());
+    declarations
+}
+
+/// the propositions over `fields`, in their order
+fn propositions(fields: &[Field]) -> ExpansionResult<Vec<Proposition>> {
+    let mut propositions: Vec<Proposition> = Vec::new();
+    for field in fields {
+        let name = &field.name;
+        let field_name = name.to_string();
+        let field_name = field_name.trim_start_matches("r#");
+        let is_bool = matches!(field.ty.as_slice(), [TokenTree::Ident(ty)] if ty == "bool");
+        let mut values = None;
+        for argument in split_arguments(&field.arguments) {
+            match argument {
+                [TokenTree::Ident(key)] if key == "skip" => values = Some(Vec::new()),
+                [TokenTree::Ident(key), TokenTree::Group(group)]
+                    if key == "values" && group.delimiter() == Delimiter::Parenthesis =>
+                {
+                    if is_bool {
+                        return Err((
+                            key.span(),
+                            "`values` applies to enumeration fields".to_string(),
+                        ));
+                    }
+                    let variants: Vec<TokenTree> = group.stream().into_iter().collect();
+                    let mut idents = Vec::new();
+                    for variant in split_arguments(&variants) {
+                        match variant {
+                            [TokenTree::Ident(ident)] => idents.push(ident.clone()),
+                            _ => {
+                                return Err((
+                                    variant[0].span(),
+                                    "expected the name of a unit variant".to_string(),
+                                ));
+                            }
+                        }
+                    }
+                    values = Some(idents);
+                }
+                _ => {
+                    return Err((
+                        argument[0].span(),
+                        "expected `values(..)` or `skip` on a field".to_string(),
+                    ));
+                }
+            }
+        }
+        let new_propositions = match values {
+            None if is_bool => vec![Proposition {
+                variant: Ident::new(&upper_camel_case(field_name), name.span()),
+                name: field_name.to_string(),
+                holds: quote!(domain.#name),
+            }],
+            None => Vec::new(),
+            Some(variants) => {
+                let ty: TokenStream = field.ty.iter().cloned().collect();
+                variants
+                    .into_iter()
+                    .map(|variant| Proposition {
+                        variant: Ident::new(
+                            &format!("{}{}", upper_camel_case(field_name), variant),
+                            variant.span(),
+                        ),
+                        name: format!("{}.{}", field_name, variant),
+                        holds: quote!(::core::matches!(domain.#name, #ty::#variant)),
+                    })
+                    .collect()
+            }
+        };
+        for proposition in new_propositions {
+            if propositions
+                .iter()
+                .any(|other| other.variant == proposition.variant)
+            {
+                return Err((
+                    proposition.variant.span(),
+                    format!("two propositions would be named `{}`", proposition.variant),
+                ));
+            }
+            propositions.push(proposition);
+        }
+    }
+    Ok(propositions)
+}
+
+/// `snake_case` as `SnakeCase`
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn generate(
+    visibility: &TokenStream,
+    domain: &Ident,
+    enum_name: &Ident,
+    propositions: &[Proposition],
+) -> TokenStream {
+    let variants: Vec<&Ident> = propositions.iter().map(|prop| &prop.variant).collect();
+    let names: Vec<&str> = propositions.iter().map(|prop| prop.name.as_str()).collect();
+    let holds = propositions.iter().map(|prop| &prop.holds);
+    let count = propositions.len();
+    let enum_doc = format!("The atomic propositions over [`{}`].", domain);
+    quote! {
+        #[doc = #enum_doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #visibility enum #enum_name {
+            #(#variants),*
+        }
+
+        impl #enum_name {
+            /// All the propositions, in the order of the fields.
+            pub const ALL: [#enum_name; #count] = [#(#enum_name::#variants),*];
+
+            /// The name of the proposition in formulae.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(#enum_name::#variants => #names),*
+                }
+            }
+
+            /// The proposition named `name` in formulae, if any.
+            pub fn from_name(name: &str) -> ::core::option::Option<Self> {
+                match name {
+                    #(#names => ::core::option::Option::Some(#enum_name::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            /// A parser of CTL formulae over the propositions, by name.
+            pub fn parser() -> ::citreelo::parser::identifiers::IdentifierParser<
+                fn(&str) -> ::core::option::Option<#enum_name>,
+            > {
+                ::citreelo::parser::identifiers::IdentifierParser::new(#enum_name::from_name)
+            }
+        }
+
+        impl ::core::fmt::Display for #enum_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl ::citreelo::kripke::AtomicProposition<#domain> for #enum_name {
+            fn is_satisfied_on_state_domain(&self, domain: &#domain) -> bool {
+                match self {
+                    #(#enum_name::#variants => #holds),*
+                }
+            }
+        }
+    }
+}
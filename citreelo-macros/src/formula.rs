@@ -0,0 +1,307 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The parser of the [ctl!](crate::ctl!) macro : a recursive descent over
+//! the token trees of the formula, producing the expression building it.
+
+use proc_macro2::{Delimiter, Group, Spacing, Span, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::ExpansionResult;
+
+/// The expression building the formula written as `input`.
+pub(crate) fn expand(input: TokenStream) -> ExpansionResult {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    Parser::new(tokens, Span::call_site()).parse_whole()
+}
+
+const PREFIX_OPERATORS: [&str; 6] = ["AX", "EX", "AF", "EF", "AG", "EG"];
+
+/// A recursive-descent parser over the token trees of a formula, or of a
+/// parenthesized sub-formula.
+struct Parser {
+    tokens: Vec<TokenTree>,
+    position: usize,
+    /// where the end of the tokens is reported
+    end: Span,
+}
+
+impl Parser {
+    fn new(tokens: Vec<TokenTree>, end: Span) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            end,
+        }
+    }
+
+    fn of_group(group: &Group) -> Self {
+        Parser::new(group.stream().into_iter().collect(), group.span_close())
+    }
+
+    fn peek(&self) -> Option<&TokenTree> {
+        self.tokens.get(self.position)
+    }
+
+    fn span(&self) -> Span {
+        self.peek().map_or(self.end, TokenTree::span)
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("`{}`", token),
+            None => "the end of the formula".to_string(),
+        }
+    }
+
+    fn error<T>(&self, expected: &str) -> ExpansionResult<T> {
+        Err((
+            self.span(),
+            format!("expected {}, found {}", expected, self.found()),
+        ))
+    }
+
+    fn is_ident(&self, offset: usize, name: &str) -> bool {
+        matches!(self.tokens.get(self.position + offset), Some(TokenTree::Ident(ident)) if ident == name)
+    }
+
+    /// whether the punctuation `symbol` is next, its characters being
+    /// joint
+    fn is_punct(&self, symbol: &str) -> bool {
+        let count = symbol.chars().count();
+        symbol
+            .chars()
+            .enumerate()
+            .all(|(index, c)| match self.tokens.get(self.position + index) {
+                Some(TokenTree::Punct(punct)) => {
+                    punct.as_char() == c
+                        && (index + 1 == count || punct.spacing() == Spacing::Joint)
+                }
+                _ => false,
+            })
+    }
+
+    fn eat_punct(&mut self, symbol: &str) -> bool {
+        let is_next = self.is_punct(symbol);
+        if is_next {
+            self.position += symbol.chars().count();
+        }
+        is_next
+    }
+
+    /// the connective `symbol`, which Rust spells twice (`&&`, `||`)
+    fn eat_connective(&mut self, symbol: char) -> ExpansionResult<bool> {
+        let doubled = format!("{}{}", symbol, symbol);
+        if self.is_punct(&doubled) {
+            return Err((
+                self.span(),
+                format!("`{}` is not a CTL connective : use `{}`", doubled, symbol),
+            ));
+        }
+        Ok(self.eat_punct(&symbol.to_string()))
+    }
+
+    fn parse_whole(&mut self) -> ExpansionResult {
+        let formula = self.parse_iff()?;
+        if self.peek().is_some() {
+            return self.error("an operator or the end of the formula");
+        }
+        Ok(formula)
+    }
+
+    fn parse_iff(&mut self) -> ExpansionResult {
+        let mut left = self.parse_imply()?;
+        while self.eat_punct("<=>") {
+            let right = self.parse_imply()?;
+            left = binary(quote!(Iff), left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_imply(&mut self) -> ExpansionResult {
+        let left = self.parse_or()?;
+        if self.eat_punct("=>") {
+            let right = self.parse_imply()?;
+            return Ok(binary(quote!(Imply), left, right));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> ExpansionResult {
+        let mut left = self.parse_and()?;
+        while self.eat_connective('|')? {
+            let right = self.parse_and()?;
+            left = binary(quote!(Or), left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ExpansionResult {
+        let mut left = self.parse_unary()?;
+        while self.eat_connective('&')? {
+            let right = self.parse_unary()?;
+            left = binary(quote!(And), left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> ExpansionResult {
+        if self.is_punct("!=") {
+            return self.error("a formula");
+        }
+        if self.eat_punct("!") {
+            let sub = self.parse_unary()?;
+            return Ok(unary(quote!(Not), sub));
+        }
+        if let Some(TokenTree::Ident(ident)) = self.peek()
+            && let Some(operator) = PREFIX_OPERATORS.iter().find(|op| ident == *op)
+        {
+            let operator = proc_macro2::Ident::new(operator, ident.span());
+            self.position += 1;
+            let sub = self.parse_unary()?;
+            return Ok(unary(quote!(#operator), sub));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> ExpansionResult {
+        let Some(token) = self.peek().cloned() else {
+            return self.error("a formula");
+        };
+        match &token {
+            TokenTree::Ident(ident) if ident == "true" || ident == "false" => {
+                self.position += 1;
+                let leaf = if ident == "true" {
+                    quote!(True)
+                } else {
+                    quote!(False)
+                };
+                Ok(quote!(::citreelo::ctl::CTLFormula::Leaf(
+                    ::citreelo::ctl::CTLFormulaLeaf::#leaf
+                )))
+            }
+            TokenTree::Ident(ident) if ident == "A" || ident == "E" => {
+                match self.tokens.get(self.position + 1) {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                        self.position += 2;
+                        let operator = if ident == "A" { quote!(AU) } else { quote!(EU) };
+                        parse_until(operator, group)
+                    }
+                    _ => self.parse_atom(),
+                }
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                self.position += 1;
+                Parser::of_group(group).parse_whole()
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                self.position += 1;
+                // a single expression is passed without its braces, which
+                // would be linted as unused
+                let is_block = group.stream().into_iter().any(
+                    |token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ';'),
+                );
+                if is_block {
+                    Ok(atom(quote!(#group)))
+                } else {
+                    Ok(atom(group.stream()))
+                }
+            }
+            TokenTree::Literal(literal) => {
+                self.position += 1;
+                Ok(atom(quote!(#literal)))
+            }
+            TokenTree::Ident(_) => self.parse_atom(),
+            TokenTree::Punct(_) if self.is_punct("::") => self.parse_atom(),
+            _ => self.error(
+                "an atomic proposition, a boolean constant, a negation, \
+                 a temporal operator or `(`",
+            ),
+        }
+    }
+
+    /// a path, and its calls, method calls, field accesses and indexing
+    fn parse_atom(&mut self) -> ExpansionResult {
+        let start = self.position;
+        self.eat_punct("::");
+        loop {
+            if !matches!(self.peek(), Some(TokenTree::Ident(_))) {
+                return self.error("a path");
+            }
+            self.position += 1;
+            if !self.eat_punct("::") {
+                break;
+            }
+        }
+        loop {
+            match self.peek() {
+                Some(TokenTree::Group(group))
+                    if matches!(
+                        group.delimiter(),
+                        Delimiter::Parenthesis | Delimiter::Bracket
+                    ) =>
+                {
+                    self.position += 1;
+                }
+                Some(TokenTree::Punct(punct))
+                    if punct.as_char() == '.'
+                        && matches!(
+                            self.tokens.get(self.position + 1),
+                            Some(TokenTree::Ident(_)) | Some(TokenTree::Literal(_))
+                        ) =>
+                {
+                    self.position += 2;
+                }
+                _ => break,
+            }
+        }
+        let expression: TokenStream = self.tokens[start..self.position].iter().cloned().collect();
+        Ok(atom(expression))
+    }
+}
+
+/// the `φ U ψ` between the brackets of an until
+fn parse_until(operator: TokenStream, group: &Group) -> ExpansionResult {
+    let mut parser = Parser::of_group(group);
+    let left = parser.parse_iff()?;
+    if !parser.is_ident(0, "U") {
+        return parser.error("`U`");
+    }
+    parser.position += 1;
+    let right = parser.parse_whole()?;
+    Ok(binary(operator, left, right))
+}
+
+fn atom(expression: TokenStream) -> TokenStream {
+    quote!(::citreelo::ctl::CTLFormula::Leaf(
+        ::citreelo::ctl::CTLFormulaLeaf::AtomicProp(#expression)
+    ))
+}
+
+fn unary(operator: TokenStream, sub: TokenStream) -> TokenStream {
+    quote!(::citreelo::ctl::CTLFormula::Unary(
+        ::citreelo::ctl::UnaryCTLOperator::#operator,
+        ::std::boxed::Box::new(#sub)
+    ))
+}
+
+fn binary(operator: TokenStream, left: TokenStream, right: TokenStream) -> TokenStream {
+    quote!(::citreelo::ctl::CTLFormula::Binary(
+        ::citreelo::ctl::BinaryCTLOperator::#operator,
+        ::std::boxed::Box::new(#left),
+        ::std::boxed::Box::new(#right)
+    ))
+}
@@ -0,0 +1,107 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Procedural macros for `citreelo` :
+//! - [ctl!], which builds a `citreelo::ctl::CTLFormula` at compile time,
+//!   with the grammar of the parser of `citreelo` (see the documentation
+//!   of `citreelo::parser`) in its default syntax, and Rust expressions as
+//!   atomic propositions :
+//!
+//! ```ignore
+//! use citreelo_macros::ctl;
+//!
+//! let formula = ctl!(AG (Ap::Req => AF Ap::Grant) & A[!Ap::Err U Ap::Done]);
+//! ```
+//!
+//! - [AtomicPropositions], which derives the atomic propositions over a
+//!   domain struct from its boolean and enumeration fields.
+//!
+//! Errors are reported by the compiler, on the faulty token.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote_spanned;
+
+mod derive;
+mod formula;
+
+/// The result of an expansion, or an error message and where to report it.
+type ExpansionResult<T = TokenStream2> = Result<T, (Span, String)>;
+
+fn emit(expansion: ExpansionResult) -> TokenStream {
+    match expansion {
+        Ok(tokens) => tokens.into(),
+        Err((span, message)) => quote_spanned!(span=> ::core::compile_error!(#message)).into(),
+    }
+}
+
+/// Builds a `CTLFormula` from a formula written in the concrete syntax
+/// of `citreelo`, with precedences from weakest to strongest binding :
+///
+/// | level | operators                                             |
+/// |-------|-------------------------------------------------------|
+/// | 1     | `<=>` (left associative)                              |
+/// | 2     | `=>` (right associative)                              |
+/// | 3     | `\|` (left associative)                               |
+/// | 4     | `&` (left associative)                                |
+/// | 5     | `!`, `AX`, `EX`, `AF`, `EF`, `AG`, `EG`               |
+/// | 6     | atoms, `true`, `false`, `(φ)`, `A[φ U ψ]`, `E[φ U ψ]` |
+///
+/// An atomic proposition is either :
+/// - a path, as `p` or `Ap::Req`, possibly followed by calls, method
+///   calls, field accesses and indexing, as `Ap::Counter(3)` or
+///   `props[0].clone()`
+/// - a literal, as `"req"` or `3`
+/// - any Rust expression between braces, as `{ x & mask }`.
+///
+/// Keywords shadow the paths of the same name, which may be written
+/// between braces : `{ AX }`.
+#[proc_macro]
+pub fn ctl(input: TokenStream) -> TokenStream {
+    emit(formula::expand(input.into()))
+}
+
+/// Derives, for a struct with named fields, the enumeration of the atomic
+/// propositions over it (named `DomainAp` for a struct `Domain`, or as
+/// given by `#[ap(name = ..)]` on the struct), with :
+/// - one variant per `bool` field, holding when the field is `true`, as
+///   `Busy` for `busy`, named `busy` in formulae
+/// - one variant per value listed by `#[ap(values(..))]` on a field of an
+///   enumeration type, holding when the field has this value, as
+///   `ModeIdle` for `Idle` in `mode`, named `mode.Idle` in formulae.
+///
+/// The other fields, and those marked `#[ap(skip)]`, make no proposition.
+/// The enumeration implements `AtomicProposition` over the struct and
+/// `Display`, and provides `ALL`, `name`, `from_name` and `parser`, an
+/// `IdentifierParser` reading the propositions by name :
+///
+/// ```ignore
+/// #[derive(AtomicPropositions)]
+/// struct Proc {
+///     busy: bool,
+///     #[ap(values(Idle, Waiting, Critical))]
+///     mode: Mode,
+///     pending: u32,
+/// }
+///
+/// let formula = ProcAp::parser()
+///     .parse_complete_ctl_formula("AG (mode.Waiting => AF mode.Critical)")
+///     .unwrap();
+/// ```
+#[proc_macro_derive(AtomicPropositions, attributes(ap))]
+pub fn derive_atomic_propositions(input: TokenStream) -> TokenStream {
+    emit(derive::expand(input.into()))
+}
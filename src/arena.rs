@@ -0,0 +1,337 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Hash-consed CTL formulae.
+//!
+//! A [CTLFormulaArena](crate::arena::CTLFormulaArena) stores formulae as
+//! a DAG of nodes in which every structurally distinct sub-formula is
+//! stored exactly once and designated by a
+//! [FormulaId](crate::arena::FormulaId). Since the operands of a node are
+//! ids, hashing and comparing a node takes constant time (up to the
+//! atomic propositions), whereas hashing a
+//! [CTLFormula](crate::ctl::CTLFormula) tree walks the whole subtree.
+//!
+//! Ids are allocated in creation order, and the operands of a node are
+//! always created before it : iterating over ids in increasing order
+//! visits every sub-formula before the formulae containing it.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::ctl::*;
+
+/// A handle on a formula stored in a [CTLFormulaArena].
+///
+/// Ids are only meaningful for the arena that produced them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct FormulaId(usize);
+
+impl FormulaId {
+    /// The index of this id in its arena (ids are allocated from 0).
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A node of a [CTLFormulaArena] : a [CTLFormula] whose operands are
+/// ids instead of subtrees.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum CTLFormulaNode<AP> {
+    Leaf(CTLFormulaLeaf<AP>),
+    // ***
+    Unary(UnaryCTLOperator, FormulaId),
+    Binary(BinaryCTLOperator, FormulaId, FormulaId),
+}
+
+impl<AP> CTLFormulaNode<AP> {
+    /// The ids of the direct operands of this node, from left to right.
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = FormulaId> {
+        let (first, second) = match self {
+            CTLFormulaNode::Leaf(_) => (None, None),
+            CTLFormulaNode::Unary(_, id1) => (Some(*id1), None),
+            CTLFormulaNode::Binary(_, id1, id2) => (Some(*id1), Some(*id2)),
+        };
+        first.into_iter().chain(second)
+    }
+
+    /// This node with its operands replaced by their images by `f`, or
+    /// `None` if `f` gives `None` for one of them.
+    pub(crate) fn map_children(
+        &self,
+        mut f: impl FnMut(FormulaId) -> Option<FormulaId>,
+    ) -> Option<CTLFormulaNode<AP>>
+    where
+        AP: Clone,
+    {
+        Some(match self {
+            CTLFormulaNode::Leaf(leaf) => CTLFormulaNode::Leaf(leaf.clone()),
+            CTLFormulaNode::Unary(op, id1) => CTLFormulaNode::Unary(op.clone(), f(*id1)?),
+            CTLFormulaNode::Binary(op, id1, id2) => {
+                CTLFormulaNode::Binary(op.clone(), f(*id1)?, f(*id2)?)
+            }
+        })
+    }
+}
+
+/// An arena of hash-consed formulae : structurally equal formulae
+/// interned in the same arena get the same [FormulaId].
+#[derive(Debug, Clone)]
+pub struct CTLFormulaArena<AP> {
+    nodes: Vec<CTLFormulaNode<AP>>,
+    ids: HashMap<CTLFormulaNode<AP>, FormulaId>,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> Default for CTLFormulaArena<AP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> CTLFormulaArena<AP> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// The number of distinct formulae stored in the arena.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The node designated by `id`.
+    ///
+    /// Panics if `id` was not produced by this arena.
+    pub fn node(&self, id: FormulaId) -> &CTLFormulaNode<AP> {
+        &self.nodes[id.0]
+    }
+
+    /// The ids of all the formulae stored in the arena, in creation
+    /// order (operands before the formulae containing them).
+    pub fn ids(&self) -> impl DoubleEndedIterator<Item = FormulaId> + use<AP> {
+        (0..self.nodes.len()).map(FormulaId)
+    }
+
+    /// The id of `node`, adding it to the arena if it is not there yet.
+    ///
+    /// Panics if an operand of `node` was not produced by this arena.
+    pub fn mk(&mut self, node: CTLFormulaNode<AP>) -> FormulaId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        assert!(
+            node.children().all(|child| child.0 < self.nodes.len()),
+            "operand id out of the arena"
+        );
+        let id = FormulaId(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    pub fn mk_leaf(&mut self, leaf: CTLFormulaLeaf<AP>) -> FormulaId {
+        self.mk(CTLFormulaNode::Leaf(leaf))
+    }
+
+    pub fn mk_unary(&mut self, op: UnaryCTLOperator, sub: FormulaId) -> FormulaId {
+        self.mk(CTLFormulaNode::Unary(op, sub))
+    }
+
+    pub fn mk_binary(
+        &mut self,
+        op: BinaryCTLOperator,
+        left: FormulaId,
+        right: FormulaId,
+    ) -> FormulaId {
+        self.mk(CTLFormulaNode::Binary(op, left, right))
+    }
+
+    /// The id of `formula`, interning all its sub-formulae.
+    pub fn intern(&mut self, formula: &CTLFormula<AP>) -> FormulaId {
+        formula.fold(&mut Interner { arena: self })
+    }
+
+    /// The id in this arena of the formula designated by `root` in
+    /// `other`, interning all its sub-formulae without unfolding the
+    /// shared ones.
+    ///
+    /// Panics if `root` was not produced by `other`.
+    pub fn import(&mut self, other: &CTLFormulaArena<AP>, root: FormulaId) -> FormulaId {
+        let mut imported = vec![None; root.0 + 1];
+        for id in other.reachable_from(root) {
+            let node = other
+                .node(id)
+                .map_children(|child| imported[child.0])
+                .expect("operands are imported first");
+            imported[id.0] = Some(self.mk(node));
+        }
+        imported[root.0].expect("the root is reachable from itself")
+    }
+
+    /// The id of `formula` if it (and hence all its sub-formulae) is
+    /// already stored in the arena.
+    pub fn get(&self, formula: &CTLFormula<AP>) -> Option<FormulaId> {
+        formula.fold(&mut Lookup { arena: self })
+    }
+
+    /// The id of `node` if it is already stored in the arena.
+    pub fn id_of(&self, node: &CTLFormulaNode<AP>) -> Option<FormulaId> {
+        self.ids.get(node).copied()
+    }
+
+    /// The ids of the sub-formulae of the formula designated by `root`
+    /// (itself included), in increasing order.
+    pub fn reachable_from(&self, root: FormulaId) -> Vec<FormulaId> {
+        let mut reachable = vec![false; root.0 + 1];
+        reachable[root.0] = true;
+        // operands have smaller ids : a single downward sweep suffices
+        for index in (0..=root.0).rev() {
+            if reachable[index] {
+                for child in self.nodes[index].children() {
+                    reachable[child.0] = true;
+                }
+            }
+        }
+        (0..=root.0)
+            .filter(|index| reachable[*index])
+            .map(FormulaId)
+            .collect()
+    }
+
+    /// The formula designated by `id`, as a tree.
+    ///
+    /// Shared sub-formulae are duplicated, so that the tree may be
+    /// exponentially larger than the DAG below `id`.
+    pub fn to_formula(&self, id: FormulaId) -> CTLFormula<AP> {
+        // (node, whether its operands have already been built)
+        let mut to_visit = vec![(id, false)];
+        let mut built: Vec<CTLFormula<AP>> = Vec::new();
+        while let Some((id, operands_built)) = to_visit.pop() {
+            match &self.nodes[id.0] {
+                CTLFormulaNode::Leaf(leaf) => built.push(CTLFormula::Leaf(leaf.clone())),
+                CTLFormulaNode::Unary(op, id1) => {
+                    if operands_built {
+                        let sub = built.pop().unwrap();
+                        built.push(CTLFormula::Unary(op.clone(), Box::new(sub)));
+                    } else {
+                        to_visit.push((id, true));
+                        to_visit.push((*id1, false));
+                    }
+                }
+                CTLFormulaNode::Binary(op, id1, id2) => {
+                    if operands_built {
+                        let right = built.pop().unwrap();
+                        let left = built.pop().unwrap();
+                        built.push(CTLFormula::Binary(
+                            op.clone(),
+                            Box::new(left),
+                            Box::new(right),
+                        ));
+                    } else {
+                        to_visit.push((id, true));
+                        to_visit.push((*id2, false));
+                        to_visit.push((*id1, false));
+                    }
+                }
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+/// Building formulae directly in an arena, e.g. from the parser (see
+/// [CtlFormulaParser::parse_complete_ctl_formula_with](crate::parser::CtlFormulaParser::parse_complete_ctl_formula_with)).
+impl<AP: Clone + PartialEq + Eq + Hash> CTLFormulaBuilder<AP> for CTLFormulaArena<AP> {
+    type Formula = FormulaId;
+
+    fn build_leaf(&mut self, leaf: CTLFormulaLeaf<AP>) -> FormulaId {
+        self.mk_leaf(leaf)
+    }
+
+    fn build_unary(&mut self, op: UnaryCTLOperator, sub: FormulaId) -> FormulaId {
+        self.mk_unary(op, sub)
+    }
+
+    fn build_binary(
+        &mut self,
+        op: BinaryCTLOperator,
+        left: FormulaId,
+        right: FormulaId,
+    ) -> FormulaId {
+        self.mk_binary(op, left, right)
+    }
+
+    fn build_formula(&mut self, formula: CTLFormula<AP>) -> FormulaId {
+        self.intern(&formula)
+    }
+}
+
+struct Interner<'a, AP> {
+    arena: &'a mut CTLFormulaArena<AP>,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> CTLFormulaFolder<AP> for Interner<'_, AP> {
+    type Output = FormulaId;
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> FormulaId {
+        self.arena.mk_leaf(leaf.clone())
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: FormulaId) -> FormulaId {
+        self.arena.mk_unary(op.clone(), sub)
+    }
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: FormulaId,
+        right: FormulaId,
+    ) -> FormulaId {
+        self.arena.mk_binary(op.clone(), left, right)
+    }
+}
+
+struct Lookup<'a, AP> {
+    arena: &'a CTLFormulaArena<AP>,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> CTLFormulaFolder<AP> for Lookup<'_, AP> {
+    /// `None` once a sub-formula is missing from the arena
+    type Output = Option<FormulaId>;
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> Option<FormulaId> {
+        self.arena.id_of(&CTLFormulaNode::Leaf(leaf.clone()))
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: Option<FormulaId>) -> Option<FormulaId> {
+        self.arena.id_of(&CTLFormulaNode::Unary(op.clone(), sub?))
+    }
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: Option<FormulaId>,
+        right: Option<FormulaId>,
+    ) -> Option<FormulaId> {
+        self.arena
+            .id_of(&CTLFormulaNode::Binary(op.clone(), left?, right?))
+    }
+}
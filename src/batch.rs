@@ -0,0 +1,144 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Checking many formulae at once.
+//!
+//! [CtlModelChecker::check_all](crate::solve::CtlModelChecker::check_all)
+//! interns a whole batch of formulae in a single arena, so that the
+//! sub-formulae they share are computed once, and evaluates the atomic
+//! propositions of the whole batch in a single sweep over the states,
+//! instead of one sweep per formula.
+//!
+//! [CtlModelChecker::check_all_in_parallel](crate::solve::CtlModelChecker::check_all_in_parallel)
+//! does the same on several threads, between which it splits the atoms,
+//! then the independent sub-formulae (e.g. the operands of a
+//! conjunction, or the formulae of the batch), and finally the
+//! extraction of the satisfaction sets.
+//!
+//! Both reuse and fill the cache of the checker.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use biodivine_lib_bdd::Bdd;
+
+use crate::arena::FormulaId;
+use crate::ctl::CTLFormula;
+use crate::kripke::AtomicProposition;
+use crate::solve::{CheckerCore, CtlModelChecker, CtlModelCheckingError, in_parallel};
+
+/// The verdict of a formula of a batch.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FormulaVerdict {
+    /// the ids of the states satisfying the formula
+    pub sat_set: HashSet<usize>,
+    /// whether the formula holds on every initial state
+    pub holds: bool,
+    /// the smallest initial state on which the formula fails, if any
+    pub failing_state: Option<usize>,
+}
+
+impl<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>
+    CtlModelChecker<'_, DOAP, AP>
+{
+    /// Checks every formula of `formulas` from `initial_states`, in one
+    /// pass over the batch, and returns their verdicts in input order.
+    ///
+    /// Fails with [CtlModelCheckingError::OutOfRangeInitialState] as
+    /// [CtlModelChecker::is_ctl_formula_sat] does.
+    pub fn check_all(
+        &self,
+        initial_states: &HashSet<usize>,
+        formulas: &[CTLFormula<AP>],
+    ) -> Result<Vec<FormulaVerdict>, CtlModelCheckingError> {
+        let core = self.core();
+        core.check_initial_states(initial_states)?;
+        let verdicts = self.with_sat_sets(
+            |arena| {
+                formulas
+                    .iter()
+                    .map(|formula| arena.intern(formula))
+                    .collect()
+            },
+            false,
+            |_, roots, sub_formulae_memoizer| {
+                core.get_verdicts(initial_states, roots, sub_formulae_memoizer)
+            },
+        );
+        Ok(verdicts)
+    }
+
+    /// As [CtlModelChecker::check_all], on `num_threads` threads (at
+    /// least one), with the same results.
+    pub fn check_all_in_parallel(
+        &self,
+        initial_states: &HashSet<usize>,
+        formulas: &[CTLFormula<AP>],
+        num_threads: usize,
+    ) -> Result<Vec<FormulaVerdict>, CtlModelCheckingError>
+    where
+        AP: Sync,
+        DOAP: Sync,
+    {
+        let core = self.core();
+        core.check_initial_states(initial_states)?;
+        let verdicts = self.with_sat_sets_in_parallel(
+            |arena| {
+                formulas
+                    .iter()
+                    .map(|formula| arena.intern(formula))
+                    .collect()
+            },
+            num_threads,
+            |_, roots, sub_formulae_memoizer| {
+                let sub_formulae_memoizer = &*sub_formulae_memoizer;
+                in_parallel(roots, num_threads, |chunk| {
+                    core.get_verdicts(initial_states, chunk, sub_formulae_memoizer)
+                })
+            },
+        );
+        Ok(verdicts)
+    }
+}
+
+impl<DOAP> CheckerCore<'_, DOAP> {
+    fn get_verdicts(
+        &self,
+        initial_states: &HashSet<usize>,
+        roots: &[FormulaId],
+        sub_formulae_memoizer: &[Option<Bdd>],
+    ) -> Vec<FormulaVerdict> {
+        roots
+            .iter()
+            .map(|root| {
+                let sat_set_bdd = sub_formulae_memoizer[root.index()]
+                    .as_ref()
+                    .expect("the roots are reachable from themselves");
+                let sat_set = self.bdd_to_states(sat_set_bdd);
+                let failing_state = initial_states
+                    .iter()
+                    .filter(|state| !sat_set.contains(state))
+                    .min()
+                    .copied();
+                FormulaVerdict {
+                    sat_set,
+                    holds: failing_state.is_none(),
+                    failing_state,
+                }
+            })
+            .collect()
+    }
+}
@@ -57,7 +57,9 @@ use crate::kripke::KripkeStructure;
 ///     the value of its argument on unused valuations, the weak preimage
 ///     never produces one, and the strong preimage includes all of them
 ///     (vacuously : an unused valuation has no outgoing transition),
-///     which the next point makes harmless ;
+///     which the next point makes harmless ; likewise, the post-image
+///     (see [KripkeStructureBddRepresentation::get_post_image_by_transition_relation])
+///     never produces an unused valuation ;
 ///   - the only places where BDDs are read back as sets of states
 ///     (extracting a satisfaction set state by state, or checking that a
 ///     set of initial states entails a satisfaction set) query
@@ -78,6 +80,32 @@ use crate::kripke::KripkeStructure;
 /// transition relation at the creation of the
 /// KripkeStructureBddRepresentation object.
 ///
+/// # Partitioned transition relation
+///
+/// For large structures, the single BDD of the transition relation may
+/// dominate memory and the time of every image computation. With
+/// [TransitionPartitioning::SourceBlocks], it is instead stored
+/// disjunctively, as one part per block of consecutive source states :
+///
+/// T = ∨_b T_b where T_b = ∨_{si -> sj, si ∈ block b} ( enc_C(i) ∧ enc_N(j) )
+///
+/// As existential quantification distributes over disjunction, the
+/// images are computed part by part, each product being quantified as
+/// soon as it is built (early quantification), and the full relation is
+/// never built :
+///   - EX(S) = ∨_b ∃s′⋅ T_b(s,s′) ∧ S(s′), skipping the parts none of
+///     whose targets is in S ;
+///   - AX(S) = ¬EX(¬S), which gives the same BDD as the monolithic
+///     computation, unused valuations included ;
+///   - Post(S) = ∨_b ∃s⋅ S(s) ∧ T_b(s,s′), skipping the parts none of
+///     whose sources is in S.
+///
+/// Only this disjunctive partitioning is implemented : there is no
+/// conjunctive partitioning of the relation, and hence no
+/// quantification schedule choosing the order in which conjuncts are
+/// combined and variables quantified. Each part is quantified over all
+/// the variables of the image at once.
+///
 /// # Relational product to substitute current states set with next states set
 ///
 /// Likewise, to facilitate symbolic model checking, we precompute and
@@ -91,14 +119,46 @@ pub(crate) struct KripkeStructureBddRepresentation {
     current_state_vars: Vec<BddVariable>,
     /// next-state bit variables [n1,...,nk], least significant bit first
     next_state_vars: Vec<BddVariable>,
-    /// formula corresponding to the transition relation
-    transition_relation: Bdd,
-    /// we also memoize the negated version
-    negated_transition_relation: Bdd,
+    /// formula corresponding to the transition relation, whole or in parts
+    transition_relation: TransitionRelation,
     /// formula that is used for the relational product
     next_iff_current: Bdd,
 }
 
+/// How the transition relation of a
+/// [CtlModelChecker](crate::solve::CtlModelChecker) is stored (see
+/// [CtlModelChecker::new_partitioned](crate::solve::CtlModelChecker::new_partitioned)).
+/// Both give the same satisfaction sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TransitionPartitioning {
+    /// A single BDD for the whole relation, and one for its negation.
+    #[default]
+    Monolithic,
+    /// One BDD per block of `block_size` consecutive source states (a
+    /// `block_size` of `0` is taken as `1`) : the images are computed
+    /// part by part, without ever building the whole relation.
+    SourceBlocks { block_size: usize },
+}
+
+/// The transitions from a block of source states.
+struct TransitionPart {
+    /// the source states, over the current-state variables
+    sources: Bdd,
+    /// the target states, over the next-state variables
+    targets: Bdd,
+    relation: Bdd,
+}
+
+enum TransitionRelation {
+    Monolithic {
+        relation: Bdd,
+        /// we also memoize the negated version
+        negated: Bdd,
+    },
+    /// the disjunction of the parts
+    Partitioned(Vec<TransitionPart>),
+}
+
 /// The number of bits over which the state ids 0..num_states are encoded,
 /// i.e., ⌈log₂(num_states)⌉ (0 for the empty and the single-state
 /// structures, whose only encoding is the empty conjunction).
@@ -140,7 +200,10 @@ impl KripkeStructureBddRepresentation {
         formula
     }
 
-    pub(crate) fn from_kripke_structure<DOAP>(kripke: &KripkeStructure<DOAP>) -> Self {
+    pub(crate) fn from_kripke_structure<DOAP>(
+        kripke: &KripkeStructure<DOAP>,
+        partitioning: TransitionPartitioning,
+    ) -> Self {
         let num_states = kripke.states().len();
         let num_bits = num_bits_for_state_ids(num_states);
         // num_bits <= usize::BITS, so 2 * num_bits always fits the
@@ -153,26 +216,54 @@ impl KripkeStructureBddRepresentation {
             all_vars.iter().skip(1).step_by(2).copied().collect();
         // ***
         // one clause per transition, fixing every bit of both endpoints
-        let mut transition_clauses = Vec::new();
-        for (origin_st_id, k_state) in kripke.states().iter().enumerate() {
-            for target_st_id in &k_state.outgoing_transitions_targets {
-                transition_clauses.push(BddPartialValuation::from_values_iter(
-                    current_state_vars
-                        .iter()
-                        .enumerate()
-                        .map(|(bit, var)| (*var, (origin_st_id >> bit) & 1 == 1))
-                        .chain(
-                            next_state_vars
-                                .iter()
-                                .enumerate()
-                                .map(|(bit, var)| (*var, (*target_st_id >> bit) & 1 == 1)),
-                        ),
-                ));
+        let transition_clauses = |sources: std::ops::Range<usize>| {
+            let mut clauses = Vec::new();
+            for origin_st_id in sources {
+                let k_state = &kripke.states()[origin_st_id];
+                for target_st_id in &k_state.outgoing_transitions_targets {
+                    clauses.push(BddPartialValuation::from_values_iter(
+                        current_state_vars
+                            .iter()
+                            .enumerate()
+                            .map(|(bit, var)| (*var, (origin_st_id >> bit) & 1 == 1))
+                            .chain(
+                                next_state_vars
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(bit, var)| (*var, (*target_st_id >> bit) & 1 == 1)),
+                            ),
+                    ));
+                }
             }
-        }
-        let transition_relation = var_set.mk_dnf(&transition_clauses);
-        // ***
-        let negated_transition_relation = transition_relation.not();
+            clauses
+        };
+        let transition_relation = match partitioning {
+            TransitionPartitioning::Monolithic => {
+                let relation = var_set.mk_dnf(&transition_clauses(0..num_states));
+                let negated = relation.not();
+                TransitionRelation::Monolithic { relation, negated }
+            }
+            TransitionPartitioning::SourceBlocks { block_size } => {
+                let block_size = block_size.max(1);
+                let parts = (0..num_states)
+                    .step_by(block_size)
+                    .map(|block_start| {
+                        let sources = block_start..num_states.min(block_start + block_size);
+                        let relation = var_set.mk_dnf(&transition_clauses(sources.clone()));
+                        TransitionPart {
+                            sources: var_set.mk_dnf(
+                                &sources
+                                    .map(|st_id| state_encoding(&current_state_vars, st_id))
+                                    .collect::<Vec<_>>(),
+                            ),
+                            targets: relation.exists(&current_state_vars),
+                            relation,
+                        }
+                    })
+                    .collect();
+                TransitionRelation::Partitioned(parts)
+            }
+        };
         // ***
         let mut next_iff_current = var_set.mk_true();
         for (current_var, next_var) in current_state_vars.iter().zip(next_state_vars.iter()) {
@@ -185,7 +276,6 @@ impl KripkeStructureBddRepresentation {
             current_state_vars,
             next_state_vars,
             transition_relation,
-            negated_transition_relation,
             next_iff_current,
         }
     }
@@ -200,19 +290,104 @@ impl KripkeStructureBddRepresentation {
         kind: PreImageKind,
         current_states: &Bdd,
     ) -> Bdd {
-        match kind {
+        match (kind, &self.transition_relation) {
             // EX(S) = ∃s′⋅ T(s,s′) ∧ S(s′)
-            PreImageKind::Weak => current_states
-                .and(&self.next_iff_current)
-                .exists(&self.current_state_vars)
-                .and(&self.transition_relation)
-                .exists(&self.next_state_vars),
+            (PreImageKind::Weak, _) => {
+                self.get_weak_pre_image_of_next(&self.to_next_states(current_states), None)
+            }
             // AX(S) = ∀s′⋅ ¬T(s,s′) ∨ S(s′)
-            PreImageKind::Strong => current_states
-                .and(&self.next_iff_current)
-                .exists(&self.current_state_vars)
-                .or(&self.negated_transition_relation)
+            (PreImageKind::Strong, TransitionRelation::Monolithic { negated, .. }) => self
+                .to_next_states(current_states)
+                .or(negated)
                 .for_all(&self.next_state_vars),
+            // AX(S) = ¬EX(¬S)
+            (PreImageKind::Strong, TransitionRelation::Partitioned(_)) => self
+                .get_weak_pre_image_of_next(&self.to_next_states(&current_states.not()), None)
+                .not(),
+        }
+    }
+
+    /// The weak preimage of `current_states` (see [PreImageKind]),
+    /// restricted to the states of `among` : the transition relation is
+    /// restricted to them first, which is cheaper when `among` is small.
+    pub(crate) fn get_weak_pre_image_among(&self, current_states: &Bdd, among: &Bdd) -> Bdd {
+        // among ∧ EX(S) = ∃s′⋅ (among(s) ∧ T(s,s′)) ∧ S(s′)
+        self.get_weak_pre_image_of_next(&self.to_next_states(current_states), Some(among))
+    }
+
+    /// `states` (over the current-state variables) over the next-state
+    /// variables.
+    fn to_next_states(&self, states: &Bdd) -> Bdd {
+        states
+            .and(&self.next_iff_current)
+            .exists(&self.current_state_vars)
+    }
+
+    /// The weak preimage of `next_states` (over the next-state
+    /// variables), restricted to the states of `among` if any.
+    fn get_weak_pre_image_of_next(&self, next_states: &Bdd, among: Option<&Bdd>) -> Bdd {
+        let restricted = |relation: &Bdd| match among {
+            Some(among) => among.and(relation),
+            None => relation.clone(),
+        };
+        match &self.transition_relation {
+            TransitionRelation::Monolithic { relation, .. } => next_states
+                .and(&restricted(relation))
+                .exists(&self.next_state_vars),
+            TransitionRelation::Partitioned(parts) => {
+                let mut pre_image = self.var_set.mk_false();
+                for part in parts {
+                    let is_skipped = next_states.and(&part.targets).is_false()
+                        || among.is_some_and(|among| among.and(&part.sources).is_false());
+                    if !is_skipped {
+                        pre_image = pre_image.or(&next_states
+                            .and(&restricted(&part.relation))
+                            .exists(&self.next_state_vars));
+                    }
+                }
+                pre_image
+            }
+        }
+    }
+
+    /// Given a BDD representing a set of states `current_states`,
+    /// returns a BDD representing the set of their successors, i.e.,
+    /// `Post(X) = {s' ∈ S | ∃ s ∈ X, s ⇾ s'}`, over the current-state
+    /// variables.
+    pub(crate) fn get_post_image_by_transition_relation(&self, current_states: &Bdd) -> Bdd {
+        // Post(S) = ∃s⋅ S(s) ∧ T(s,s′), then renamed from s′ to s
+        let next_states = match &self.transition_relation {
+            TransitionRelation::Monolithic { relation, .. } => current_states
+                .and(relation)
+                .exists(&self.current_state_vars),
+            TransitionRelation::Partitioned(parts) => {
+                let mut post_image = self.var_set.mk_false();
+                for part in parts {
+                    if !current_states.and(&part.sources).is_false() {
+                        post_image = post_image.or(&current_states
+                            .and(&part.relation)
+                            .exists(&self.current_state_vars));
+                    }
+                }
+                post_image
+            }
+        };
+        next_states
+            .and(&self.next_iff_current)
+            .exists(&self.next_state_vars)
+    }
+
+    /// The least set of states containing `initial_states` and closed
+    /// under the post-image, i.e., the states forward-reachable from
+    /// `initial_states`.
+    pub(crate) fn get_forward_reachable_states(&self, initial_states: &Bdd) -> Bdd {
+        let mut current = initial_states.clone();
+        loop {
+            let next = current.or(&self.get_post_image_by_transition_relation(&current));
+            if next == current {
+                return current;
+            }
+            current = next;
         }
     }
 }
@@ -235,6 +410,7 @@ impl KripkeStructureBddRepresentation {
 /// # References
 ///
 /// The weak/strong preimage terminology can be found in <https://doi.org/10.1016/S0004-3702(02)00374-0>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum PreImageKind {
     Weak,
     Strong,
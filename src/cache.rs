@@ -0,0 +1,280 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The satisfaction sets kept by a
+//! [CtlModelChecker](crate::solve::CtlModelChecker) from one query to
+//! the next.
+//!
+//! A checker keeps the satisfaction sets of the sub-formulae of its
+//! queries, so that a sub-formula shared by several queries (e.g.
+//! `AG !error`, or an atom) is computed once :
+//!
+//! ```
+//! use std::collections::HashSet;
+//! use citreelo::labels::{label_parser, labelled_kripke};
+//! use citreelo::parser::CtlFormulaParser;
+//! use citreelo::solve::CtlModelChecker;
+//!
+//! let kripke = labelled_kripke([(vec!["idle"], vec![1]), (vec!["error"], vec![1])]).unwrap();
+//! let checker = CtlModelChecker::new(&kripke);
+//! let parse = |input| label_parser().parse_complete_ctl_formula(input).unwrap();
+//! assert_eq!(checker.get_sat_set(&parse("AG !error")), HashSet::new());
+//! // `AG !error` is not computed again
+//! assert_eq!(checker.get_sat_set(&parse("idle => AG !error")), HashSet::from([1]));
+//! ```
+//!
+//! The formulae are hash-consed in a
+//! [CTLFormulaArena](crate::arena::CTLFormulaArena), so that the cached
+//! sub-formulae are found in constant time. The cache holds at most a
+//! given number of satisfaction sets, evicting the least recently used
+//! ones ; when the arena grows much larger than the cache, it is rebuilt
+//! from the formulae of the remaining entries.
+//!
+//! A query is computed in an arena of its own : the cache is only
+//! consulted before, and filled after, so that the checker need not
+//! hold it while computing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+use biodivine_lib_bdd::Bdd;
+
+use crate::arena::{CTLFormulaArena, FormulaId};
+
+/// The number of satisfaction sets a
+/// [CtlModelChecker](crate::solve::CtlModelChecker) keeps by default
+/// (see [CtlModelChecker::with_cache_capacity](crate::solve::CtlModelChecker::with_cache_capacity)).
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// The arena is rebuilt once it holds more than this many nodes per
+/// cached satisfaction set, so that the formulae of evicted entries do
+/// not accumulate forever.
+const MAX_ARENA_NODES_PER_ENTRY: usize = 4;
+
+/// The satisfaction sets kept across queries, for formulae over atomic
+/// propositions of type `AP`, which concurrent queries share.
+pub(crate) struct SatSetCache<AP> {
+    entries: Mutex<CacheEntries<AP>>,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> SatSetCache<AP> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        SatSetCache {
+            entries: Mutex::new(CacheEntries {
+                arena: CTLFormulaArena::new(),
+                sat_sets: Vec::new(),
+                capacity,
+                by_last_use: BTreeMap::new(),
+                last_uses: HashMap::new(),
+                clock: 0,
+                compacted_len: 0,
+            }),
+        }
+    }
+
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut entries = self.entries();
+        entries.capacity = capacity;
+        entries.evict_overflow();
+        entries.compact_if_too_large();
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.entries().capacity
+    }
+
+    /// The number of cached satisfaction sets.
+    pub(crate) fn len(&self) -> usize {
+        self.entries().last_uses.len()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// The entries, which remain usable after a panic of another thread.
+    fn entries(&self) -> MutexGuard<'_, CacheEntries<AP>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The cached satisfaction sets of the formulae of `arena`, indexed
+    /// by [FormulaId::index], which become the most recently used ones.
+    pub(crate) fn lookup(&self, arena: &CTLFormulaArena<AP>) -> Vec<Option<Bdd>> {
+        let mut entries = self.entries();
+        if entries.capacity == 0 {
+            return Vec::new();
+        }
+        let cache_ids = entries.find(arena);
+        let sat_sets: Vec<Option<Bdd>> = cache_ids
+            .iter()
+            .map(|cache_id| entries.sat_sets.get((*cache_id)?.index())?.clone())
+            .collect();
+        for (cache_id, sat_set) in cache_ids.into_iter().zip(&sat_sets) {
+            if let (Some(cache_id), Some(_)) = (cache_id, sat_set) {
+                entries.touch(cache_id);
+            }
+        }
+        sat_sets
+    }
+
+    /// Caches the satisfaction sets of the formulae of `arena`, indexed
+    /// by [FormulaId::index], and evicts the least recently used entries
+    /// beyond the capacity.
+    pub(crate) fn store(&self, arena: &CTLFormulaArena<AP>, sat_sets: &[Option<Bdd>]) {
+        let mut entries = self.entries();
+        if entries.capacity == 0 {
+            return;
+        }
+        let mut cache_ids: Vec<FormulaId> = Vec::with_capacity(sat_sets.len());
+        let mut stored = Vec::new();
+        for (id, sat_set) in arena.ids().zip(sat_sets) {
+            let node = arena
+                .node(id)
+                .map_children(|child| Some(cache_ids[child.index()]))
+                .expect("the operands are imported first");
+            let cache_id = entries.arena.mk(node);
+            cache_ids.push(cache_id);
+            if let Some(sat_set) = sat_set {
+                if entries.sat_sets.len() <= cache_id.index() {
+                    entries.sat_sets.resize(cache_id.index() + 1, None);
+                }
+                entries.sat_sets[cache_id.index()].get_or_insert_with(|| sat_set.clone());
+                stored.push(cache_id);
+            }
+        }
+        for cache_id in stored {
+            entries.touch(cache_id);
+        }
+        entries.evict_overflow();
+        entries.compact_if_too_large();
+    }
+}
+
+/// A least-recently-used cache of satisfaction sets, indexed by the ids
+/// of their formulae in `arena`.
+struct CacheEntries<AP> {
+    arena: CTLFormulaArena<AP>,
+    sat_sets: Vec<Option<Bdd>>,
+    capacity: usize,
+    /// the cached entries, by time of last use
+    by_last_use: BTreeMap<u64, FormulaId>,
+    /// the time of last use of the cached entries
+    last_uses: HashMap<FormulaId, u64>,
+    clock: u64,
+    /// the length of the arena when it was last rebuilt
+    compacted_len: usize,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> CacheEntries<AP> {
+    fn clear(&mut self) {
+        self.arena = CTLFormulaArena::new();
+        self.sat_sets.clear();
+        self.by_last_use.clear();
+        self.last_uses.clear();
+        self.compacted_len = 0;
+    }
+
+    /// Rebuilds the arena from the formulae of the cached entries and
+    /// their sub-formulae only, if it has grown too large. The threshold
+    /// is at least twice the length of the last rebuilt arena, so that
+    /// the cost of the rebuilds is amortized over the insertions.
+    fn compact_if_too_large(&mut self) {
+        let max_arena_len = self
+            .capacity
+            .saturating_mul(MAX_ARENA_NODES_PER_ENTRY)
+            .max(self.compacted_len.saturating_mul(2));
+        if self.arena.len() <= max_arena_len {
+            return;
+        }
+        let mut live = vec![false; self.arena.len()];
+        for id in self.last_uses.keys() {
+            live[id.index()] = true;
+        }
+        // operands have smaller ids than the formulae containing them
+        for id in self.arena.ids().rev() {
+            if live[id.index()] {
+                for child in self.arena.node(id).children() {
+                    live[child.index()] = true;
+                }
+            }
+        }
+        let mut arena = CTLFormulaArena::new();
+        let mut sat_sets = Vec::new();
+        let mut new_ids: Vec<Option<FormulaId>> = vec![None; self.arena.len()];
+        for id in self.arena.ids().filter(|id| live[id.index()]) {
+            let node = self
+                .arena
+                .node(id)
+                .map_children(|child| new_ids[child.index()])
+                .expect("the operands of a kept formula are kept");
+            let new_id = arena.mk(node);
+            new_ids[id.index()] = Some(new_id);
+            if let Some(sat_set) = self.sat_sets.get_mut(id.index()).and_then(Option::take) {
+                sat_sets.resize(new_id.index() + 1, None);
+                sat_sets[new_id.index()] = Some(sat_set);
+            }
+        }
+        let remap = |id: FormulaId| new_ids[id.index()].expect("the cached formulae are kept");
+        self.by_last_use = self
+            .by_last_use
+            .iter()
+            .map(|(last_use, id)| (*last_use, remap(*id)))
+            .collect();
+        self.last_uses = self
+            .last_uses
+            .iter()
+            .map(|(id, last_use)| (remap(*id), *last_use))
+            .collect();
+        self.compacted_len = arena.len();
+        self.arena = arena;
+        self.sat_sets = sat_sets;
+    }
+
+    fn touch(&mut self, id: FormulaId) {
+        self.clock += 1;
+        if let Some(last_use) = self.last_uses.insert(id, self.clock) {
+            self.by_last_use.remove(&last_use);
+        }
+        self.by_last_use.insert(self.clock, id);
+    }
+
+    fn evict_overflow(&mut self) {
+        while self.last_uses.len() > self.capacity {
+            let Some((_, id)) = self.by_last_use.pop_first() else {
+                break;
+            };
+            self.last_uses.remove(&id);
+            self.sat_sets[id.index()] = None;
+        }
+    }
+
+    /// The ids in the cache of the formulae of `arena`, for those which
+    /// are there.
+    fn find(&self, arena: &CTLFormulaArena<AP>) -> Vec<Option<FormulaId>> {
+        let mut cache_ids: Vec<Option<FormulaId>> = Vec::with_capacity(arena.len());
+        for id in arena.ids() {
+            let cache_id = arena
+                .node(id)
+                .map_children(|child| cache_ids[child.index()])
+                .and_then(|node| self.arena.id_of(&node));
+            cache_ids.push(cache_id);
+        }
+        cache_ids
+    }
+}
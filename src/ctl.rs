@@ -14,7 +14,13 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-use std::{collections::HashSet, hash::Hash};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    hash::Hash,
+    ops::Range,
+    sync::Arc,
+};
 
 /// The unary connectives of CTL : boolean negation and the six
 /// path-quantified temporal operators on a single sub-formula.
@@ -54,13 +60,64 @@ pub enum BinaryCTLOperator {
     EU,
 }
 
-/// The leaves of a [CTLFormula] : the boolean constants and the
-/// user-defined atomic propositions.
+/// The leaves of a [CTLFormula] : the boolean constants, the
+/// user-defined atomic propositions and the sets of states.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum CTLFormulaLeaf<AP> {
     True,
     False,
     AtomicProp(AP),
+    /// holds exactly on the states of the set, e.g. one computed by a
+    /// previous query or outside of the checker ; the ids which are not
+    /// those of states of the checked structure are ignored
+    States(StateSet),
+}
+
+/// A set of state ids, cheap to clone, written `{0, 2, 5}`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct StateSet(Arc<BTreeSet<usize>>);
+
+impl StateSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, state_id: usize) -> bool {
+        self.0.contains(&state_id)
+    }
+
+    /// The ids, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromIterator<usize> for StateSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(state_ids: I) -> Self {
+        StateSet(Arc::new(state_ids.into_iter().collect()))
+    }
+}
+
+/// As returned by [get_sat_set](crate::solve::CtlModelChecker::get_sat_set).
+impl From<&HashSet<usize>> for StateSet {
+    fn from(state_ids: &HashSet<usize>) -> Self {
+        state_ids.iter().copied().collect()
+    }
+}
+
+impl fmt::Display for StateSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<String> = self.iter().map(|id| id.to_string()).collect();
+        write!(f, "{{{}}}", ids.join(", "))
+    }
 }
 
 /// The abstract syntax tree of a CTL formula over atomic propositions
@@ -76,58 +133,364 @@ pub enum CTLFormula<AP> {
     Binary(BinaryCTLOperator, Box<CTLFormula<AP>>, Box<CTLFormula<AP>>),
 }
 
+/// The position of a sub-formula occurrence inside a [CTLFormula] : the
+/// path of child indices leading to it from the root, where the operand
+/// of a unary operator and the left operand of a binary one have index
+/// 0, and the right operand of a binary one has index 1.
+/// The root is at the empty position.
+pub type CTLFormulaPosition = Vec<usize>;
+
+impl<AP: Clone> CTLFormula<AP> {
+    /// The sub-formula occurring at `position`, if any.
+    pub fn subformula_at(&self, position: &[usize]) -> Option<&CTLFormula<AP>> {
+        let mut current = self;
+        for child_index in position {
+            current = match (current, child_index) {
+                (CTLFormula::Unary(_, phi1), 0) => phi1,
+                (CTLFormula::Binary(_, phi1, _), 0) => phi1,
+                (CTLFormula::Binary(_, _, phi2), 1) => phi2,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// A copy of this formula in which the sub-formula occurring at
+    /// `position` is replaced by `replacement`, or `None` if there is no
+    /// such position.
+    pub fn replace_at(
+        &self,
+        position: &[usize],
+        replacement: CTLFormula<AP>,
+    ) -> Option<CTLFormula<AP>> {
+        // walks down to the position, then rebuilds the path bottom-up,
+        // without recursing on the depth of the formula
+        enum PathStep<AP> {
+            Unary(UnaryCTLOperator),
+            Left(BinaryCTLOperator, Box<CTLFormula<AP>>),
+            Right(BinaryCTLOperator, Box<CTLFormula<AP>>),
+        }
+        let mut path = Vec::with_capacity(position.len());
+        let mut current = self;
+        for child_index in position {
+            current = match (current, child_index) {
+                (CTLFormula::Unary(op, phi1), 0) => {
+                    path.push(PathStep::Unary(op.clone()));
+                    phi1
+                }
+                (CTLFormula::Binary(op, phi1, phi2), 0) => {
+                    path.push(PathStep::Left(op.clone(), phi2.clone()));
+                    phi1
+                }
+                (CTLFormula::Binary(op, phi1, phi2), 1) => {
+                    path.push(PathStep::Right(op.clone(), phi1.clone()));
+                    phi2
+                }
+                _ => return None,
+            };
+        }
+        let mut rebuilt = replacement;
+        while let Some(step) = path.pop() {
+            rebuilt = match step {
+                PathStep::Unary(op) => CTLFormula::Unary(op, Box::new(rebuilt)),
+                PathStep::Left(op, phi2) => CTLFormula::Binary(op, Box::new(rebuilt), phi2),
+                PathStep::Right(op, phi1) => CTLFormula::Binary(op, phi1, Box::new(rebuilt)),
+            };
+        }
+        Some(rebuilt)
+    }
+}
+
 /// The leaves occurring in a [CTLFormula], as returned by
-/// [CTLFormula::collect_leaves] : the set of distinct atomic
-/// propositions, and, if present, one representative `true` leaf
-/// and one representative `false` leaf.
+/// [CTLFormula::collect_leaves] : the sets of distinct atomic
+/// propositions and of distinct [CTLFormulaLeaf::States] leaves, and, if
+/// present, one representative `true` leaf and one representative
+/// `false` leaf.
 pub struct CollectedLeaves<'a, AP> {
     pub atoms: HashSet<&'a CTLFormula<AP>>,
+    pub state_sets: HashSet<&'a CTLFormula<AP>>,
     pub true_formula: Option<&'a CTLFormula<AP>>,
     pub false_formula: Option<&'a CTLFormula<AP>>,
 }
 
 impl<AP: Clone + PartialEq + Eq + Hash> CTLFormula<AP> {
     pub fn collect_leaves(&self) -> CollectedLeaves<'_, AP> {
-        let mut atoms = HashSet::new();
-        let mut true_formula = None;
-        let mut false_formula = None;
-        self.collect_leaves_rec(&mut atoms, &mut true_formula, &mut false_formula);
-        CollectedLeaves {
-            atoms,
-            true_formula,
-            false_formula,
+        let mut collected = CollectedLeaves {
+            atoms: HashSet::new(),
+            state_sets: HashSet::new(),
+            true_formula: None,
+            false_formula: None,
+        };
+        for phi in self.subformulas() {
+            match phi {
+                CTLFormula::Leaf(CTLFormulaLeaf::True) => {
+                    collected.true_formula.get_or_insert(phi);
+                }
+                CTLFormula::Leaf(CTLFormulaLeaf::False) => {
+                    collected.false_formula.get_or_insert(phi);
+                }
+                CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(_)) => {
+                    collected.atoms.insert(phi);
+                }
+                CTLFormula::Leaf(CTLFormulaLeaf::States(_)) => {
+                    collected.state_sets.insert(phi);
+                }
+                CTLFormula::Unary(..) | CTLFormula::Binary(..) => {}
+            }
         }
+        collected
+    }
+}
+
+/// A bottom-up computation over a [CTLFormula], applied by
+/// [CTLFormula::fold] : each node is mapped to an output computed from
+/// its operator and the outputs of its operands.
+pub trait CTLFormulaFolder<AP> {
+    type Output;
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> Self::Output;
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: Self::Output) -> Self::Output;
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: Self::Output,
+        right: Self::Output,
+    ) -> Self::Output;
+}
+
+/// A top-down construction of formulae, used by the parser
+/// (see [crate::parser::CtlFormulaParser::parse_ctl_formula_with]) to
+/// produce either [CTLFormula] trees ([CTLFormulaTreeBuilder]) or ids in
+/// a [crate::arena::CTLFormulaArena].
+pub trait CTLFormulaBuilder<AP> {
+    type Formula;
+
+    fn build_leaf(&mut self, leaf: CTLFormulaLeaf<AP>) -> Self::Formula;
+
+    fn build_unary(&mut self, op: UnaryCTLOperator, sub: Self::Formula) -> Self::Formula;
+
+    fn build_binary(
+        &mut self,
+        op: BinaryCTLOperator,
+        left: Self::Formula,
+        right: Self::Formula,
+    ) -> Self::Formula;
+
+    /// Converts a formula given as a tree, e.g. one returned by
+    /// [crate::parser::CtlFormulaParser::parse_atomic_proposition].
+    fn build_formula(&mut self, formula: CTLFormula<AP>) -> Self::Formula;
+
+    /// Called by the parser once `formula` is complete, with the byte
+    /// range of the input it was read from ; it is called again with a
+    /// wider range when the formula is parenthesized, or is the expansion
+    /// of a named formula. Ignored by default (see
+    /// [crate::parser::spans]).
+    fn with_span(&mut self, formula: Self::Formula, span: Range<usize>) -> Self::Formula {
+        let _ = span;
+        formula
+    }
+}
+
+/// The [CTLFormulaBuilder] producing plain [CTLFormula] trees.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CTLFormulaTreeBuilder;
+
+impl<AP> CTLFormulaBuilder<AP> for CTLFormulaTreeBuilder {
+    type Formula = CTLFormula<AP>;
+
+    fn build_leaf(&mut self, leaf: CTLFormulaLeaf<AP>) -> CTLFormula<AP> {
+        CTLFormula::Leaf(leaf)
+    }
+
+    fn build_unary(&mut self, op: UnaryCTLOperator, sub: CTLFormula<AP>) -> CTLFormula<AP> {
+        CTLFormula::Unary(op, Box::new(sub))
+    }
+
+    fn build_binary(
+        &mut self,
+        op: BinaryCTLOperator,
+        left: CTLFormula<AP>,
+        right: CTLFormula<AP>,
+    ) -> CTLFormula<AP> {
+        CTLFormula::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    fn build_formula(&mut self, formula: CTLFormula<AP>) -> CTLFormula<AP> {
+        formula
+    }
+}
+
+impl<AP> CTLFormula<AP> {
+    /// The direct operands of this formula, from left to right.
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = &CTLFormula<AP>> {
+        let (first, second) = match self {
+            CTLFormula::Leaf(_) => (None, None),
+            CTLFormula::Unary(_, phi1) => (Some(phi1.as_ref()), None),
+            CTLFormula::Binary(_, phi1, phi2) => (Some(phi1.as_ref()), Some(phi2.as_ref())),
+        };
+        first.into_iter().chain(second)
     }
 
-    fn collect_leaves_rec<'a>(
-        &'a self,
-        atoms: &mut HashSet<&'a CTLFormula<AP>>,
-        true_formula: &mut Option<&'a CTLFormula<AP>>,
-        false_formula: &mut Option<&'a CTLFormula<AP>>,
-    ) {
-        match self {
-            x @ CTLFormula::Leaf(leaf) => match leaf {
-                CTLFormulaLeaf::True => {
-                    if true_formula.is_none() {
-                        *true_formula = Some(x);
+    /// Applies `folder` bottom-up over this formula, operands being
+    /// folded from left to right.
+    ///
+    /// The traversal uses an explicit stack rather than recursion, so
+    /// that arbitrarily deep formulae can be folded.
+    pub fn fold<F: CTLFormulaFolder<AP>>(&self, folder: &mut F) -> F::Output {
+        // (node, whether its operands have already been folded)
+        let mut to_visit = vec![(self, false)];
+        let mut outputs = Vec::new();
+        while let Some((phi, operands_folded)) = to_visit.pop() {
+            match phi {
+                CTLFormula::Leaf(leaf) => outputs.push(folder.fold_leaf(leaf)),
+                CTLFormula::Unary(op, phi1) => {
+                    if operands_folded {
+                        let sub = outputs.pop().unwrap();
+                        outputs.push(folder.fold_unary(op, sub));
+                    } else {
+                        to_visit.push((phi, true));
+                        to_visit.push((phi1, false));
                     }
                 }
-                CTLFormulaLeaf::False => {
-                    if false_formula.is_none() {
-                        *false_formula = Some(x);
+                CTLFormula::Binary(op, phi1, phi2) => {
+                    if operands_folded {
+                        let right = outputs.pop().unwrap();
+                        let left = outputs.pop().unwrap();
+                        outputs.push(folder.fold_binary(op, left, right));
+                    } else {
+                        to_visit.push((phi, true));
+                        to_visit.push((phi2, false));
+                        to_visit.push((phi1, false));
                     }
                 }
-                CTLFormulaLeaf::AtomicProp(_) => {
-                    atoms.insert(x);
-                }
-            },
-            CTLFormula::Unary(_, phi1) => {
-                phi1.collect_leaves_rec(atoms, true_formula, false_formula);
             }
-            CTLFormula::Binary(_, phi1, phi2) => {
-                phi1.collect_leaves_rec(atoms, true_formula, false_formula);
-                phi2.collect_leaves_rec(atoms, true_formula, false_formula);
+        }
+        outputs.pop().unwrap()
+    }
+
+    /// The same formula with every atomic proposition mapped through `f`.
+    pub fn map_atoms<AP2>(&self, mut f: impl FnMut(&AP) -> AP2) -> CTLFormula<AP2> {
+        let result: Result<_, std::convert::Infallible> = self.try_map_atoms(|ap| Ok(f(ap)));
+        match result {
+            Ok(mapped) => mapped,
+        }
+    }
+
+    /// The same formula with every atomic proposition mapped through `f`,
+    /// or the first error returned by `f` (in left-to-right order of
+    /// occurrence), after which `f` is not called anymore.
+    pub fn try_map_atoms<AP2, E>(
+        &self,
+        f: impl FnMut(&AP) -> Result<AP2, E>,
+    ) -> Result<CTLFormula<AP2>, E> {
+        let mut mapper = AtomMapper { f, error: None };
+        let mapped = self.fold(&mut mapper);
+        match mapper.error {
+            Some(error) => Err(error),
+            None => Ok(mapped.expect("no atom failed to map")),
+        }
+    }
+
+    /// The sub-formulae of this formula (itself included), in pre-order,
+    /// operands from left to right.
+    pub fn subformulas(&self) -> Subformulas<'_, AP> {
+        Subformulas {
+            to_visit: vec![self],
+        }
+    }
+
+    /// The sub-formulae of this formula (itself included) together with
+    /// their positions, in the same order as [Self::subformulas].
+    pub fn positioned_subformulas(&self) -> PositionedSubformulas<'_, AP> {
+        PositionedSubformulas {
+            to_visit: vec![(Vec::new(), self)],
+        }
+    }
+}
+
+struct AtomMapper<F, E> {
+    f: F,
+    error: Option<E>,
+}
+
+impl<AP, AP2, E, F: FnMut(&AP) -> Result<AP2, E>> CTLFormulaFolder<AP> for AtomMapper<F, E> {
+    /// `None` once an error has occurred
+    type Output = Option<CTLFormula<AP2>>;
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> Self::Output {
+        let mapped = match leaf {
+            CTLFormulaLeaf::True => CTLFormulaLeaf::True,
+            CTLFormulaLeaf::False => CTLFormulaLeaf::False,
+            CTLFormulaLeaf::States(states) => CTLFormulaLeaf::States(states.clone()),
+            CTLFormulaLeaf::AtomicProp(ap) => {
+                if self.error.is_some() {
+                    return None;
+                }
+                match (self.f)(ap) {
+                    Ok(mapped_ap) => CTLFormulaLeaf::AtomicProp(mapped_ap),
+                    Err(error) => {
+                        self.error = Some(error);
+                        return None;
+                    }
+                }
             }
         };
+        Some(CTLFormula::Leaf(mapped))
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: Self::Output) -> Self::Output {
+        Some(CTLFormula::Unary(op.clone(), Box::new(sub?)))
+    }
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: Self::Output,
+        right: Self::Output,
+    ) -> Self::Output {
+        Some(CTLFormula::Binary(
+            op.clone(),
+            Box::new(left?),
+            Box::new(right?),
+        ))
+    }
+}
+
+/// Pre-order iterator over the sub-formulae of a [CTLFormula]
+/// (see [CTLFormula::subformulas]).
+pub struct Subformulas<'a, AP> {
+    to_visit: Vec<&'a CTLFormula<AP>>,
+}
+
+impl<'a, AP> Iterator for Subformulas<'a, AP> {
+    type Item = &'a CTLFormula<AP>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let phi = self.to_visit.pop()?;
+        self.to_visit.extend(phi.children().rev());
+        Some(phi)
+    }
+}
+
+/// Pre-order iterator over the sub-formulae of a [CTLFormula] and their
+/// positions (see [CTLFormula::positioned_subformulas]).
+pub struct PositionedSubformulas<'a, AP> {
+    to_visit: Vec<(CTLFormulaPosition, &'a CTLFormula<AP>)>,
+}
+
+impl<'a, AP> Iterator for PositionedSubformulas<'a, AP> {
+    type Item = (CTLFormulaPosition, &'a CTLFormula<AP>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (position, phi) = self.to_visit.pop()?;
+        let children: Vec<_> = phi.children().enumerate().collect();
+        for (child_index, child) in children.into_iter().rev() {
+            let mut child_position = position.clone();
+            child_position.push(child_index);
+            self.to_visit.push((child_position, child));
+        }
+        Some((position, phi))
     }
 }
@@ -0,0 +1,621 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Atomic propositions comparing the variables of the states, as in
+//! `count >= 3 & mode = Idle & x + y < 10`.
+//!
+//! The domain labelling the states exposes its variables by implementing
+//! [StateVariables](crate::expressions::StateVariables). The atoms are
+//! [Comparison](crate::expressions::Comparison)s between integer
+//! arithmetic expressions (`+`, `-`, `*`, `/`, `%`, unary `-` and
+//! parentheses), booleans and enumerated values, and are parsed by
+//! [ExpressionParser](crate::expressions::ExpressionParser), which is
+//! given the names of the variables. The nom parsers
+//! [comparison](crate::expressions::comparison) and
+//! [expression](crate::expressions::expression) it is built on may also
+//! be called from any other
+//! [CtlFormulaParser](crate::parser::CtlFormulaParser), e.g. to mix
+//! comparisons with other kinds of atoms :
+//!
+//! | atom                  | holds on a state where                     |
+//! |-----------------------|--------------------------------------------|
+//! | `x + y < 10`          | the sum of the integers `x` and `y` is below 10 |
+//! | `mode = Idle`, `mode != Idle` | the variable `mode` has (not) the enumerated value `Idle` |
+//! | `busy = true`, `busy` | the boolean variable `busy` is true        |
+//!
+//! The comparison operators are `=` (or `==`), `!=`, `<`, `<=`, `>`, `>=`
+//! (or `≠`, `≤`, `≥`). Identifiers which are not variables are enumerated
+//! values, and `true` and `false` are booleans. An atom must involve a
+//! variable, and integers only are ordered and added : e.g. `Idle < 3`
+//! and `mode + 1 = 2` are rejected, but `mode < 3` is accepted since the
+//! type of `mode` is only known on the states. Comparisons between values
+//! of different types, undefined variables and arithmetic errors
+//! (overflows, divisions by zero) make an atom false on a state.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use nom::IResult;
+use nom::error::{ErrorKind, ParseError};
+
+use crate::ctl::{CTLFormula, CTLFormulaLeaf};
+use crate::kripke::AtomicProposition;
+use crate::parser::CtlFormulaParser;
+use crate::parser::syntax::CtlSyntax;
+use crate::printer::CtlFormulaPrinter;
+
+/// The value of a variable of a state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    /// an enumerated value, by name
+    Symbol(String),
+}
+
+/// A domain of states exposing named variables.
+pub trait StateVariables {
+    /// The value of the variable `name` on this state, if it has one.
+    fn variable(&self, name: &str) -> Option<Value>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// An expression over the variables of a state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Constant(Value),
+    Variable(String),
+    Neg(Box<Expr>),
+    Arithmetic(ArithmeticOperator, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComparisonOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// An atomic proposition comparing two expressions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Comparison {
+    pub left: Expr,
+    pub op: ComparisonOperator,
+    pub right: Expr,
+}
+
+impl Expr {
+    /// The value of the expression on `state`, if it is defined.
+    pub fn evaluate<D: StateVariables + ?Sized>(&self, state: &D) -> Option<Value> {
+        match self {
+            Expr::Constant(value) => Some(value.clone()),
+            Expr::Variable(name) => state.variable(name),
+            Expr::Neg(sub) => match sub.evaluate(state)? {
+                Value::Int(i) => i.checked_neg().map(Value::Int),
+                _ => None,
+            },
+            Expr::Arithmetic(op, left, right) => {
+                let (Value::Int(left), Value::Int(right)) =
+                    (left.evaluate(state)?, right.evaluate(state)?)
+                else {
+                    return None;
+                };
+                let result = match op {
+                    ArithmeticOperator::Add => left.checked_add(right),
+                    ArithmeticOperator::Sub => left.checked_sub(right),
+                    ArithmeticOperator::Mul => left.checked_mul(right),
+                    ArithmeticOperator::Div => left.checked_div(right),
+                    ArithmeticOperator::Mod => left.checked_rem(right),
+                };
+                result.map(Value::Int)
+            }
+        }
+    }
+}
+
+impl Comparison {
+    pub fn evaluate<D: StateVariables + ?Sized>(&self, state: &D) -> bool {
+        let (Some(left), Some(right)) = (self.left.evaluate(state), self.right.evaluate(state))
+        else {
+            return false;
+        };
+        match (self.op, &left, &right) {
+            (ComparisonOperator::Eq, _, _) => left == right,
+            (ComparisonOperator::Ne, _, _) => {
+                std::mem::discriminant(&left) == std::mem::discriminant(&right) && left != right
+            }
+            (op, Value::Int(left), Value::Int(right)) => match op {
+                ComparisonOperator::Lt => left < right,
+                ComparisonOperator::Le => left <= right,
+                ComparisonOperator::Gt => left > right,
+                ComparisonOperator::Ge => left >= right,
+                ComparisonOperator::Eq | ComparisonOperator::Ne => unreachable!(),
+            },
+            _ => false,
+        }
+    }
+}
+
+impl<D: StateVariables> AtomicProposition<D> for Comparison {
+    fn is_satisfied_on_state_domain(&self, state: &D) -> bool {
+        self.evaluate(state)
+    }
+}
+
+// ***
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Symbol(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl ArithmeticOperator {
+    fn symbol(self) -> &'static str {
+        match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Sub => "-",
+            ArithmeticOperator::Mul => "*",
+            ArithmeticOperator::Div => "/",
+            ArithmeticOperator::Mod => "%",
+        }
+    }
+
+    /// 1 for additive operators, 2 for multiplicative ones
+    fn precedence(self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Sub => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl Expr {
+    /// 1 or 2 for arithmetic, 3 for self-delimiting expressions
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Arithmetic(op, ..) => op.precedence(),
+            _ => 3,
+        }
+    }
+
+    fn fmt_at_least(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// With as few parentheses as possible, the operators being left
+/// associative.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Constant(value) => write!(f, "{}", value),
+            Expr::Variable(name) => write!(f, "{}", name),
+            Expr::Neg(sub) => {
+                write!(f, "-")?;
+                sub.fmt_at_least(f, 3)
+            }
+            Expr::Arithmetic(op, left, right) => {
+                left.fmt_at_least(f, op.precedence())?;
+                write!(f, " {} ", op.symbol())?;
+                right.fmt_at_least(f, op.precedence() + 1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOperator::Eq => "=",
+            ComparisonOperator::Ne => "!=",
+            ComparisonOperator::Lt => "<",
+            ComparisonOperator::Le => "<=",
+            ComparisonOperator::Gt => ">",
+            ComparisonOperator::Ge => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op, self.right)
+    }
+}
+
+// ***
+
+/// The maximal nesting of parentheses, minus signs and arithmetic
+/// operators within an expression, which is read by recursive descent,
+/// and then evaluated and printed recursively : deeper parentheses are
+/// read as those of sub-formulae, and longer chains of operators, as in
+/// `x + 1 + ... + 1`, are rejected.
+const MAX_EXPRESSION_DEPTH: usize = 64;
+
+/// A parser and printer for CTL formulae whose atomic propositions are
+/// [Comparison]s (see the [module documentation](self)).
+#[derive(Debug, Clone, Default)]
+pub struct ExpressionParser {
+    variables: HashSet<String>,
+    syntax: CtlSyntax,
+}
+
+/// The static type of an expression, when it is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Int,
+    Bool,
+    Symbol,
+    /// that of a variable, or of a parenthesized variable
+    Unknown,
+}
+
+/// A parsed expression, with its type, whether it involves a variable,
+/// and the height of its syntax tree.
+struct Typed {
+    expr: Expr,
+    ty: Type,
+    has_variable: bool,
+    height: usize,
+}
+
+impl ExpressionParser {
+    /// A parser in which the given names denote variables.
+    pub fn new<S: Into<String>>(variables: impl IntoIterator<Item = S>) -> Self {
+        ExpressionParser {
+            variables: variables.into_iter().map(Into::into).collect(),
+            syntax: CtlSyntax::default(),
+        }
+    }
+
+    /// Sets the [syntax profile](crate::parser::syntax) of the formulae.
+    pub fn with_syntax(mut self, syntax: CtlSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// The comparison at the start of `input`, and the rest.
+    pub fn parse_comparison<'a>(&self, input: &'a str) -> Option<(&'a str, Comparison)> {
+        let reader = Reader {
+            variables: &self.variables,
+            lenient: false,
+        };
+        reader.comparison(input)
+    }
+}
+
+/// A nom parser of the well-typed [Comparison] at the start of its input,
+/// in which the given names denote variables (see the
+/// [module documentation](self)).
+pub fn comparison<'a, E: ParseError<&'a str>>(
+    variables: &HashSet<String>,
+) -> impl Fn(&'a str) -> IResult<&'a str, Comparison, E> {
+    move |input| {
+        let reader = Reader {
+            variables,
+            lenient: false,
+        };
+        reader
+            .comparison(input)
+            .ok_or_else(|| nom::Err::Error(E::from_error_kind(input, ErrorKind::Verify)))
+    }
+}
+
+/// A nom parser of the well-typed arithmetic [Expr] at the start of its
+/// input, in which the given names denote variables, and other
+/// identifiers enumerated values.
+pub fn expression<'a, E: ParseError<&'a str>>(
+    variables: &HashSet<String>,
+) -> impl Fn(&'a str) -> IResult<&'a str, Expr, E> {
+    move |input| {
+        let reader = Reader {
+            variables,
+            lenient: false,
+        };
+        reader
+            .expression(input, 0)
+            .map(|(rest, typed)| (rest, typed.expr))
+            .ok_or_else(|| nom::Err::Error(E::from_error_kind(input, ErrorKind::Verify)))
+    }
+}
+
+fn is_integer(ty: Type) -> bool {
+    matches!(ty, Type::Int | Type::Unknown)
+}
+
+/// A recursive-descent reader of expressions.
+struct Reader<'p> {
+    variables: &'p HashSet<String>,
+    /// whether all the identifiers are variables
+    lenient: bool,
+}
+
+impl Reader<'_> {
+    /// `input` starting with a comparison, which is well-typed if
+    /// `lenient` holds every identifier to be a variable
+    fn comparison<'a>(&self, input: &'a str) -> Option<(&'a str, Comparison)> {
+        let (rest, left) = self.expression(input, 0)?;
+        let Some((after_op, op)) = comparison_operator(rest) else {
+            // a boolean variable on its own
+            return match left.expr {
+                Expr::Variable(_) => Some((
+                    rest,
+                    Comparison {
+                        left: left.expr,
+                        op: ComparisonOperator::Eq,
+                        right: Expr::Constant(Value::Bool(true)),
+                    },
+                )),
+                _ => None,
+            };
+        };
+        let (rest, right) = self.expression(after_op, 0)?;
+        let comparable = match op {
+            ComparisonOperator::Eq | ComparisonOperator::Ne => {
+                left.ty == Type::Unknown || right.ty == Type::Unknown || left.ty == right.ty
+            }
+            _ => is_integer(left.ty) && is_integer(right.ty),
+        };
+        if !comparable || !(left.has_variable || right.has_variable) {
+            return None;
+        }
+        Some((
+            rest,
+            Comparison {
+                left: left.expr,
+                op,
+                right: right.expr,
+            },
+        ))
+    }
+
+    /// a sum of products
+    fn expression<'a>(&self, input: &'a str, depth: usize) -> Option<(&'a str, Typed)> {
+        self.binary(input, depth, 1)
+    }
+
+    /// operands separated by the arithmetic operators of `precedence`
+    fn binary<'a>(&self, input: &'a str, depth: usize, precedence: u8) -> Option<(&'a str, Typed)> {
+        let operand = |input| {
+            if precedence == 1 {
+                self.binary(input, depth, 2)
+            } else {
+                self.unary(input, depth)
+            }
+        };
+        let (mut rest, mut left) = operand(input)?;
+        while let Some((after_op, op)) =
+            arithmetic_operator(rest).filter(|(_, op)| op.precedence() == precedence)
+        {
+            let Some((after, right)) = operand(after_op) else {
+                break;
+            };
+            let height = left.height.max(right.height) + 1;
+            if !is_integer(left.ty) || !is_integer(right.ty) || height > MAX_EXPRESSION_DEPTH {
+                return None;
+            }
+            left = Typed {
+                expr: Expr::Arithmetic(op, Box::new(left.expr), Box::new(right.expr)),
+                ty: Type::Int,
+                has_variable: left.has_variable || right.has_variable,
+                height,
+            };
+            rest = after;
+        }
+        Some((rest, left))
+    }
+
+    fn unary<'a>(&self, input: &'a str, depth: usize) -> Option<(&'a str, Typed)> {
+        let input = input.trim_start();
+        if let Some(after) = input.strip_prefix('-')
+            && !after.starts_with('>')
+        {
+            if depth == MAX_EXPRESSION_DEPTH {
+                return None;
+            }
+            let (rest, sub) = self.unary(after, depth + 1)?;
+            if !is_integer(sub.ty) || sub.height == MAX_EXPRESSION_DEPTH {
+                return None;
+            }
+            let typed = match sub.expr {
+                // so that the minimal integer can be written
+                Expr::Constant(Value::Int(i)) => Typed {
+                    expr: Expr::Constant(Value::Int(i.checked_neg()?)),
+                    ..sub
+                },
+                expr => Typed {
+                    expr: Expr::Neg(Box::new(expr)),
+                    ty: Type::Int,
+                    height: sub.height + 1,
+                    ..sub
+                },
+            };
+            return Some((rest, typed));
+        }
+        if let Some(after) = input.strip_prefix('(') {
+            if depth == MAX_EXPRESSION_DEPTH {
+                return None;
+            }
+            let (rest, typed) = self.expression(after, depth + 1)?;
+            let rest = rest.trim_start().strip_prefix(')')?;
+            return Some((rest, typed));
+        }
+        let digits = input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len());
+        if digits > 0 {
+            let value = input[..digits].parse().ok()?;
+            let typed = Typed {
+                expr: Expr::Constant(Value::Int(value)),
+                ty: Type::Int,
+                has_variable: false,
+                height: 0,
+            };
+            return Some((&input[digits..], typed));
+        }
+        if !input.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+        let len = input
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(input.len());
+        let (name, rest) = input.split_at(len);
+        let typed = if self.lenient || self.variables.contains(name) {
+            Typed {
+                expr: Expr::Variable(name.to_string()),
+                ty: Type::Unknown,
+                has_variable: true,
+                height: 0,
+            }
+        } else if name == "true" || name == "false" {
+            Typed {
+                expr: Expr::Constant(Value::Bool(name == "true")),
+                ty: Type::Bool,
+                has_variable: false,
+                height: 0,
+            }
+        } else {
+            Typed {
+                expr: Expr::Constant(Value::Symbol(name.to_string())),
+                ty: Type::Symbol,
+                has_variable: false,
+                height: 0,
+            }
+        };
+        Some((rest, typed))
+    }
+}
+
+/// the comparison operator after the whitespace starting `input` ; not
+/// the `<=>`, `=>` or `<->` of the connectives
+fn comparison_operator(input: &str) -> Option<(&str, ComparisonOperator)> {
+    let at = input.trim_start();
+    let operators = [
+        ("==", ComparisonOperator::Eq),
+        ("!=", ComparisonOperator::Ne),
+        ("<=", ComparisonOperator::Le),
+        (">=", ComparisonOperator::Ge),
+        ("≠", ComparisonOperator::Ne),
+        ("≤", ComparisonOperator::Le),
+        ("≥", ComparisonOperator::Ge),
+        ("=", ComparisonOperator::Eq),
+        ("<", ComparisonOperator::Lt),
+        (">", ComparisonOperator::Gt),
+    ];
+    let (rest, op) = operators
+        .into_iter()
+        .find_map(|(symbol, op)| Some((at.strip_prefix(symbol)?, op)))?;
+    if rest.starts_with('>') || (op == ComparisonOperator::Lt && rest.starts_with("->")) {
+        return None;
+    }
+    Some((rest, op))
+}
+
+/// the arithmetic operator after the whitespace starting `input` ; not
+/// the `->` of the connectives
+fn arithmetic_operator(input: &str) -> Option<(&str, ArithmeticOperator)> {
+    let at = input.trim_start();
+    let op = match at.chars().next()? {
+        '+' => ArithmeticOperator::Add,
+        '-' if !at.starts_with("->") => ArithmeticOperator::Sub,
+        '*' => ArithmeticOperator::Mul,
+        '/' => ArithmeticOperator::Div,
+        '%' => ArithmeticOperator::Mod,
+        _ => return None,
+    };
+    Some((&at[1..], op))
+}
+
+impl CtlFormulaParser<Comparison> for ExpressionParser {
+    fn parse_atomic_proposition<'a, E: ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> IResult<&'a str, CTLFormula<Comparison>, E> {
+        let (rest, comparison) = comparison(&self.variables)(input)?;
+        Ok((
+            rest,
+            CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(comparison)),
+        ))
+    }
+
+    /// The first identifier which is not a variable, in an atom which
+    /// would be well-formed if it were one.
+    fn unknown_atomic_proposition<'a>(&self, input: &'a str) -> Option<(&'a str, String)> {
+        let reader = Reader {
+            variables: &self.variables,
+            lenient: true,
+        };
+        let (rest, comparison) = reader.comparison(input)?;
+        let mut unknown = None;
+        for expr in [&comparison.left, &comparison.right] {
+            first_unknown_variable(expr, &self.variables, &mut unknown);
+        }
+        Some((rest, unknown?))
+    }
+
+    fn syntax(&self) -> CtlSyntax {
+        self.syntax
+    }
+
+    /// Expressions may start with a parenthesis, as in `(x + y) < 10`.
+    fn parenthesized_atoms(&self) -> bool {
+        true
+    }
+}
+
+fn first_unknown_variable(expr: &Expr, variables: &HashSet<String>, unknown: &mut Option<String>) {
+    match expr {
+        Expr::Variable(name) if unknown.is_none() && !variables.contains(name) => {
+            *unknown = Some(name.clone());
+        }
+        Expr::Neg(sub) => first_unknown_variable(sub, variables, unknown),
+        Expr::Arithmetic(_, left, right) => {
+            first_unknown_variable(left, variables, unknown);
+            first_unknown_variable(right, variables, unknown);
+        }
+        _ => {}
+    }
+}
+
+impl CtlFormulaPrinter<Comparison> for ExpressionParser {
+    fn print_atomic_proposition(&self, comparison: &Comparison) -> String {
+        comparison.to_string()
+    }
+
+    fn syntax(&self) -> CtlSyntax {
+        self.syntax
+    }
+}
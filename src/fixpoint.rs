@@ -0,0 +1,150 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The fixpoint computations of the temporal operators, with a
+//! [FixpointStrategy](crate::fixpoint::FixpointStrategy) selectable on a
+//! [CtlModelChecker](crate::solve::CtlModelChecker) (see
+//! [CtlModelChecker::with_fixpoint_strategy](crate::solve::CtlModelChecker::with_fixpoint_strategy)).
+//!
+//! The least fixpoints (`EF`, `AF`, `EU`, `AU`) grow a set `Z` from the
+//! states satisfying the goal, and the greatest fixpoints (`EG`, `AG`)
+//! shrink a set `Z` from the states satisfying the invariant. Both
+//! strategies compute the same sets : the naive one takes the preimage
+//! of the whole of `Z` at every iteration, whereas the frontier one
+//! only takes that of the states added to (resp. removed from) `Z` by
+//! the previous iteration, where they alone decide the next ones :
+//!   - for `E[φ U ψ]` (and `EF`), the new states are the `φ` states
+//!     outside `Z` with a successor among the added ones ;
+//!   - for `AG φ`, the removed states are the states of `Z` with a
+//!     successor among the removed ones (after a first iteration over
+//!     the whole of `Z`, which removes the states with a successor
+//!     outside `φ`).
+//!
+//! For `A[φ U ψ]`, `AF` and `EG`, a state with a successor in the
+//! frontier may keep its verdict, as it depends on all its successors
+//! (resp. on whether it keeps one) : checking the candidates takes a
+//! second preimage per iteration, which costs more than it saves, so
+//! that these fixpoints are iterated naively in both strategies.
+//!
+//! The naive strategy is the default, the frontier one being opt-in :
+//! the `strategy/*` groups of `benches/bench_solve.rs` compare them on
+//! the `EF`, `EU` and `AG` fixpoints.
+
+use biodivine_lib_bdd::Bdd;
+
+use crate::bdd::{KripkeStructureBddRepresentation, PreImageKind};
+
+/// How the fixpoints of the temporal operators are iterated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FixpointStrategy {
+    /// Takes the preimage of the whole current set at every iteration.
+    #[default]
+    Naive,
+    /// Takes the preimage of the states added to (resp. removed from)
+    /// the current set by the previous iteration only, for the `EF`,
+    /// `EU` and `AG` fixpoints.
+    Frontier,
+}
+
+/// The least fixpoint of `Z = after ∨ (before ∧ pre(Z))`, `pre` being
+/// the preimage of the given `kind`.
+pub(crate) fn until_fixpoint(
+    strategy: FixpointStrategy,
+    mc: &KripkeStructureBddRepresentation,
+    kind: PreImageKind,
+    before: &Bdd,
+    after: &Bdd,
+) -> Bdd {
+    match (strategy, kind) {
+        (FixpointStrategy::Frontier, PreImageKind::Weak) => {
+            frontier_until_fixpoint(mc, before, after)
+        }
+        _ => naive_until_fixpoint(before, after, |x| {
+            mc.get_pre_image_by_transition_relation(kind, x)
+        }),
+    }
+}
+
+/// The greatest fixpoint of `Z = bdd ∧ pre(Z)`, `pre` being the
+/// preimage of the given `kind`.
+pub(crate) fn global_fixpoint(
+    strategy: FixpointStrategy,
+    mc: &KripkeStructureBddRepresentation,
+    kind: PreImageKind,
+    bdd: &Bdd,
+) -> Bdd {
+    match (strategy, kind) {
+        (FixpointStrategy::Frontier, PreImageKind::Strong) => frontier_global_fixpoint(mc, bdd),
+        _ => naive_global_fixpoint(bdd, |x| mc.get_pre_image_by_transition_relation(kind, x)),
+    }
+}
+
+fn naive_global_fixpoint(bdd: &Bdd, step_fn: impl Fn(&Bdd) -> Bdd) -> Bdd {
+    let mut current = bdd.clone();
+    loop {
+        let next = current.and(&step_fn(&current));
+        if next == current {
+            break;
+        } else {
+            current = next;
+        }
+    }
+    current
+}
+
+fn naive_until_fixpoint(before: &Bdd, after: &Bdd, step_fn: impl Fn(&Bdd) -> Bdd) -> Bdd {
+    let mut current = after.clone();
+    loop {
+        let next = current.or(&before.and(&step_fn(&current)));
+        if next == current {
+            break;
+        } else {
+            current = next;
+        }
+    }
+    current
+}
+
+/// The least fixpoint of `Z = after ∨ (before ∧ EX(Z))`.
+fn frontier_until_fixpoint(
+    mc: &KripkeStructureBddRepresentation,
+    before: &Bdd,
+    after: &Bdd,
+) -> Bdd {
+    let mut current = after.clone();
+    let mut frontier = after.clone();
+    loop {
+        let undecided = before.and_not(&current);
+        frontier = mc.get_weak_pre_image_among(&frontier, &undecided);
+        if frontier.is_false() {
+            break;
+        }
+        current = current.or(&frontier);
+    }
+    current
+}
+
+/// The greatest fixpoint of `Z = bdd ∧ AX(Z)`.
+fn frontier_global_fixpoint(mc: &KripkeStructureBddRepresentation, bdd: &Bdd) -> Bdd {
+    let mut current = bdd.clone();
+    let mut frontier =
+        current.and_not(&mc.get_pre_image_by_transition_relation(PreImageKind::Strong, &current));
+    while !frontier.is_false() {
+        current = current.and_not(&frontier);
+        frontier = mc.get_weak_pre_image_among(&frontier, &current);
+    }
+    current
+}
@@ -0,0 +1,190 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A ready-made domain for quick models : each state is labelled with the
+//! set of the names of the propositions holding on it, so that a model
+//! and its formulae can be written with strings only, without defining a
+//! domain and a type of atomic propositions :
+//!
+//! ```
+//! use std::collections::HashSet;
+//! use citreelo::labels::{label_parser, labelled_kripke};
+//! use citreelo::parser::CtlFormulaParser;
+//! use citreelo::solve::get_sat_set;
+//!
+//! let kripke = labelled_kripke([
+//!     (vec!["idle"], vec![1]),
+//!     (vec!["req"], vec![2]),
+//!     (vec!["grant", "busy"], vec![0]),
+//! ])
+//! .unwrap();
+//! let formula = label_parser()
+//!     .parse_complete_ctl_formula("AG (req => AF grant)")
+//!     .unwrap();
+//! assert_eq!(get_sat_set(&kripke, &formula), HashSet::from([0, 1, 2]));
+//! ```
+//!
+//! The parser accepts any name (see
+//! [IdentifierParser](crate::parser::identifiers::IdentifierParser)) :
+//! a proposition which labels no state holds nowhere.
+
+use std::borrow::Borrow;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::kripke::{AtomicProposition, KripkeState, KripkeStructure, KripkeStructureBuildError};
+use crate::parser::identifiers::{AtomResolver, IdentifierParser};
+
+/// The name of a proposition : cheap to clone, compared by name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Label(Arc<str>);
+
+impl Label {
+    pub fn new(name: &str) -> Self {
+        Label(Arc::from(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Label {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Interns labels, so that the labels of the same name share their
+/// allocation.
+#[derive(Debug, Clone, Default)]
+pub struct LabelInterner {
+    labels: HashSet<Label>,
+}
+
+impl LabelInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The label named `name`, shared with the previous ones of this name.
+    pub fn intern(&mut self, name: &str) -> Label {
+        if let Some(label) = self.labels.get(name) {
+            return label.clone();
+        }
+        let label = Label::new(name);
+        self.labels.insert(label.clone());
+        label
+    }
+
+    /// The set of the labels named `names`.
+    pub fn label_set<'n>(&mut self, names: impl IntoIterator<Item = &'n str>) -> LabelSet {
+        names.into_iter().map(|name| self.intern(name)).collect()
+    }
+}
+
+/// The labels of a state : the propositions holding on it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSet {
+    labels: BTreeSet<Label>,
+}
+
+impl LabelSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `label`, returning whether it was absent.
+    pub fn insert(&mut self, label: Label) -> bool {
+        self.labels.insert(label)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.labels.contains(name)
+    }
+
+    /// The labels, in the order of their names.
+    pub fn iter(&self) -> impl Iterator<Item = &Label> {
+        self.labels.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl FromIterator<Label> for LabelSet {
+    fn from_iter<I: IntoIterator<Item = Label>>(labels: I) -> Self {
+        LabelSet {
+            labels: labels.into_iter().collect(),
+        }
+    }
+}
+
+/// As `{busy, grant}`.
+impl fmt::Display for LabelSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.labels.iter().map(Label::name).collect();
+        write!(f, "{{{}}}", names.join(", "))
+    }
+}
+
+impl AtomicProposition<LabelSet> for Label {
+    fn is_satisfied_on_state_domain(&self, labels: &LabelSet) -> bool {
+        labels.contains(self.name())
+    }
+}
+
+/// Builds a [KripkeStructure] from, for each state, the names of the
+/// propositions holding on it and the ids of its successors (see
+/// [KripkeStructure::new]).
+pub fn labelled_kripke<'n, N: IntoIterator<Item = &'n str>>(
+    states: impl IntoIterator<Item = (N, Vec<usize>)>,
+) -> Result<KripkeStructure<LabelSet>, KripkeStructureBuildError> {
+    let mut interner = LabelInterner::new();
+    let states = states
+        .into_iter()
+        .map(|(names, targets)| KripkeState::new(interner.label_set(names), targets))
+        .collect();
+    KripkeStructure::new(states)
+}
+
+/// The [AtomResolver] accepting any name as a [Label].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnyLabel;
+
+impl AtomResolver<Label> for AnyLabel {
+    fn resolve(&self, name: &str) -> Option<Label> {
+        Some(Label::new(name))
+    }
+}
+
+/// A parser for CTL formulae over [Label]s, reading identifiers and
+/// quoted names as in [IdentifierParser].
+pub fn label_parser() -> IdentifierParser<AnyLabel> {
+    IdentifierParser::new(AnyLabel)
+}
@@ -21,7 +21,8 @@ limitations under the License.
 //!   are labelled with values in a user-chosen domain, on which user-defined
 //!   [atomic propositions](kripke::AtomicProposition) are evaluated.
 //! - Properties are [CTL formulae](ctl::CTLFormula), built directly as an
-//!   AST or parsed from a concrete syntax (see [parser]).
+//!   AST or parsed from a concrete syntax (see [parser]), and printed
+//!   back (see [printer]).
 //! - Checking is symbolic : sets of states are manipulated as reduced
 //!   ordered binary decision diagrams (via
 //!   [biodivine-lib-bdd](https://docs.rs/biodivine-lib-bdd)), and all CTL
@@ -32,6 +33,10 @@ limitations under the License.
 //! many) and the one-shot functions [solve::get_sat_set] /
 //! [solve::is_ctl_formula_sat].
 //!
+//! Independently of any model, [satisfiability::check_ctl_satisfiability]
+//! and [satisfiability::check_ctl_validity] detect contradictory and
+//! trivially true specifications.
+//!
 //! # Example
 //!
 //! ```
@@ -110,16 +115,40 @@ limitations under the License.
 //! assert_eq!(checker.get_sat_set(&psi), HashSet::from([0, 1, 2]));
 //! ```
 
-/// the BDD encoding of Kripke structures (internal)
+/// hash-consed CTL formulae
+pub mod arena;
+/// checking many formulae at once, with shared evaluation
+pub mod batch;
+/// the BDD encoding of Kripke structures (internal), and the partitioning of its transition relation
 pub mod bdd;
+/// the satisfaction sets kept by a checker across queries
+pub mod cache;
 /// the CTL formula AST
 pub mod ctl;
+/// atomic propositions comparing the variables of the states
+pub mod expressions;
+/// the fixpoint computations of the temporal operators, and their strategies
+pub mod fixpoint;
 /// Kripke structures and their validating constructor
 pub mod kripke;
+/// a ready-made domain of states labelled by sets of proposition names
+pub mod labels;
 /// a concrete syntax for CTL formulae, with operator precedence
 pub mod parser;
+/// atomic propositions given as named closures over the domain
+pub mod predicates;
+/// printing CTL formulae in the concrete syntax of the parser
+pub mod printer;
+/// model-independent satisfiability and validity checking of CTL formulae
+pub mod satisfiability;
 /// the model-checking algorithms
 pub mod solve;
+/// specification files of named properties, and their batch checking
+pub mod spec;
+/// formula templates quantified over ranges of indices
+pub mod templates;
+/// vacuity detection for satisfied properties
+pub mod vacuity;
 
 /// visualization helpers (Graphviz rendering of Kripke structures)
 pub mod util;
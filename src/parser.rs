@@ -14,9 +14,14 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-//! A recursive-descent parser for CTL formulae with the usual operator
-//! precedences, so that e.g. `AG (p => EF q)` can be written without
-//! fully parenthesizing every sub-formula.
+//! An operator-precedence parser for CTL formulae with the usual
+//! operator precedences, so that e.g. `AG (p => EF q)` can be written
+//! without fully parenthesizing every sub-formula.
+//!
+//! The parser keeps its pending constructs on an explicit work stack
+//! rather than recursing, so that deeply nested formulae (e.g. generated
+//! ones) cannot overflow the native stack ; their depth is instead
+//! bounded by [CtlFormulaParser::max_nesting_depth](crate::parser::CtlFormulaParser::max_nesting_depth).
 //!
 //! # Grammar
 //!
@@ -31,6 +36,11 @@ limitations under the License.
 //! | 5     | `!`, `AX`, `EX`, `AF`, `EF`, `AG`, `EG`| prefix        |
 //! | 6     | atoms, `true`, `false`, `(φ)`, `A[φ U ψ]`, `E[φ U ψ]` | |
 //!
+//! A set of states given by their ids, as `{0, 2}`, is an atom as well
+//! (see [CTLFormulaLeaf::States](crate::ctl::CTLFormulaLeaf::States)),
+//! if the parser enables them (see
+//! [CtlFormulaParser::state_set_literals](crate::parser::CtlFormulaParser::state_set_literals)).
+//!
 //! Prefix operators chain (`AG EF p`, `!AX !p`) and bind tighter than
 //! the binary connectives: `AX p & q` reads as `(AX p) & q`.
 //! The until operators use the classic bracket notation `A[φ U ψ]` /
@@ -42,6 +52,17 @@ limitations under the License.
 //! named exactly like a keyword are shadowed wherever the grammar
 //! expects that keyword, and should be avoided.
 //!
+//! The table above uses the default spellings of the boolean constants
+//! and connectives : other notations (Unicode, C-style, NuSMV) are
+//! available as [syntax profiles](crate::parser::syntax), chosen by
+//! [CtlFormulaParser::syntax](crate::parser::CtlFormulaParser::syntax).
+//!
+//! Sub-formulae may be named and used by name, with `let` bindings
+//! (`let ok = idle & !busy in AG (ok => EF ok)`), if the parser enables
+//! them (see [CtlFormulaParser::let_bindings](crate::parser::CtlFormulaParser::let_bindings)),
+//! or with definitions shared by several formulae : see
+//! [macros](crate::parser::macros).
+//!
 //! Atomic propositions themselves are parsed by the user-provided
 //! [CtlFormulaParser::parse_atomic_proposition](crate::parser::CtlFormulaParser::parse_atomic_proposition).
 //!
@@ -56,12 +77,28 @@ use nom::{
     IResult, Parser,
     bytes::complete::tag,
     character::complete::{char as nom_char, multispace0},
-    combinator::cut,
     error::{ErrorKind, ParseError},
 };
 
 use crate::ctl::*;
 
+pub mod diagnostics;
+pub mod identifiers;
+pub mod macros;
+pub mod spans;
+pub mod syntax;
+
+use diagnostics::{
+    CtlDiagnostic, Expectation, Issue, hint_for, misspelled_operator_hint,
+    parenthesized_until_hint, token_len,
+};
+use macros::{
+    Binding, CtlDefinitions, DefinitionError, MAX_EXPANSION_DEPTH, Scope, Target,
+    parse_definition_head, parse_identifier, split_arguments,
+};
+use spans::{CtlSpans, SpanBuilder};
+use syntax::{Connective, CtlSyntax};
+
 /// The reasons for which [CtlFormulaParser::parse_complete_ctl_formula]
 /// may reject its input. Offsets are byte offsets into the input string.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -70,6 +107,31 @@ pub enum CtlParseError {
     SyntaxError { offset: usize, near: String },
     /// a formula was recognized but it does not span the whole input
     TrailingInput { offset: usize, near: String },
+    /// the formula nests more than `limit` operators (see
+    /// [CtlFormulaParser::max_nesting_depth]) ; the offset is that of the
+    /// operator exceeding the limit
+    NestingTooDeep { offset: usize, limit: usize },
+    /// a named formula (see [macros]) is used but neither bound by a
+    /// `let` nor defined
+    UndefinedName { offset: usize, name: String },
+    /// a named formula is used with the wrong number of arguments
+    WrongArgumentCount {
+        offset: usize,
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// a definition uses itself, through the definitions of `cycle`
+    /// (which starts and ends with the same name)
+    CyclicDefinition { offset: usize, cycle: Vec<String> },
+    /// the body of a definition used at this offset is not a valid formula
+    InvalidDefinition { offset: usize, name: String },
+    /// named formulae are expanded within each other more than `limit`
+    /// times (see [macros::MAX_EXPANSION_DEPTH])
+    ExpansionTooDeep { offset: usize, limit: usize },
+    /// a well-formed but unknown atomic proposition (see
+    /// [CtlFormulaParser::unknown_atomic_proposition])
+    UnknownAtom { offset: usize, name: String },
 }
 
 impl fmt::Display for CtlParseError {
@@ -85,16 +147,61 @@ impl fmt::Display for CtlParseError {
                     offset, near
                 )
             }
+            CtlParseError::NestingTooDeep { offset, limit } => {
+                write!(
+                    f,
+                    "the CTL formula nests more than {} operators at offset {}",
+                    limit, offset
+                )
+            }
+            CtlParseError::UndefinedName { offset, name } => {
+                write!(f, "`{}` is not defined, at offset {}", name, offset)
+            }
+            CtlParseError::WrongArgumentCount {
+                offset,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument(s) but {} were given, at offset {}",
+                name, expected, found, offset
+            ),
+            CtlParseError::CyclicDefinition { offset, cycle } => write!(
+                f,
+                "`{}` is defined in terms of itself ({}), at offset {}",
+                cycle[0],
+                cycle.join(" -> "),
+                offset
+            ),
+            CtlParseError::InvalidDefinition { offset, name } => write!(
+                f,
+                "the definition of `{}` used at offset {} is not a valid formula",
+                name, offset
+            ),
+            CtlParseError::ExpansionTooDeep { offset, limit } => write!(
+                f,
+                "named formulae are nested more than {} times at offset {}",
+                limit, offset
+            ),
+            CtlParseError::UnknownAtom { offset, name } => write!(
+                f,
+                "unknown atomic proposition `{}` at offset {}",
+                name, offset
+            ),
         }
     }
 }
 
 impl std::error::Error for CtlParseError {}
 
-fn error_snippet(rest: &str) -> String {
+pub(crate) fn error_snippet(rest: &str) -> String {
     rest.chars().take(24).collect()
 }
 
+/// The default value of [CtlFormulaParser::max_nesting_depth].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 10_000;
+
 /// A parser for CTL formulae over atomic propositions of type `AP`.
 ///
 /// Implementors only provide [Self::parse_atomic_proposition]; the
@@ -107,6 +214,62 @@ pub trait CtlFormulaParser<AP>: Sized {
         input: &'a str,
     ) -> IResult<&'a str, CTLFormula<AP>, E>;
 
+    /// The maximal nesting depth of the formulae accepted by the parser,
+    /// i.e., the maximal number of nodes on a branch of their syntax
+    /// tree (an atomic proposition counting for one node) ; deeper
+    /// formulae are rejected with [CtlParseError::NestingTooDeep].
+    ///
+    /// Parsing itself does not recurse, whatever the depth, but the
+    /// derived implementations of `Drop`, `Clone`, `PartialEq`, ... on
+    /// [CTLFormula] do : the default limit keeps them well within the
+    /// default stack size of a thread.
+    fn max_nesting_depth(&self) -> usize {
+        DEFAULT_MAX_NESTING_DEPTH
+    }
+
+    /// When [Self::parse_atomic_proposition] fails on `input` : the name
+    /// of the atomic proposition starting `input` if it is well-formed
+    /// but unknown (e.g. undeclared), with the rest of the input, so that
+    /// it is reported as [CtlParseError::UnknownAtom] rather than as a
+    /// syntax error. The default recognizes no such name.
+    fn unknown_atomic_proposition<'a>(&self, input: &'a str) -> Option<(&'a str, String)> {
+        let _ = input;
+        None
+    }
+
+    /// The spellings of the boolean constants and connectives accepted
+    /// by the parser (see [syntax]) ; the default is
+    /// [CtlSyntax::Citreelo].
+    fn syntax(&self) -> CtlSyntax {
+        CtlSyntax::default()
+    }
+
+    /// Whether formulae may name their sub-formulae with `let` bindings
+    /// (see [macros]), `let` and `in` being keywords ; the default is
+    /// `false`, so that atomic propositions may be named `let` or `in`.
+    ///
+    /// Bindings are always allowed when parsing with definitions (see
+    /// [Self::parse_complete_ctl_formula_with_definitions]), and in
+    /// [specification files](crate::spec).
+    fn let_bindings(&self) -> bool {
+        false
+    }
+
+    /// Whether atomic propositions may start with a parenthesis, as
+    /// `(x + y) < 10` does : [Self::parse_atomic_proposition] is then
+    /// tried first wherever a `(` may open a sub-formula. The default is
+    /// `false`, so that a parenthesis always opens a sub-formula.
+    fn parenthesized_atoms(&self) -> bool {
+        false
+    }
+
+    /// Whether sets of state ids, as `{0, 2}`, are read as
+    /// [CTLFormulaLeaf::States] leaves, as they are printed ; the default
+    /// is `false`, so that atomic propositions may start with `{`.
+    fn state_set_literals(&self) -> bool {
+        false
+    }
+
     /// Combinator-style parser: parses the longest formula at the start
     /// of `input` (leading whitespace allowed) and returns the rest.
     ///
@@ -116,35 +279,139 @@ pub trait CtlFormulaParser<AP>: Sized {
         &self,
         input: &'a str,
     ) -> IResult<&'a str, CTLFormula<AP>, E> {
-        parse_iff_level(self, input)
+        self.parse_ctl_formula_with(&mut CTLFormulaTreeBuilder, input)
+    }
+
+    /// As [Self::parse_ctl_formula], but the formula is produced by
+    /// `builder`, e.g. directly as an id in a
+    /// [CTLFormulaArena](crate::arena::CTLFormulaArena).
+    ///
+    /// On failure, the nodes already produced by `builder` are not
+    /// reclaimed.
+    fn parse_ctl_formula_with<'a, B: CTLFormulaBuilder<AP>, E: ParseError<&'a str>>(
+        &self,
+        builder: &mut B,
+        input: &'a str,
+    ) -> IResult<&'a str, B::Formula, E> {
+        parse_formula(self, builder, input, None, &mut Scope::new(input, None))
+            .map(|(rem, operand)| (rem, operand.formula))
+            .map_err(|failure| match failure {
+                ParseFailure::Nom(e) => e,
+                ParseFailure::NestingTooDeep { at } => {
+                    nom::Err::Failure(E::from_error_kind(at, ErrorKind::TooLarge))
+                }
+                ParseFailure::Definition { at, .. } => {
+                    nom::Err::Failure(E::from_error_kind(at, ErrorKind::Verify))
+                }
+                // as for the other atoms, an enclosing combinator may try
+                // something else
+                ParseFailure::UnknownAtom { at, .. } => {
+                    nom::Err::Error(E::from_error_kind(at, ErrorKind::Verify))
+                }
+            })
     }
 
     /// Parses `input` as one CTL formula spanning the whole string
     /// (modulo surrounding whitespace).
     fn parse_complete_ctl_formula(&self, input: &str) -> Result<CTLFormula<AP>, CtlParseError> {
-        match self.parse_ctl_formula::<nom::error::Error<&str>>(input) {
-            Ok((rem, phi)) => {
-                let trailing = rem.trim_start();
-                if trailing.is_empty() {
-                    Ok(phi)
-                } else {
-                    Err(CtlParseError::TrailingInput {
-                        offset: input.len() - trailing.len(),
-                        near: error_snippet(trailing),
-                    })
-                }
-            }
-            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
-                Err(CtlParseError::SyntaxError {
-                    offset: input.len() - e.input.len(),
-                    near: error_snippet(e.input),
+        self.parse_complete_ctl_formula_with(&mut CTLFormulaTreeBuilder, input)
+    }
+
+    /// Parses `input` as one CTL formula spanning the whole string, as
+    /// [Self::parse_complete_ctl_formula], but reports errors for human
+    /// readers : the parser recovers after each syntax error so as to
+    /// report all of them, located by line and column, with the expected
+    /// tokens and hints for common mistakes (see [diagnostics]).
+    fn parse_ctl_formula_with_diagnostics(
+        &self,
+        input: &str,
+    ) -> Result<CTLFormula<AP>, Vec<CtlDiagnostic>> {
+        parse_formula_with_diagnostics(
+            self,
+            &mut CTLFormulaTreeBuilder,
+            input,
+            Scope::new(input, None),
+        )
+    }
+
+    /// As [Self::parse_complete_ctl_formula], but the formula is
+    /// produced by `builder` (see [Self::parse_ctl_formula_with]).
+    fn parse_complete_ctl_formula_with<B: CTLFormulaBuilder<AP>>(
+        &self,
+        builder: &mut B,
+        input: &str,
+    ) -> Result<B::Formula, CtlParseError> {
+        parse_complete_formula(self, builder, input, None)
+    }
+
+    /// As [Self::parse_complete_ctl_formula], where the named formulae
+    /// of `definitions` may also be used (see [macros]) : e.g., with
+    /// `resp(a, b)` defined as `AG (a => AF b)`, `resp(p, q) & EF p`
+    /// parses as `AG (p => AF q) & EF p`.
+    fn parse_complete_ctl_formula_with_definitions(
+        &self,
+        definitions: &CtlDefinitions,
+        input: &str,
+    ) -> Result<CTLFormula<AP>, CtlParseError> {
+        parse_complete_formula(self, &mut CTLFormulaTreeBuilder, input, Some(definitions))
+    }
+
+    /// As [Self::parse_complete_ctl_formula], along with the byte ranges
+    /// of `input` from which its sub-formulae were read (see [spans]).
+    fn parse_complete_ctl_formula_with_spans(
+        &self,
+        input: &str,
+    ) -> Result<(CTLFormula<AP>, CtlSpans), CtlParseError> {
+        parse_complete_formula(self, &mut SpanBuilder, input, None).map(SpanBuilder::finish)
+    }
+}
+
+fn parse_complete_formula<AP, P: CtlFormulaParser<AP>, B: CTLFormulaBuilder<AP>>(
+    formula_parser: &P,
+    builder: &mut B,
+    input: &str,
+    definitions: Option<&CtlDefinitions>,
+) -> Result<B::Formula, CtlParseError> {
+    let mut scope = Scope::new(input, definitions);
+    // positions may be in the arguments of a named formula, which are
+    // not suffixes of the input
+    let offset = |at: &str| at.as_ptr() as usize - input.as_ptr() as usize;
+    match parse_formula::<AP, P, B, nom::error::Error<&str>>(
+        formula_parser,
+        builder,
+        input,
+        None,
+        &mut scope,
+    ) {
+        Ok((rem, phi)) => {
+            let trailing = rem.trim_start();
+            if trailing.is_empty() {
+                Ok(phi.formula)
+            } else {
+                Err(CtlParseError::TrailingInput {
+                    offset: offset(trailing),
+                    near: error_snippet(trailing),
                 })
             }
-            Err(nom::Err::Incomplete(_)) => Err(CtlParseError::SyntaxError {
-                offset: input.len(),
-                near: String::new(),
-            }),
         }
+        Err(ParseFailure::NestingTooDeep { at }) => Err(CtlParseError::NestingTooDeep {
+            offset: offset(at),
+            limit: formula_parser.max_nesting_depth(),
+        }),
+        Err(ParseFailure::Definition { at, error }) => Err(error.into_parse_error(offset(at))),
+        Err(ParseFailure::UnknownAtom { at, name }) => Err(CtlParseError::UnknownAtom {
+            offset: offset(at),
+            name,
+        }),
+        Err(ParseFailure::Nom(nom::Err::Error(e)))
+        | Err(ParseFailure::Nom(nom::Err::Failure(e))) => Err(CtlParseError::SyntaxError {
+            offset: offset(e.input),
+            near: error_snippet(e.input),
+        }),
+        Err(ParseFailure::Nom(nom::Err::Incomplete(_))) => Err(CtlParseError::SyntaxError {
+            offset: input.len(),
+            near: String::new(),
+        }),
     }
 }
 
@@ -166,81 +433,153 @@ fn parse_keyword<'a, E: ParseError<&'a str>>(
     }
 }
 
-/// level 1 : `<=>`, left-associative
-fn parse_iff_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
-    formula_parser: &P,
+/// matches one of the `spellings` of a token of the current syntax
+/// profile : words up to a word boundary (see [parse_keyword]), symbols
+/// as is
+fn parse_spelling<'a, E: ParseError<&'a str>>(
+    spellings: &[&str],
     input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (mut rem, mut formula) = parse_imply_level(formula_parser, input)?;
-    while let Ok((after_op, _)) = (multispace0::<&'a str, E>, tag("<=>")).parse(rem) {
-        let (after_rhs, rhs) = parse_imply_level(formula_parser, after_op)?;
-        formula = CTLFormula::Binary(BinaryCTLOperator::Iff, Box::new(formula), Box::new(rhs));
-        rem = after_rhs;
+) -> Option<&'a str> {
+    spellings.iter().find_map(|spelling| {
+        if spelling.starts_with(char::is_alphabetic) {
+            parse_keyword::<E>(spelling, input).ok().map(|(rem, _)| rem)
+        } else {
+            input.strip_prefix(spelling)
+        }
+    })
+}
+
+/// matches a set of state ids, as `{0, 2}`, as printed for a
+/// [CTLFormulaLeaf::States] leaf
+fn parse_state_set(input: &str) -> Option<(&str, StateSet)> {
+    let (body, after) = input.strip_prefix('{')?.split_once('}')?;
+    let body = body.trim();
+    if body.is_empty() {
+        return Some((after, StateSet::default()));
     }
-    Ok((rem, formula))
+    let states = body
+        .split(',')
+        .map(|id| id.trim().parse::<usize>().ok())
+        .collect::<Option<StateSet>>()?;
+    Some((after, states))
 }
 
-/// level 2 : `=>`, right-associative
-fn parse_imply_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
-    formula_parser: &P,
-    input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (rem, lhs) = parse_or_level(formula_parser, input)?;
-    // NB: on "<=>" the tag below fails (it starts with '<'),
-    // so iff/imply do not steal each other's operator
-    if let Ok((after_op, _)) = (multispace0::<&'a str, E>, tag("=>")).parse(rem) {
-        let (after_rhs, rhs) = parse_imply_level(formula_parser, after_op)?;
-        Ok((
-            after_rhs,
-            CTLFormula::Binary(BinaryCTLOperator::Imply, Box::new(lhs), Box::new(rhs)),
-        ))
-    } else {
-        Ok((rem, lhs))
+/// A formula under construction, with its nesting depth.
+struct Operand<F> {
+    formula: F,
+    depth: usize,
+}
+
+/// An operand and the input after it.
+type ParsedOperand<'a, F> = (&'a str, Operand<F>);
+
+/// A construct opened on the parser's work stack, whose operands are
+/// still being parsed. Positions are the input at the operator, for
+/// error reporting.
+enum Frame<'a, F> {
+    /// a prefix operator `!`, `AX`, ..., waiting for its operand
+    Prefix(UnaryCTLOperator, &'a str),
+    /// a binary connective and its left operand, which starts at the
+    /// first position, waiting for the right one
+    Infix(Connective, Operand<F>, &'a str, &'a str),
+    /// an opening parenthesis
+    Paren(&'a str),
+    /// `A[` / `E[`, waiting for the left operand of the until, which is
+    /// closed by the given delimiter (`]`, or `)` when recovering from
+    /// `A(φ U ψ)`)
+    UntilLeft(BinaryCTLOperator, &'a str, char),
+    /// `A[φ U` / `E[φ U`, waiting for the right operand
+    UntilRight(BinaryCTLOperator, Operand<F>, &'a str, char),
+    /// `let name(params) =`, waiting for the body of the binding, which
+    /// starts at `body` and is closed by `in` ; the parameters are bound
+    /// while parsing it, above the `outer` binding and up to index `bound`
+    LetBody {
+        at: &'a str,
+        name: &'a str,
+        params: Vec<&'a str>,
+        body: &'a str,
+        outer: Option<usize>,
+        bound: usize,
+        /// the number of issues recorded before the body, if recovering
+        issues: usize,
+    },
+    /// `let ... in`, waiting for the formula in the scope of the binding,
+    /// which is unbound when it ends
+    Let {
+        at: &'a str,
+        outer: Option<usize>,
+        bound: usize,
+    },
+}
+
+enum ParseFailure<'a, E> {
+    Nom(nom::Err<E>),
+    NestingTooDeep {
+        at: &'a str,
+    },
+    /// at the use of a named formula
+    Definition {
+        at: &'a str,
+        error: DefinitionError,
+    },
+    /// see [CtlFormulaParser::unknown_atomic_proposition]
+    UnknownAtom {
+        at: &'a str,
+        name: String,
+    },
+}
+
+/// the failures of the expansion of named formulae, which are parsed
+/// with the concrete error type so as to locate them
+type ExpansionFailure<'a> = ParseFailure<'a, nom::error::Error<&'a str>>;
+
+impl<E> From<nom::Err<E>> for ParseFailure<'_, E> {
+    fn from(e: nom::Err<E>) -> Self {
+        ParseFailure::Nom(e)
     }
 }
 
-/// level 3 : `|`, left-associative
-fn parse_or_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
-    formula_parser: &P,
-    input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (mut rem, mut formula) = parse_and_level(formula_parser, input)?;
-    while let Ok((after_op, _)) = (multispace0::<&'a str, E>, nom_char('|')).parse(rem) {
-        let (after_rhs, rhs) = parse_and_level(formula_parser, after_op)?;
-        formula = CTLFormula::Binary(BinaryCTLOperator::Or, Box::new(formula), Box::new(rhs));
-        rem = after_rhs;
+/// errors past the point where only a formula can follow (inside
+/// parentheses, after a prefix operator or after `A[` / `E[`) are not
+/// recoverable by an enclosing combinator
+fn committed<E>(e: nom::Err<E>) -> nom::Err<E> {
+    match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        other => other,
     }
-    Ok((rem, formula))
 }
 
-/// level 4 : `&`, left-associative
-fn parse_and_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
-    formula_parser: &P,
-    input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (mut rem, mut formula) = parse_unary_level(formula_parser, input)?;
-    while let Ok((after_op, _)) = (multispace0::<&'a str, E>, nom_char('&')).parse(rem) {
-        let (after_rhs, rhs) = parse_unary_level(formula_parser, after_op)?;
-        formula = CTLFormula::Binary(BinaryCTLOperator::And, Box::new(formula), Box::new(rhs));
-        rem = after_rhs;
+/// the binding power of the binary connectives (higher binds tighter),
+/// and whether they associate to the left
+pub(crate) fn binding_power(op: &BinaryCTLOperator) -> (u8, bool) {
+    match op {
+        BinaryCTLOperator::Iff => (1, true),
+        BinaryCTLOperator::Imply => (2, false),
+        BinaryCTLOperator::Or => (3, true),
+        BinaryCTLOperator::And => (4, true),
+        BinaryCTLOperator::AU | BinaryCTLOperator::EU => {
+            unreachable!("untils are bracketed, not infix")
+        }
     }
-    Ok((rem, formula))
 }
 
-/// level 5 : the prefix operators `!`, `AX`, `EX`, `AF`, `EF`, `AG`,
-/// `EG`, plus the bracketed `A[φ U ψ]` / `E[φ U ψ]`
-fn parse_unary_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
-    formula_parser: &P,
+/// as [binding_power], for the connectives of all the syntax profiles :
+/// `xor` and `xnor` bind as `|`
+fn connective_binding_power(connective: Connective) -> (u8, bool) {
+    match connective {
+        Connective::Xor | Connective::Xnor => binding_power(&BinaryCTLOperator::Or),
+        other => binding_power(&other.operator()),
+    }
+}
+
+/// the opening of a construct : a prefix operator, a parenthesis, or
+/// the `A[` / `E[` of an until
+fn parse_opening<'a, F, E: ParseError<&'a str>>(
+    syntax: CtlSyntax,
     input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (input, _) = multispace0(input)?;
-    // ***
-    if let Ok((rem, _)) = nom_char::<&'a str, E>('!').parse(input) {
-        let (rem, sub_phi) = cut(|i| parse_unary_level(formula_parser, i)).parse(rem)?;
-        return Ok((
-            rem,
-            CTLFormula::Unary(UnaryCTLOperator::Not, Box::new(sub_phi)),
-        ));
+) -> Option<(&'a str, Frame<'a, F>)> {
+    if let Some(rem) = parse_spelling::<E>(syntax.not_spellings(), input) {
+        return Some((rem, Frame::Prefix(UnaryCTLOperator::Not, input)));
     }
     // ***
     let unary_temporal_keywords = [
@@ -253,64 +592,970 @@ fn parse_unary_level<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
     ];
     for (keyword, operator) in unary_temporal_keywords {
         if let Ok((rem, _)) = parse_keyword::<E>(keyword, input) {
-            let (rem, sub_phi) = cut(|i| parse_unary_level(formula_parser, i)).parse(rem)?;
-            return Ok((rem, CTLFormula::Unary(operator, Box::new(sub_phi))));
+            return Some((rem, Frame::Prefix(operator, input)));
         }
     }
     // ***
+    if let Some((rem, operator, _)) = parse_until_opening::<E>(input, '[') {
+        return Some((rem, Frame::UntilLeft(operator, input, ']')));
+    }
+    // a bare "A" / "E" without '[' may still be an atomic proposition
+    // ***
+    if let Ok((rem, _)) = nom_char::<&'a str, E>('(').parse(input) {
+        return Some((rem, Frame::Paren(input)));
+    }
+    None
+}
+
+/// `A` or `E` followed by the `bracket` opening the until, which is
+/// returned with its position
+fn parse_until_opening<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    bracket: char,
+) -> Option<(&'a str, BinaryCTLOperator, &'a str)> {
     let until_keywords = [("A", BinaryCTLOperator::AU), ("E", BinaryCTLOperator::EU)];
-    for (keyword, operator) in until_keywords {
-        if let Ok((rem, _)) = parse_keyword::<E>(keyword, input)
-            && let Ok((rem, _)) = (multispace0::<&'a str, E>, nom_char('[')).parse(rem)
-        {
-            // beyond "A[" / "E[" this can only be an until : commit
-            let (rem, (phi1, phi2)) = cut(|i| parse_until_body(formula_parser, i)).parse(rem)?;
-            return Ok((
-                rem,
-                CTLFormula::Binary(operator, Box::new(phi1), Box::new(phi2)),
-            ));
+    until_keywords.into_iter().find_map(|(keyword, operator)| {
+        let (rem, _) = parse_keyword::<E>(keyword, input).ok()?;
+        let (at, _) = multispace0::<&'a str, E>(rem).ok()?;
+        let (rem, _) = nom_char::<&'a str, E>(bracket).parse(at).ok()?;
+        Some((rem, operator, at))
+    })
+}
+
+/// the binary connective following an operand, if any
+fn parse_infix<'a, E: ParseError<&'a str>>(
+    syntax: CtlSyntax,
+    input: &'a str,
+) -> Option<(&'a str, Connective, &'a str)> {
+    let (at, _) = multispace0::<&'a str, E>(input).ok()?;
+    // NB: a spelling comes before the shorter ones it starts with, and
+    // "=>" does not match "<=>" (it starts with '<'), so that connectives
+    // do not steal each other's spelling
+    syntax
+        .connectives()
+        .iter()
+        .find_map(|(spelling, connective)| {
+            parse_spelling::<E>(&[spelling], at).map(|rem| (rem, *connective, at))
+        })
+}
+
+/// when recovering : a binary connective of another syntax profile (e.g.
+/// `->` in the default one), recorded as an error and read as its
+/// counterpart
+fn parse_misspelled_infix<'a>(
+    syntax: CtlSyntax,
+    input: &'a str,
+    issues: &mut Vec<Issue<'a>>,
+) -> Option<(&'a str, Connective, &'a str)> {
+    let at = input.trim_start();
+    let (misspelled, connective) = syntax.foreign_connective_at(at)?;
+    let correct = syntax.connective_spelling(&connective.operator());
+    record(
+        issues,
+        Issue::Unexpected {
+            at,
+            expected: Expectation::End,
+            hint: Some(misspelled_operator_hint(misspelled, correct)),
+        },
+    );
+    Some((&at[misspelled.len()..], connective, at))
+}
+
+/// records `issue`, unless an error was already reported at the same
+/// position (recovery may fail several times at the same token)
+fn record<'a>(issues: &mut Vec<Issue<'a>>, issue: Issue<'a>) {
+    if issues
+        .last()
+        .is_none_or(|last| last.at().as_ptr() != issue.at().as_ptr())
+    {
+        issues.push(issue);
+    }
+}
+
+fn build_unary<'a, AP, B: CTLFormulaBuilder<AP>, E>(
+    builder: &mut B,
+    limit: usize,
+    op: UnaryCTLOperator,
+    sub: Operand<B::Formula>,
+    at: &'a str,
+) -> Result<Operand<B::Formula>, ParseFailure<'a, E>> {
+    let depth = sub.depth + 1;
+    if depth > limit {
+        return Err(ParseFailure::NestingTooDeep { at });
+    }
+    Ok(Operand {
+        formula: builder.build_unary(op, sub.formula),
+        depth,
+    })
+}
+
+/// builds the node(s) of `left connective right`, `xor` adding a
+/// negation
+fn build_connective<'a, AP, B: CTLFormulaBuilder<AP>, E>(
+    builder: &mut B,
+    limit: usize,
+    connective: Connective,
+    left: Operand<B::Formula>,
+    right: Operand<B::Formula>,
+    at: &'a str,
+) -> Result<Operand<B::Formula>, ParseFailure<'a, E>> {
+    let built = build_binary(builder, limit, connective.operator(), left, right, at)?;
+    if connective.is_negated() {
+        build_unary(builder, limit, UnaryCTLOperator::Not, built, at)
+    } else {
+        Ok(built)
+    }
+}
+
+fn build_binary<'a, AP, B: CTLFormulaBuilder<AP>, E>(
+    builder: &mut B,
+    limit: usize,
+    op: BinaryCTLOperator,
+    left: Operand<B::Formula>,
+    right: Operand<B::Formula>,
+    at: &'a str,
+) -> Result<Operand<B::Formula>, ParseFailure<'a, E>> {
+    let depth = left.depth.max(right.depth) + 1;
+    if depth > limit {
+        return Err(ParseFailure::NestingTooDeep { at });
+    }
+    Ok(Operand {
+        formula: builder.build_binary(op, left.formula, right.formula),
+        depth,
+    })
+}
+
+/// reports to `builder` the text of `operand`, from `start` to `end`,
+/// unless it is not part of `source` (e.g. in the body of a definition)
+fn with_span<'a, AP, B: CTLFormulaBuilder<AP>>(
+    builder: &mut B,
+    source: &'a str,
+    operand: Operand<B::Formula>,
+    start: &'a str,
+    end: &'a str,
+) -> Operand<B::Formula> {
+    let base = source.as_ptr() as usize;
+    let (start, end) = (start.as_ptr() as usize, end.as_ptr() as usize);
+    if base <= start && start <= end && end <= base + source.len() {
+        Operand {
+            formula: builder.with_span(operand.formula, start - base..end - base),
+            ..operand
         }
-        // a bare "A" / "E" without '[' may still be an atomic
-        // proposition : fall through to the primary level
+    } else {
+        operand
     }
-    // ***
-    parse_primary(formula_parser, input)
 }
 
-/// the part after `A[` / `E[` : `φ U ψ ]`
-fn parse_until_body<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
+/// Parses the longest formula at the start of `input`, by operator
+/// precedence over an explicit work stack (see [Frame]) : the native
+/// stack does not grow with the nesting depth of the formula.
+///
+/// With `issues`, syntax errors are recorded there instead of failing :
+/// the parser then recovers by skipping unexpected tokens, closing
+/// unclosed constructs and standing in `true` for missing operands, and
+/// consumes the whole input.
+///
+/// Named formulae are looked up in `scope`, and their uses expanded by
+/// parsing their bodies again (see [parse_named_use]) : only this
+/// expansion recurses, up to [MAX_EXPANSION_DEPTH].
+fn parse_formula<
+    'a,
+    AP,
+    P: CtlFormulaParser<AP>,
+    B: CTLFormulaBuilder<AP>,
+    E: ParseError<&'a str>,
+>(
     formula_parser: &P,
+    builder: &mut B,
     input: &'a str,
-) -> IResult<&'a str, (CTLFormula<AP>, CTLFormula<AP>), E> {
-    let (rem, phi1) = parse_iff_level(formula_parser, input)?;
-    let (rem, _) = multispace0(rem)?;
-    let (rem, _) = parse_keyword::<E>("U", rem)?;
-    let (rem, phi2) = parse_iff_level(formula_parser, rem)?;
-    let (rem, _) = multispace0(rem)?;
-    let (rem, _) = nom_char(']').parse(rem)?;
-    Ok((rem, (phi1, phi2)))
-}
-
-/// level 6 : `true`, `false`, parenthesized formulae and the
-/// user-provided atomic propositions
-fn parse_primary<'a, AP, P: CtlFormulaParser<AP>, E: ParseError<&'a str>>(
+    mut issues: Option<&mut Vec<Issue<'a>>>,
+    scope: &mut Scope<'a>,
+) -> Result<ParsedOperand<'a, B::Formula>, ParseFailure<'a, E>> {
+    let limit = formula_parser.max_nesting_depth();
+    let allows_let = scope.definitions.is_some() || formula_parser.let_bindings();
+    let syntax = formula_parser.syntax();
+    let mut frames: Vec<Frame<'a, B::Formula>> = Vec::new();
+    let mut rem = input;
+    'operand: loop {
+        // *** expecting an operand : open constructs until a leaf
+        let (input, _) = multispace0(rem)?;
+        // an atomic proposition may start with a parenthesis, as in
+        // `(x + y) < 10`, which otherwise opens a sub-formula
+        let leading_atom = if formula_parser.parenthesized_atoms() && input.starts_with('(') {
+            formula_parser.parse_atomic_proposition::<E>(input).ok()
+        } else {
+            None
+        };
+        let opening = match leading_atom {
+            Some(_) => None,
+            None => parse_opening::<B::Formula, E>(syntax, input),
+        }
+        .or_else(|| parse_misspelled_opening::<_, E>(syntax, input, issues.as_deref_mut()?));
+        if let Some((after, frame)) = opening {
+            frames.push(frame);
+            rem = after;
+            continue 'operand;
+        }
+        // *** named formulae
+        if allows_let && let Ok((after, _)) = parse_keyword::<E>("let", input) {
+            let (after, frame) = open_let(input, after, issues.as_deref_mut(), scope)?;
+            frames.push(frame);
+            rem = after;
+            continue 'operand;
+        }
+        let named = parse_named_use::<AP, P, B, E>(
+            formula_parser,
+            builder,
+            input,
+            issues.as_deref_mut(),
+            scope,
+        )?;
+        let (after, mut operand) = if let Some((after, operand)) = named {
+            // the text of a use within the body of a named formula is
+            // shared by all the uses of the latter : an expansion keeps the
+            // text of the arguments, or that of the outermost use
+            if scope.depth == 0 {
+                (
+                    after,
+                    with_span(builder, scope.source, operand, input, after),
+                )
+            } else {
+                (after, operand)
+            }
+        } else {
+            // *** leaves
+            let (after, formula) = if let Some((after, atom)) = leading_atom {
+                (after, builder.build_formula(atom))
+            } else if let Some((after, states)) = formula_parser
+                .state_set_literals()
+                .then(|| parse_state_set(input))
+                .flatten()
+            {
+                (after, builder.build_leaf(CTLFormulaLeaf::States(states)))
+            } else if let Some(after) = parse_spelling::<E>(syntax.true_spellings(), input) {
+                (after, builder.build_leaf(CTLFormulaLeaf::True))
+            } else if let Some(after) = parse_spelling::<E>(syntax.false_spellings(), input) {
+                (after, builder.build_leaf(CTLFormulaLeaf::False))
+            } else {
+                match formula_parser.parse_atomic_proposition(input) {
+                    Ok((after, atom)) => (after, builder.build_formula(atom)),
+                    Err(e) => {
+                        let after =
+                            leaf_failure(formula_parser, input, e, &frames, issues.as_deref_mut())?;
+                        (after, builder.build_leaf(CTLFormulaLeaf::True))
+                    }
+                }
+            };
+            if limit == 0 {
+                return Err(ParseFailure::NestingTooDeep { at: input });
+            }
+            let operand = Operand { formula, depth: 1 };
+            (
+                after,
+                with_span(builder, scope.source, operand, input, after),
+            )
+        };
+        let mut start = input;
+        rem = after;
+        // *** an operand was parsed : close constructs
+        loop {
+            // prefix operators bind tighter than the binary connectives
+            while let Some(Frame::Prefix(..)) = frames.last() {
+                let Some(Frame::Prefix(op, at)) = frames.pop() else {
+                    unreachable!()
+                };
+                operand = build_unary(builder, limit, op, operand, at)?;
+                operand = with_span(builder, scope.source, operand, at, rem);
+                start = at;
+            }
+            // a binary connective continues the current expression, once
+            // the pending connectives that bind at least as tightly are
+            // applied
+            let infix = match issues.as_deref_mut() {
+                Some(issues) => parse_misspelled_infix(syntax, rem, issues),
+                None => None,
+            }
+            .or_else(|| parse_infix::<E>(syntax, rem));
+            if let Some((after_op, op, op_at)) = infix {
+                let (power, left_assoc) = connective_binding_power(op);
+                while let Some(Frame::Infix(pending_op, ..)) = frames.last() {
+                    let (pending_power, _) = connective_binding_power(*pending_op);
+                    if pending_power < power || (pending_power == power && !left_assoc) {
+                        break;
+                    }
+                    let Some(Frame::Infix(pending_op, left, left_start, at)) = frames.pop() else {
+                        unreachable!()
+                    };
+                    operand = build_connective(builder, limit, pending_op, left, operand, at)?;
+                    operand = with_span(builder, scope.source, operand, left_start, rem);
+                    start = left_start;
+                }
+                frames.push(Frame::Infix(op, operand, start, op_at));
+                rem = after_op;
+                continue 'operand;
+            }
+            if let Some(issues) = issues.as_deref_mut()
+                && let Some(after) = skip_unexpected_token::<_, E>(rem, &frames, issues)
+            {
+                rem = after;
+                continue;
+            }
+            // otherwise the expression ends
+            while let Some(Frame::Infix(..)) = frames.last() {
+                let Some(Frame::Infix(op, left, left_start, at)) = frames.pop() else {
+                    unreachable!()
+                };
+                operand = build_connective(builder, limit, op, left, operand, at)?;
+                operand = with_span(builder, scope.source, operand, left_start, rem);
+            }
+            match frames.pop() {
+                None => return Ok((rem, operand)),
+                Some(Frame::Paren(open)) => {
+                    rem = close_group(rem, ')', issues.as_deref_mut())?;
+                    // the parentheses belong to the text of the operand
+                    operand = with_span(builder, scope.source, operand, open, rem);
+                    start = open;
+                }
+                Some(Frame::UntilLeft(op, at, closing)) => {
+                    let (after, _) = multispace0(rem)?;
+                    match parse_keyword::<E>("U", after) {
+                        Ok((after, _)) => {
+                            frames.push(Frame::UntilRight(op, operand, at, closing));
+                            rem = after;
+                            continue 'operand;
+                        }
+                        Err(e) => {
+                            let expected = Expectation::UntilKeyword;
+                            missing(issues.as_deref_mut(), after, expected, e)?;
+                            // the until misses its right operand
+                            let right = true_operand(builder);
+                            operand = build_binary(builder, limit, op, operand, right, at)?;
+                            rem = after.strip_prefix(closing).unwrap_or(after);
+                            operand = with_span(builder, scope.source, operand, at, rem);
+                            start = at;
+                        }
+                    }
+                }
+                Some(Frame::UntilRight(op, left, at, closing)) => {
+                    rem = close_group(rem, closing, issues.as_deref_mut())?;
+                    operand = build_binary(builder, limit, op, left, operand, at)?;
+                    operand = with_span(builder, scope.source, operand, at, rem);
+                    start = at;
+                }
+                Some(frame @ Frame::LetBody { at: let_at, .. }) => {
+                    let (after, scoped) = close_let_body(frame, rem, issues.as_deref_mut(), scope)?;
+                    rem = after;
+                    if let Some(frame) = scoped {
+                        frames.push(frame);
+                        continue 'operand;
+                    }
+                    // the let misses the formula in its scope
+                    operand = true_operand(builder);
+                    operand = with_span(builder, scope.source, operand, let_at, rem);
+                    start = let_at;
+                }
+                Some(Frame::Let { at, outer, bound }) => {
+                    scope.bindings.truncate(bound);
+                    scope.innermost = outer;
+                    // the binding belongs to the text of the formula in its
+                    // scope
+                    operand = with_span(builder, scope.source, operand, at, rem);
+                    start = at;
+                }
+                Some(Frame::Prefix(..)) | Some(Frame::Infix(..)) => {
+                    unreachable!("prefix and infix frames were closed above")
+                }
+            }
+        }
+    }
+}
+
+/// a missing operand, which stands for `true` when recovering
+fn true_operand<AP, B: CTLFormulaBuilder<AP>>(builder: &mut B) -> Operand<B::Formula> {
+    Operand {
+        formula: builder.build_leaf(CTLFormulaLeaf::True),
+        depth: 1,
+    }
+}
+
+/// fails with the error `e` on a token which must follow, or when
+/// recovering, records that `expected` is missing at `at`
+fn missing<'a, E>(
+    issues: Option<&mut Vec<Issue<'a>>>,
+    at: &'a str,
+    expected: Expectation,
+    e: nom::Err<E>,
+) -> Result<(), nom::Err<E>> {
+    let Some(issues) = issues else {
+        return Err(committed(e));
+    };
+    record(
+        issues,
+        Issue::Unexpected {
+            at,
+            expected,
+            hint: None,
+        },
+    );
+    Ok(())
+}
+
+/// the `closing` delimiter of a group, see [missing]
+fn close_group<'a, E: ParseError<&'a str>>(
+    rem: &'a str,
+    closing: char,
+    issues: Option<&mut Vec<Issue<'a>>>,
+) -> Result<&'a str, nom::Err<E>> {
+    match (multispace0, nom_char(closing)).parse(rem) {
+        Ok((after, _)) => Ok(after),
+        Err(e) => {
+            let (at, _) = multispace0(rem)?;
+            missing(issues, at, Expectation::Closing(closing), e)?;
+            Ok(at)
+        }
+    }
+}
+
+/// when recovering : a negation of another syntax profile, or an until
+/// opened by a parenthesis (`A(φ U ψ)`), recorded as an error and read
+/// as intended
+fn parse_misspelled_opening<'a, F, E: ParseError<&'a str>>(
+    syntax: CtlSyntax,
+    input: &'a str,
+    issues: &mut Vec<Issue<'a>>,
+) -> Option<(&'a str, Frame<'a, F>)> {
+    if let Some(misspelled) = syntax
+        .foreign_not_spellings()
+        .find(|spelling| input.starts_with(spelling))
+    {
+        let correct = syntax.not_spellings()[0];
+        record(
+            issues,
+            Issue::Unexpected {
+                at: input,
+                expected: Expectation::Formula,
+                hint: Some(misspelled_operator_hint(misspelled, correct)),
+            },
+        );
+        let frame = Frame::Prefix(UnaryCTLOperator::Not, input);
+        return Some((&input[misspelled.len()..], frame));
+    }
+    let (after, operator, at) = parse_until_opening::<E>(input, '(')?;
+    record(
+        issues,
+        Issue::Unexpected {
+            at,
+            expected: Expectation::Token("["),
+            hint: Some(parenthesized_until_hint()),
+        },
+    );
+    Some((after, Frame::UntilLeft(operator, input, ')')))
+}
+
+/// when recovering : skips the token at `rem` if it neither continues
+/// nor closes the innermost group of `frames`, and records it as an
+/// error. Unclosed groups are instead closed at the end of the input or
+/// at the closing delimiter of an enclosing group.
+fn skip_unexpected_token<'a, F, E: ParseError<&'a str>>(
+    rem: &'a str,
+    frames: &[Frame<'a, F>],
+    issues: &mut Vec<Issue<'a>>,
+) -> Option<&'a str> {
+    let (at, _) = multispace0::<&'a str, E>(rem).ok()?;
+    let innermost_group = frames.iter().rev().find(|frame| {
+        !matches!(
+            frame,
+            Frame::Prefix(..) | Frame::Infix(..) | Frame::Let { .. }
+        )
+    });
+    let (expected, closes) = match innermost_group {
+        None => (Expectation::End, at.is_empty()),
+        Some(Frame::Paren(_)) => (Expectation::Closing(')'), at.starts_with(')')),
+        Some(Frame::UntilLeft(..)) => (
+            Expectation::UntilKeyword,
+            parse_keyword::<E>("U", at).is_ok(),
+        ),
+        Some(Frame::UntilRight(.., closing)) => {
+            (Expectation::Closing(*closing), at.starts_with(*closing))
+        }
+        Some(Frame::LetBody { .. }) => (Expectation::LetIn, parse_keyword::<E>("in", at).is_ok()),
+        Some(Frame::Prefix(..)) | Some(Frame::Infix(..)) | Some(Frame::Let { .. }) => {
+            unreachable!()
+        }
+    };
+    if closes || at.is_empty() || at.starts_with([')', ']']) {
+        return None;
+    }
+    record(
+        issues,
+        Issue::Unexpected {
+            at,
+            expected,
+            hint: hint_for(expected, at),
+        },
+    );
+    Some(&at[token_len(at)..])
+}
+
+/// Fails on the error `e` met when parsing a leaf at `input`, or when
+/// recovering, records it and returns the input after the faulty token,
+/// which stands for `true`. Calls of names which are not in scope and
+/// atoms reported by [CtlFormulaParser::unknown_atomic_proposition] get
+/// their own errors.
+fn leaf_failure<'a, AP, P: CtlFormulaParser<AP>, F, E: ParseError<&'a str>>(
     formula_parser: &P,
     input: &'a str,
-) -> IResult<&'a str, CTLFormula<AP>, E> {
-    let (input, _) = multispace0(input)?;
-    // ***
-    if let Ok((rem, _)) = nom_char::<&'a str, E>('(').parse(input) {
-        let (rem, phi) = cut(|i| parse_iff_level(formula_parser, i)).parse(rem)?;
-        let (rem, _) = cut((multispace0, nom_char(')'))).parse(rem)?;
-        return Ok((rem, phi));
+    e: nom::Err<E>,
+    frames: &[Frame<'a, F>],
+    issues: Option<&mut Vec<Issue<'a>>>,
+) -> Result<&'a str, ParseFailure<'a, E>> {
+    let undefined = parse_identifier(input).filter(|(_, after_name)| after_name.starts_with('('));
+    if let Some((name, after_name)) = undefined {
+        let error = DefinitionError::Undefined(name.to_string());
+        let Some(issues) = issues else {
+            return Err(ParseFailure::Definition { at: input, error });
+        };
+        record(issues, Issue::Definition { at: input, error });
+        return Ok(match split_arguments(&after_name[1..]) {
+            Ok((_, after)) | Err(after) => after,
+        });
     }
-    // ***
-    if let Ok((rem, _)) = parse_keyword::<E>("true", input) {
-        return Ok((rem, CTLFormula::Leaf(CTLFormulaLeaf::True)));
+    if let Some((after, name)) = formula_parser.unknown_atomic_proposition(input) {
+        let Some(issues) = issues else {
+            return Err(ParseFailure::UnknownAtom { at: input, name });
+        };
+        record(issues, Issue::UnknownAtom { at: input, name });
+        return Ok(after);
     }
-    if let Ok((rem, _)) = parse_keyword::<E>("false", input) {
-        return Ok((rem, CTLFormula::Leaf(CTLFormulaLeaf::False)));
+    let Some(issues) = issues else {
+        let is_committed = frames
+            .iter()
+            .any(|frame| !matches!(frame, Frame::Infix(..)));
+        return Err(if is_committed { committed(e) } else { e }.into());
+    };
+    record(
+        issues,
+        Issue::Unexpected {
+            at: input,
+            expected: Expectation::Formula,
+            hint: hint_for(Expectation::Formula, input),
+        },
+    );
+    // the faulty token stands for the missing operand, unless it may
+    // continue the formula
+    let syntax = formula_parser.syntax();
+    let continues_formula = input.is_empty()
+        || input.starts_with([')', ']'])
+        || parse_infix::<E>(syntax, input).is_some()
+        || syntax.foreign_connective_at(input).is_some()
+        || (parse_keyword::<E>("in", input).is_ok()
+            && frames
+                .iter()
+                .any(|frame| matches!(frame, Frame::LetBody { .. })));
+    let skipped = if continues_formula {
+        0
+    } else {
+        token_len(input)
+    };
+    Ok(&input[skipped..])
+}
+
+/// `let name(params) =`, whose keyword is at `at` : binds the
+/// parameters, and opens the body of the binding
+fn open_let<'a, F, E: ParseError<&'a str>>(
+    at: &'a str,
+    after_keyword: &'a str,
+    mut issues: Option<&mut Vec<Issue<'a>>>,
+    scope: &mut Scope<'a>,
+) -> Result<(&'a str, Frame<'a, F>), ParseFailure<'a, E>> {
+    let head = parse_definition_head(after_keyword);
+    if let Some((at, expected)) = head.missing {
+        let Some(issues) = issues.as_deref_mut() else {
+            return Err(nom::Err::Failure(E::from_error_kind(at, ErrorKind::Tag)).into());
+        };
+        record(
+            issues,
+            Issue::Unexpected {
+                at,
+                expected,
+                hint: None,
+            },
+        );
+    }
+    let outer = scope.innermost;
+    let bound = scope.bindings.len();
+    for param in &head.params {
+        scope.bind_placeholder(param);
+    }
+    let frame = Frame::LetBody {
+        at,
+        name: head.name,
+        params: head.params,
+        body: head.rest,
+        outer,
+        bound,
+        issues: issues.as_deref().map_or(0, Vec::len),
+    };
+    Ok((head.rest, frame))
+}
+
+/// The end of the body of a [Frame::LetBody], at `rem` : binds the
+/// name, and opens the formula in its scope, after `in`, as a
+/// [Frame::Let]. When recovering, a missing `in` is recorded and no frame
+/// is opened.
+fn close_let_body<'a, F, E: ParseError<&'a str>>(
+    frame: Frame<'a, F>,
+    rem: &'a str,
+    issues: Option<&mut Vec<Issue<'a>>>,
+    scope: &mut Scope<'a>,
+) -> Result<(&'a str, Option<Frame<'a, F>>), ParseFailure<'a, E>> {
+    let Frame::LetBody {
+        at: let_at,
+        name,
+        params,
+        body,
+        outer,
+        bound,
+        issues: issues_before,
+    } = frame
+    else {
+        unreachable!("only the body of a let is closed by `in`")
+    };
+    // the parameters are no longer in scope
+    scope.bindings.truncate(bound);
+    scope.innermost = outer;
+    let (at, _) = multispace0(rem)?;
+    match parse_keyword::<E>("in", at) {
+        Ok((after, _)) => {
+            // a body with errors stands for `true` wherever it is used,
+            // rather than reporting them again
+            let has_errors = issues
+                .as_deref()
+                .is_some_and(|issues| issues.len() > issues_before);
+            let text = body[..body.len() - rem.len()].trim();
+            scope.bind(Binding {
+                name,
+                params,
+                body: (!has_errors).then_some(text),
+                outer,
+                body_scope: outer,
+                used: false,
+            });
+            let frame = Frame::Let {
+                at: let_at,
+                outer,
+                bound,
+            };
+            Ok((after, Some(frame)))
+        }
+        Err(e) => {
+            missing(issues, at, Expectation::LetIn, e)?;
+            Ok((at, None))
+        }
+    }
+}
+
+/// A use at `input` of a named formula in `scope`, with its arguments if
+/// any, expanded (see [expand]) ; `None` if `input` starts with no such
+/// name.
+///
+/// When recovering, errors in the arguments are reported where they are,
+/// and the use then stands for `true`, as it does if its expansion fails.
+fn parse_named_use<
+    'a,
+    AP,
+    P: CtlFormulaParser<AP>,
+    B: CTLFormulaBuilder<AP>,
+    E: ParseError<&'a str>,
+>(
+    formula_parser: &P,
+    builder: &mut B,
+    input: &'a str,
+    issues: Option<&mut Vec<Issue<'a>>>,
+    scope: &mut Scope<'a>,
+) -> Result<Option<ParsedOperand<'a, B::Formula>>, ParseFailure<'a, E>> {
+    let Some((name, after_name, target)) = parse_identifier(input)
+        .filter(|(_, after_name)| !after_name.starts_with('.'))
+        .and_then(|(name, after_name)| Some((name, after_name, scope.lookup(name)?)))
+    else {
+        return Ok(None);
+    };
+    let (after, arguments) = match after_name.strip_prefix('(').map(split_arguments) {
+        None => (after_name, Ok(Vec::new())),
+        Some(Ok((arguments, after))) => (after, Ok(arguments)),
+        // the closing parenthesis is missing
+        Some(Err(at)) => (at, Err(at)),
+    };
+    let operand = match (arguments, issues) {
+        (Ok(arguments), None) => expand(formula_parser, builder, scope, target, &arguments, input)
+            .map_err(into_failure)?,
+        (Ok(arguments), Some(issues)) => {
+            let before = issues.len();
+            for argument in &arguments {
+                parse_formula::<AP, P, B, E>(
+                    formula_parser,
+                    builder,
+                    argument,
+                    Some(&mut *issues),
+                    scope,
+                )?;
+            }
+            let expanded = if issues.len() > before {
+                None
+            } else {
+                match expand(formula_parser, builder, scope, target, &arguments, input) {
+                    Ok(operand) => Some(operand),
+                    Err(ParseFailure::NestingTooDeep { at }) => {
+                        return Err(ParseFailure::NestingTooDeep { at });
+                    }
+                    Err(failure) => {
+                        record(issues, expansion_issue(failure, name, input));
+                        None
+                    }
+                }
+            };
+            expanded.unwrap_or_else(|| true_operand(builder))
+        }
+        (Err(at), issues) => {
+            let e = nom::Err::Error(E::from_error_kind(at, ErrorKind::Char));
+            missing(issues, at, Expectation::Closing(')'), e)?;
+            true_operand(builder)
+        }
+    };
+    if operand.depth > formula_parser.max_nesting_depth() {
+        return Err(ParseFailure::NestingTooDeep { at: input });
+    }
+    Ok(Some((after, operand)))
+}
+
+/// Expands the use at `at` of the named formula `target`, with the
+/// texts of its `arguments`, by parsing its body in strict mode with its
+/// parameters bound to the arguments.
+///
+/// Failures in a text which is not part of the formula being parsed
+/// (the body of a [CtlDefinitions] entry) are reported at the use.
+fn expand<'a, AP, P: CtlFormulaParser<AP>, B: CTLFormulaBuilder<AP>>(
+    formula_parser: &P,
+    builder: &mut B,
+    scope: &mut Scope<'a>,
+    target: Target<'a>,
+    arguments: &[&'a str],
+    at: &'a str,
+) -> Result<Operand<B::Formula>, ExpansionFailure<'a>> {
+    let failure = |error| ParseFailure::Definition { at, error };
+    let (name, params, body, body_scope) = match target {
+        Target::Bound(index) => {
+            let binding = &mut scope.bindings[index];
+            binding.used = true;
+            (
+                binding.name,
+                binding.params.clone(),
+                binding.body,
+                binding.body_scope,
+            )
+        }
+        Target::Defined(name, definition) => (
+            name,
+            definition.params.iter().map(String::as_str).collect(),
+            Some(definition.body.as_str()),
+            None,
+        ),
+    };
+    if arguments.len() != params.len() {
+        return Err(failure(DefinitionError::WrongArgumentCount {
+            name: name.to_string(),
+            expected: params.len(),
+            found: arguments.len(),
+        }));
+    }
+    let Some(body) = body else {
+        return Ok(true_operand(builder));
+    };
+    if scope.depth == MAX_EXPANSION_DEPTH {
+        return Err(failure(DefinitionError::TooDeep));
+    }
+    let is_defined = matches!(target, Target::Defined(..));
+    if is_defined {
+        if let Some(start) = scope.expanding.iter().position(|other| *other == name) {
+            let mut cycle: Vec<String> = scope.expanding[start..]
+                .iter()
+                .map(|other| other.to_string())
+                .collect();
+            cycle.push(name.to_string());
+            return Err(failure(DefinitionError::Cyclic(cycle)));
+        }
+        scope.expanding.push(name);
+    }
+    // the arguments are read in the scope of the use, the body in that
+    // of its definition
+    let caller_scope = scope.innermost;
+    let bound = scope.bindings.len();
+    scope.innermost = body_scope;
+    for (param, argument) in params.into_iter().zip(arguments) {
+        scope.bind(Binding {
+            name: param,
+            params: Vec::new(),
+            body: Some(argument),
+            outer: None,
+            body_scope: caller_scope,
+            used: false,
+        });
+    }
+    scope.depth += 1;
+    let mut result = parse_whole(formula_parser, builder, scope, body);
+    // the arguments of unused parameters are still checked
+    for index in bound..scope.bindings.len() {
+        if result.is_ok() && !scope.bindings[index].used {
+            let argument = scope.bindings[index].body.unwrap_or_default();
+            scope.innermost = caller_scope;
+            if let Err(e) = parse_whole(formula_parser, builder, scope, argument) {
+                result = Err(e);
+            }
+        }
+    }
+    scope.depth -= 1;
+    scope.bindings.truncate(bound);
+    scope.innermost = caller_scope;
+    if is_defined {
+        scope.expanding.pop();
+    }
+    result.map_err(|e| relocate(e, scope.source, name, at))
+}
+
+/// parses the whole of `text` as one formula, in strict mode
+fn parse_whole<'a, AP, P: CtlFormulaParser<AP>, B: CTLFormulaBuilder<AP>>(
+    formula_parser: &P,
+    builder: &mut B,
+    scope: &mut Scope<'a>,
+    text: &'a str,
+) -> Result<Operand<B::Formula>, ExpansionFailure<'a>> {
+    let (rem, operand) = parse_formula(formula_parser, builder, text, None, scope)?;
+    let trailing = rem.trim_start();
+    if trailing.is_empty() {
+        Ok(operand)
+    } else {
+        let e = nom::error::Error::from_error_kind(trailing, ErrorKind::Eof);
+        Err(ParseFailure::Nom(nom::Err::Failure(e)))
+    }
+}
+
+/// whether `text` is a sub-slice of `source`
+fn is_within(source: &str, text: &str) -> bool {
+    let start = source.as_ptr() as usize;
+    let position = text.as_ptr() as usize;
+    start <= position && position + text.len() <= start + source.len()
+}
+
+/// a failure in the expansion of `name`, reported at its use `at` if it
+/// is not located in `source`
+fn relocate<'a>(
+    failure: ExpansionFailure<'a>,
+    source: &'a str,
+    name: &str,
+    at: &'a str,
+) -> ExpansionFailure<'a> {
+    let position = match &failure {
+        ParseFailure::Nom(nom::Err::Error(e)) | ParseFailure::Nom(nom::Err::Failure(e)) => e.input,
+        ParseFailure::Nom(nom::Err::Incomplete(_)) => "",
+        ParseFailure::NestingTooDeep { at }
+        | ParseFailure::Definition { at, .. }
+        | ParseFailure::UnknownAtom { at, .. } => at,
+    };
+    if is_within(source, position) {
+        return failure;
+    }
+    match failure {
+        ParseFailure::NestingTooDeep { .. } => ParseFailure::NestingTooDeep { at },
+        ParseFailure::Definition {
+            error:
+                error @ (DefinitionError::Cyclic(_)
+                | DefinitionError::Invalid(_)
+                | DefinitionError::TooDeep),
+            ..
+        } => ParseFailure::Definition { at, error },
+        _ => ParseFailure::Definition {
+            at,
+            error: DefinitionError::Invalid(name.to_string()),
+        },
+    }
+}
+
+/// an expansion failure, for the error type of the caller : expansions
+/// are not recoverable by an enclosing combinator
+fn into_failure<'a, E: ParseError<&'a str>>(failure: ExpansionFailure<'a>) -> ParseFailure<'a, E> {
+    match failure {
+        ParseFailure::Nom(nom::Err::Error(e)) | ParseFailure::Nom(nom::Err::Failure(e)) => {
+            ParseFailure::Nom(nom::Err::Failure(E::from_error_kind(e.input, e.code)))
+        }
+        ParseFailure::Nom(nom::Err::Incomplete(needed)) => {
+            ParseFailure::Nom(nom::Err::Incomplete(needed))
+        }
+        ParseFailure::NestingTooDeep { at } => ParseFailure::NestingTooDeep { at },
+        ParseFailure::Definition { at, error } => ParseFailure::Definition { at, error },
+        ParseFailure::UnknownAtom { at, name } => ParseFailure::UnknownAtom { at, name },
+    }
+}
+
+/// when recovering : the issue to report for an expansion failure of the
+/// use `at` of `name`
+fn expansion_issue<'a>(failure: ExpansionFailure<'a>, name: &str, at: &'a str) -> Issue<'a> {
+    match failure {
+        ParseFailure::Definition { at, error } => Issue::Definition { at, error },
+        ParseFailure::UnknownAtom { at, name } => Issue::UnknownAtom { at, name },
+        ParseFailure::Nom(nom::Err::Error(e)) | ParseFailure::Nom(nom::Err::Failure(e)) => {
+            Issue::Unexpected {
+                at: e.input,
+                expected: Expectation::End,
+                hint: None,
+            }
+        }
+        _ => Issue::Definition {
+            at,
+            error: DefinitionError::Invalid(name.to_string()),
+        },
+    }
+}
+
+/// Parses the whole of `input` as one formula, recovering from syntax
+/// errors to report all of them, located in the source of `scope` (of
+/// which `input` is a sub-slice).
+pub(crate) fn parse_formula_with_diagnostics<
+    'a,
+    AP,
+    P: CtlFormulaParser<AP>,
+    B: CTLFormulaBuilder<AP>,
+>(
+    formula_parser: &P,
+    builder: &mut B,
+    input: &'a str,
+    mut scope: Scope<'a>,
+) -> Result<B::Formula, Vec<CtlDiagnostic>> {
+    let mut issues = Vec::new();
+    let result = parse_formula::<AP, P, B, nom::error::Error<&str>>(
+        formula_parser,
+        builder,
+        input,
+        Some(&mut issues),
+        &mut scope,
+    );
+    let phi = match result {
+        Ok((_, phi)) => Some(phi.formula),
+        Err(ParseFailure::NestingTooDeep { at }) => {
+            let limit = formula_parser.max_nesting_depth();
+            record(&mut issues, Issue::NestingTooDeep { at, limit });
+            None
+        }
+        // recovery does not fail on syntax errors
+        Err(ParseFailure::Nom(_))
+        | Err(ParseFailure::Definition { .. })
+        | Err(ParseFailure::UnknownAtom { .. }) => None,
+    };
+    let source = scope.source;
+    match phi {
+        Some(phi) if issues.is_empty() => Ok(phi),
+        _ => Err(issues
+            .into_iter()
+            .map(|issue| CtlDiagnostic::from_issue(source, issue))
+            .collect()),
     }
-    // ***
-    formula_parser.parse_atomic_proposition(input)
 }
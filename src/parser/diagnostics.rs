@@ -0,0 +1,308 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Human-oriented diagnostics for CTL formulae written by hand.
+//!
+//! Unlike [CtlParseError](crate::parser::CtlParseError), which reports
+//! the first error only, a [CtlDiagnostic] locates an error by line and
+//! column, lists the tokens that were expected there and may suggest a
+//! fix for common mistakes. The parser recovers after each error, so that
+//! [CtlFormulaParser::parse_ctl_formula_with_diagnostics](crate::parser::CtlFormulaParser::parse_ctl_formula_with_diagnostics)
+//! reports every error of its input in one run.
+
+use std::fmt;
+
+use crate::parser::macros::DefinitionError;
+
+/// What the parser expected at the position of a syntax error.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Expectation {
+    /// the start of a formula
+    Formula,
+    /// an operator, or the given closing delimiter
+    Closing(char),
+    /// an operator, or the `U` of an until
+    UntilKeyword,
+    /// an operator, or the end of the formula
+    End,
+    /// exactly the given token
+    Token(&'static str),
+    /// the name of a named formula or of one of its parameters
+    Name,
+    /// an operator, or the `in` of a `let`
+    LetIn,
+}
+
+impl Expectation {
+    fn tokens(self) -> Vec<String> {
+        match self {
+            Expectation::Formula => vec![
+                "an atomic proposition".to_string(),
+                "a boolean constant".to_string(),
+                "a negation".to_string(),
+                "a temporal operator".to_string(),
+                "`(`".to_string(),
+            ],
+            Expectation::Closing(delimiter) => {
+                vec![format!("`{}`", delimiter), "an operator".to_string()]
+            }
+            Expectation::UntilKeyword => vec!["`U`".to_string(), "an operator".to_string()],
+            Expectation::End => vec![
+                "an operator".to_string(),
+                "the end of the formula".to_string(),
+            ],
+            Expectation::Token(token) => vec![format!("`{}`", token)],
+            Expectation::Name => vec!["a name".to_string()],
+            Expectation::LetIn => vec!["`in`".to_string(), "an operator".to_string()],
+        }
+    }
+
+    /// The expected tokens, as a phrase.
+    pub(crate) fn describe(self) -> String {
+        enumerate(&self.tokens())
+    }
+}
+
+/// An error found by the parser, positioned by the remaining input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum Issue<'a> {
+    Unexpected {
+        at: &'a str,
+        expected: Expectation,
+        hint: Option<String>,
+    },
+    NestingTooDeep {
+        at: &'a str,
+        limit: usize,
+    },
+    /// at the use of a named formula
+    Definition {
+        at: &'a str,
+        error: DefinitionError,
+    },
+    UnknownAtom {
+        at: &'a str,
+        name: String,
+    },
+}
+
+impl<'a> Issue<'a> {
+    pub(crate) fn at(&self) -> &'a str {
+        match self {
+            Issue::Unexpected { at, .. }
+            | Issue::NestingTooDeep { at, .. }
+            | Issue::Definition { at, .. }
+            | Issue::UnknownAtom { at, .. } => at,
+        }
+    }
+}
+
+/// A syntax error in a CTL formula, located in its source text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CtlDiagnostic {
+    /// the byte offset of the faulty input in the source
+    pub offset: usize,
+    /// the line of the faulty input, starting from 1
+    pub line: usize,
+    /// the column of the faulty input, in characters, starting from 1
+    pub column: usize,
+    /// the length of the faulty input, in characters (0 at the end of
+    /// the source)
+    pub length: usize,
+    pub message: String,
+    /// the tokens which would have been accepted at this position (empty
+    /// if the error is not about an unexpected token)
+    pub expected: Vec<String>,
+    /// a suggested fix, for common mistakes
+    pub hint: Option<String>,
+}
+
+impl fmt::Display for CtlDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} : {}", self.line, self.column, self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CtlDiagnostic {}
+
+impl CtlDiagnostic {
+    /// Locates an error positioned by `at`, a sub-slice of `source`.
+    pub(crate) fn from_issue(source: &str, issue: Issue<'_>) -> Self {
+        let at = issue.at();
+        let offset = at.as_ptr() as usize - source.as_ptr() as usize;
+        let (line, column) = locate(source, offset);
+        let token = token_at(at);
+        match issue {
+            Issue::Unexpected { expected, hint, .. } => {
+                let expected = expected.tokens();
+                let found = if token.is_empty() {
+                    "the end of the input".to_string()
+                } else {
+                    format!("`{}`", token)
+                };
+                CtlDiagnostic {
+                    offset,
+                    line,
+                    column,
+                    length: token.chars().count(),
+                    message: format!("expected {}, found {}", enumerate(&expected), found),
+                    expected,
+                    hint,
+                }
+            }
+            Issue::NestingTooDeep { limit, .. } => CtlDiagnostic {
+                offset,
+                line,
+                column,
+                length: token.chars().count(),
+                message: format!("the formula nests more than {} operators", limit),
+                expected: Vec::new(),
+                hint: None,
+            },
+            Issue::Definition { error, .. } => CtlDiagnostic {
+                offset,
+                line,
+                column,
+                length: token.chars().count(),
+                message: error.to_string(),
+                expected: Vec::new(),
+                hint: None,
+            },
+            Issue::UnknownAtom { name, .. } => CtlDiagnostic {
+                offset,
+                line,
+                column,
+                length: token.chars().count(),
+                message: format!("unknown atomic proposition `{}`", name),
+                expected: Vec::new(),
+                hint: None,
+            },
+        }
+    }
+
+    /// Renders this diagnostic with the faulty line of `source` and a
+    /// caret under the faulty input, e.g. :
+    ///
+    /// ```text
+    /// error: expected `U` or an operator, found `)`
+    ///   --> 1:8
+    ///   |
+    /// 1 | A[p & q)
+    ///   |        ^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_text = source.lines().nth(self.line - 1).unwrap_or("");
+        let line_number = self.line.to_string();
+        let margin = " ".repeat(line_number.len());
+        let mut rendered = format!(
+            "error: {}\n{} --> {}:{}\n{} |\n{} | {}\n{} | {}{}\n",
+            self.message,
+            margin,
+            self.line,
+            self.column,
+            margin,
+            line_number,
+            line_text,
+            margin,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.length.max(1))
+        );
+        if let Some(hint) = &self.hint {
+            rendered.push_str(&format!("{} = hint: {}\n", margin, hint));
+        }
+        rendered
+    }
+}
+
+/// Renders all of `diagnostics` (see [CtlDiagnostic::render]), separated
+/// by blank lines.
+pub fn render_diagnostics(source: &str, diagnostics: &[CtlDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(source))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The line and column (in characters, both starting from 1) of the byte
+/// `offset` of `source`.
+pub(crate) fn locate(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |nl| nl + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+fn enumerate(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [single] => single.clone(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
+    }
+}
+
+/// The length in bytes of the token starting `input`, for error recovery
+/// and reporting : a word, a bracket, or a run of other symbols.
+pub(crate) fn token_len(input: &str) -> usize {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let is_bracket = |c: char| "()[]".contains(c);
+    match input.chars().next() {
+        None => 0,
+        Some(c) if is_word_char(c) => input.find(|c| !is_word_char(c)).unwrap_or(input.len()),
+        Some(c) if is_bracket(c) => c.len_utf8(),
+        Some(_) => input
+            .find(|c: char| is_word_char(c) || is_bracket(c) || c.is_whitespace())
+            .unwrap_or(input.len()),
+    }
+}
+
+pub(crate) fn token_at(input: &str) -> &str {
+    &input[..token_len(input)]
+}
+
+/// A suggested fix for the unexpected token starting `at`.
+pub(crate) fn hint_for(expected: Expectation, at: &str) -> Option<String> {
+    let token = token_at(at);
+    match expected {
+        Expectation::Formula => {
+            let upper = token.to_uppercase();
+            if token != upper && ["AX", "EX", "AF", "EF", "AG", "EG"].contains(&upper.as_str()) {
+                Some(format!("temporal operators are upper-case : `{}`", upper))
+            } else {
+                None
+            }
+        }
+        Expectation::Closing(_) | Expectation::End if token == "U" => {
+            Some("`U` can only occur in an until formula : `A[φ U ψ]` or `E[φ U ψ]`".to_string())
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn misspelled_operator_hint(found: &str, correct: &str) -> String {
+    format!("`{}` is written `{}` in CTL formulae", found, correct)
+}
+
+pub(crate) fn parenthesized_until_hint() -> String {
+    "until formulae are written with square brackets : `A[φ U ψ]` or `E[φ U ψ]`".to_string()
+}
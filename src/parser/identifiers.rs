@@ -0,0 +1,224 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! A ready-made [CtlFormulaParser] whose atomic propositions are named
+//! by identifiers, resolved by a map or a closure :
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use citreelo::parser::CtlFormulaParser;
+//! use citreelo::parser::identifiers::IdentifierParser;
+//!
+//! let atoms = HashMap::from([("req".to_string(), 0), ("proc1.grant".to_string(), 1)]);
+//! let parser = IdentifierParser::new(atoms);
+//! assert!(parser.parse_complete_ctl_formula("AG (req => AF proc1.grant)").is_ok());
+//! assert!(parser.parse_complete_ctl_formula("AG busy").is_err());
+//! ```
+//!
+//! By default, a name is either :
+//! - an identifier, made of letters, digits and `_` and not starting with
+//!   a digit, or a sequence of such identifiers separated by `.`, as in
+//!   `proc1.x` ; the character classes and the separator are configurable
+//! - or any text between double quotes, in which `\"` and `\\` stand for
+//!   `"` and `\`, as in `"x > 0"`.
+//!
+//! Well-formed names which the resolver does not know are reported as
+//! [CtlParseError::UnknownAtom](crate::parser::CtlParseError::UnknownAtom).
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+use nom::IResult;
+use nom::error::{ErrorKind, ParseError};
+
+use crate::ctl::{CTLFormula, CTLFormulaLeaf};
+use crate::parser::CtlFormulaParser;
+use crate::parser::syntax::CtlSyntax;
+
+/// Resolves the names of atomic propositions.
+///
+/// Implemented by maps from names and by closures `&str -> Option<AP>`.
+pub trait AtomResolver<AP> {
+    fn resolve(&self, name: &str) -> Option<AP>;
+}
+
+impl<AP: Clone, S: BuildHasher> AtomResolver<AP> for HashMap<String, AP, S> {
+    fn resolve(&self, name: &str) -> Option<AP> {
+        self.get(name).cloned()
+    }
+}
+
+impl<AP: Clone> AtomResolver<AP> for BTreeMap<String, AP> {
+    fn resolve(&self, name: &str) -> Option<AP> {
+        self.get(name).cloned()
+    }
+}
+
+impl<AP, F: Fn(&str) -> Option<AP>> AtomResolver<AP> for F {
+    fn resolve(&self, name: &str) -> Option<AP> {
+        self(name)
+    }
+}
+
+/// A parser for CTL formulae over named atomic propositions (see the
+/// [module documentation](self)).
+#[derive(Debug, Clone)]
+pub struct IdentifierParser<R> {
+    resolver: R,
+    is_start_char: fn(char) -> bool,
+    is_char: fn(char) -> bool,
+    separator: Option<char>,
+    quote: Option<char>,
+    syntax: CtlSyntax,
+    let_bindings: bool,
+}
+
+impl<R> IdentifierParser<R> {
+    pub fn new(resolver: R) -> Self {
+        IdentifierParser {
+            resolver,
+            is_start_char: |c| c.is_alphabetic() || c == '_',
+            is_char: |c| c.is_alphanumeric() || c == '_',
+            separator: Some('.'),
+            quote: Some('"'),
+            syntax: CtlSyntax::default(),
+            let_bindings: false,
+        }
+    }
+
+    /// Sets the characters which may start an identifier, and those which
+    /// may follow.
+    pub fn with_chars(
+        mut self,
+        is_start_char: fn(char) -> bool,
+        is_char: fn(char) -> bool,
+    ) -> Self {
+        self.is_start_char = is_start_char;
+        self.is_char = is_char;
+        self
+    }
+
+    /// Sets the separator of namespaced identifiers (`.` by default), or
+    /// disables them.
+    pub fn with_separator(mut self, separator: Option<char>) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Sets the delimiter of quoted names (`"` by default), or disables
+    /// them.
+    pub fn with_quote(mut self, quote: Option<char>) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets the [syntax profile](crate::parser::syntax) of the formulae.
+    pub fn with_syntax(mut self, syntax: CtlSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Enables or disables `let` bindings in the formulae (see
+    /// [CtlFormulaParser::let_bindings]) ; they are disabled by default,
+    /// so that `let` and `in` may name atomic propositions.
+    pub fn with_let_bindings(mut self, enabled: bool) -> Self {
+        self.let_bindings = enabled;
+        self
+    }
+
+    /// The name at the start of `input`, unquoted, and the rest.
+    pub fn parse_name<'a>(&self, input: &'a str) -> Option<(&'a str, Cow<'a, str>)> {
+        if let Some(quote) = self.quote
+            && let Some(quoted) = input.strip_prefix(quote)
+        {
+            return parse_quoted(quoted, quote);
+        }
+        let mut len = 0;
+        loop {
+            let mut chars = input[len..].chars();
+            let start = chars.next().filter(|c| (self.is_start_char)(*c))?;
+            len += start.len_utf8();
+            len += chars
+                .take_while(|c| (self.is_char)(*c))
+                .map(char::len_utf8)
+                .sum::<usize>();
+            // a separator continues the name only if an identifier follows
+            let continues = self.separator.is_some_and(|separator| {
+                let mut after = input[len..].chars();
+                after.next() == Some(separator) && after.next().is_some_and(self.is_start_char)
+            });
+            if !continues {
+                return Some((&input[len..], Cow::Borrowed(&input[..len])));
+            }
+            len += self.separator.map_or(0, char::len_utf8);
+        }
+    }
+}
+
+/// the text up to the closing `quote`, unescaped, and the rest after it
+fn parse_quoted(input: &str, quote: char) -> Option<(&str, Cow<'_, str>)> {
+    let mut name = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => name.push(chars.next()?.1),
+            c if c == quote => {
+                let rest = &input[index + c.len_utf8()..];
+                if name.len() == index {
+                    return Some((rest, Cow::Borrowed(&input[..index])));
+                }
+                return Some((rest, Cow::Owned(name)));
+            }
+            c => name.push(c),
+        }
+    }
+    None
+}
+
+impl<AP, R: AtomResolver<AP>> CtlFormulaParser<AP> for IdentifierParser<R> {
+    fn parse_atomic_proposition<'a, E: ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> IResult<&'a str, CTLFormula<AP>, E> {
+        let Some((rest, name)) = self.parse_name(input) else {
+            return Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Alpha)));
+        };
+        match self.resolver.resolve(&name) {
+            Some(ap) => Ok((rest, CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(ap)))),
+            None => Err(nom::Err::Error(E::from_error_kind(
+                input,
+                ErrorKind::Verify,
+            ))),
+        }
+    }
+
+    fn unknown_atomic_proposition<'a>(&self, input: &'a str) -> Option<(&'a str, String)> {
+        let (rest, name) = self.parse_name(input)?;
+        match self.resolver.resolve(&name) {
+            Some(_) => None,
+            None => Some((rest, name.into_owned())),
+        }
+    }
+
+    fn syntax(&self) -> CtlSyntax {
+        self.syntax
+    }
+
+    fn let_bindings(&self) -> bool {
+        self.let_bindings
+    }
+}
@@ -0,0 +1,388 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Named formulae : `let` bindings and file-level definitions.
+//!
+//! A formula may name a sub-formula, possibly with parameters, and use
+//! it in the rest of the formula :
+//!
+//! ```text
+//! let ok = idle & !busy in AG (ok => EF ok)
+//! let resp(a, b) = AG (a => AF b) in resp(req1, grant1) & resp(req2, grant2)
+//! ```
+//!
+//! The scope of a `let` extends as far right as possible, as that of a
+//! lambda : `p & let x = q in x | r` reads as `p & (let x = q in (x | r))`.
+//! Definitions may also be given for a whole file, in any order, by
+//! `define` entries of [specification files](crate::spec) or through
+//! [CtlDefinitions].
+//!
+//! `let` bindings are opt-in : they are recognized when the parser
+//! enables them (see [CtlFormulaParser::let_bindings](crate::parser::CtlFormulaParser::let_bindings)),
+//! when parsing with definitions, and in specification files, where
+//! `let` and `in` are keywords ; otherwise, they may name atomic
+//! propositions.
+//!
+//! Names are identifiers (`[A-Za-z_][A-Za-z0-9_]*`) and shadow the atomic
+//! propositions of the same name. Arguments
+//! follow the name without space, as in `resp(p, q)`. Uses are expanded
+//! by the parser, which only produces plain formulae : the body of a
+//! definition is read again at each of its uses, with its parameters
+//! standing for the (textual) arguments, and names are resolved where
+//! they are written (lexical scoping).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::parser::CtlParseError;
+use crate::parser::diagnostics::Expectation;
+
+/// The maximal number of nested expansions of named formulae, beyond
+/// which the parser fails with [CtlParseError::ExpansionTooDeep].
+pub const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// A named formula, with parameters.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CtlDefinition {
+    pub params: Vec<String>,
+    /// the text of the formula, in the syntax of the parser using the
+    /// definition
+    pub body: String,
+}
+
+/// File-level definitions of named formulae, which may refer to each
+/// other in any order (see
+/// [CtlFormulaParser::parse_complete_ctl_formula_with_definitions](crate::parser::CtlFormulaParser::parse_complete_ctl_formula_with_definitions)).
+///
+/// Definitions are not checked when added : errors in their bodies are
+/// reported, as [CtlParseError::InvalidDefinition], where they are used.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CtlDefinitions {
+    definitions: HashMap<String, CtlDefinition>,
+}
+
+impl CtlDefinitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines `name(params)` as `body`, returning the definition it
+    /// replaces, if any.
+    pub fn define(
+        &mut self,
+        name: impl Into<String>,
+        params: &[&str],
+        body: impl Into<String>,
+    ) -> Option<CtlDefinition> {
+        let definition = CtlDefinition {
+            params: params.iter().map(|param| param.to_string()).collect(),
+            body: body.into(),
+        };
+        self.definitions.insert(name.into(), definition)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CtlDefinition> {
+        self.definitions.get(name)
+    }
+
+    pub(crate) fn get_key_value(&self, name: &str) -> Option<(&str, &CtlDefinition)> {
+        self.definitions
+            .get_key_value(name)
+            .map(|(name, definition)| (name.as_str(), definition))
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+/// A name bound by a `let`, or a parameter of the body being expanded.
+pub(crate) struct Binding<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) params: Vec<&'a str>,
+    /// the text of the named formula ; `None` for a formula which stands
+    /// for `true` (the parameters of a body being checked, or a `let`
+    /// whose body has errors, already reported)
+    pub(crate) body: Option<&'a str>,
+    /// the next binding in scope
+    pub(crate) outer: Option<usize>,
+    /// the innermost binding in scope for the body
+    pub(crate) body_scope: Option<usize>,
+    pub(crate) used: bool,
+}
+
+/// What a name in scope refers to.
+#[derive(Clone, Copy)]
+pub(crate) enum Target<'a> {
+    /// a [Binding], by index
+    Bound(usize),
+    Defined(&'a str, &'a CtlDefinition),
+}
+
+/// The names in scope while parsing : the bindings, each linked to the
+/// next one in scope, then the file-level definitions.
+pub(crate) struct Scope<'a> {
+    pub(crate) definitions: Option<&'a CtlDefinitions>,
+    pub(crate) bindings: Vec<Binding<'a>>,
+    /// the innermost binding in scope
+    pub(crate) innermost: Option<usize>,
+    /// the file-level definitions being expanded, to detect cycles
+    pub(crate) expanding: Vec<&'a str>,
+    /// the number of nested expansions
+    pub(crate) depth: usize,
+    /// the text of the formula being parsed : failures in the bodies of
+    /// definitions written elsewhere are reported where they are used
+    pub(crate) source: &'a str,
+}
+
+impl<'a> Scope<'a> {
+    pub(crate) fn new(source: &'a str, definitions: Option<&'a CtlDefinitions>) -> Self {
+        Scope {
+            definitions,
+            bindings: Vec::new(),
+            innermost: None,
+            expanding: Vec::new(),
+            depth: 0,
+            source,
+        }
+    }
+
+    /// The scope of the body of the definition `name`, written in
+    /// `source`, for checking it : its parameters stand for `true`.
+    pub(crate) fn checking(
+        source: &'a str,
+        definitions: &'a CtlDefinitions,
+        name: &'a str,
+        params: &[&'a str],
+    ) -> Self {
+        let mut scope = Scope::new(source, Some(definitions));
+        scope.expanding.push(name);
+        for param in params {
+            scope.bind_placeholder(param);
+        }
+        scope
+    }
+
+    /// Binds `binding`, linked to the innermost binding in scope, and
+    /// makes it the innermost one.
+    pub(crate) fn bind(&mut self, binding: Binding<'a>) {
+        self.bindings.push(Binding {
+            outer: self.innermost,
+            ..binding
+        });
+        self.innermost = Some(self.bindings.len() - 1);
+    }
+
+    pub(crate) fn bind_placeholder(&mut self, name: &'a str) {
+        self.bind(Binding {
+            name,
+            params: Vec::new(),
+            body: None,
+            outer: None,
+            body_scope: None,
+            used: false,
+        });
+    }
+
+    pub(crate) fn lookup(&self, name: &str) -> Option<Target<'a>> {
+        let mut current = self.innermost;
+        while let Some(index) = current {
+            if self.bindings[index].name == name {
+                return Some(Target::Bound(index));
+            }
+            current = self.bindings[index].outer;
+        }
+        let (name, definition) = self.definitions?.get_key_value(name)?;
+        Some(Target::Defined(name, definition))
+    }
+}
+
+/// An error in the use of a named formula.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum DefinitionError {
+    Undefined(String),
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// the names of the definitions in the cycle, the first one last again
+    Cyclic(Vec<String>),
+    /// the body of a definition written elsewhere does not parse
+    Invalid(String),
+    TooDeep,
+}
+
+impl DefinitionError {
+    pub(crate) fn into_parse_error(self, offset: usize) -> CtlParseError {
+        match self {
+            DefinitionError::Undefined(name) => CtlParseError::UndefinedName { offset, name },
+            DefinitionError::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => CtlParseError::WrongArgumentCount {
+                offset,
+                name,
+                expected,
+                found,
+            },
+            DefinitionError::Cyclic(cycle) => CtlParseError::CyclicDefinition { offset, cycle },
+            DefinitionError::Invalid(name) => CtlParseError::InvalidDefinition { offset, name },
+            DefinitionError::TooDeep => CtlParseError::ExpansionTooDeep {
+                offset,
+                limit: MAX_EXPANSION_DEPTH,
+            },
+        }
+    }
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Undefined(name) => write!(f, "`{}` is not defined", name),
+            DefinitionError::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} argument(s) but {} were given",
+                name, expected, found
+            ),
+            DefinitionError::Cyclic(cycle) => {
+                write!(f, "`{}` is defined in terms of itself : ", cycle[0])?;
+                write!(f, "{}", cycle.join(" -> "))
+            }
+            DefinitionError::Invalid(name) => {
+                write!(f, "the definition of `{}` is not a valid formula", name)
+            }
+            DefinitionError::TooDeep => write!(
+                f,
+                "the named formulae are nested more than {} times",
+                MAX_EXPANSION_DEPTH
+            ),
+        }
+    }
+}
+
+/// An identifier at the start of `input`, and the rest.
+pub(crate) fn parse_identifier(input: &str) -> Option<(&str, &str)> {
+    if !input.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    let len = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(input.len());
+    Some(input.split_at(len))
+}
+
+/// The head `name(param, ...) =` of a `let` or of a `define`, with the
+/// rest of the input.
+pub(crate) struct DefinitionHead<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) params: Vec<&'a str>,
+    pub(crate) rest: &'a str,
+    /// the position of the first missing token, and what was expected
+    /// there ; the head is then read as if the token were present
+    pub(crate) missing: Option<(&'a str, Expectation)>,
+}
+
+pub(crate) fn parse_definition_head(input: &str) -> DefinitionHead<'_> {
+    let mut missing = None;
+    let mut miss = |at, expected| {
+        missing.get_or_insert((at, expected));
+    };
+    let at = input.trim_start();
+    let (name, mut rest) = parse_identifier(at).unwrap_or_else(|| {
+        miss(at, Expectation::Name);
+        ("", at)
+    });
+    let mut params = Vec::new();
+    if let Some(mut list) = rest.strip_prefix('(') {
+        if let Some(after) = list.trim_start().strip_prefix(')') {
+            // `name()` has no parameter
+            rest = after;
+        } else {
+            loop {
+                let at = list.trim_start();
+                match parse_identifier(at) {
+                    Some((param, after)) => {
+                        params.push(param);
+                        list = after.trim_start();
+                    }
+                    None => {
+                        miss(at, Expectation::Name);
+                        list = at;
+                    }
+                }
+                if let Some(after) = list.strip_prefix(',') {
+                    list = after;
+                } else {
+                    rest = list.strip_prefix(')').unwrap_or_else(|| {
+                        miss(list, Expectation::Token(")"));
+                        list
+                    });
+                    break;
+                }
+            }
+        }
+    }
+    let at = rest.trim_start();
+    let rest = at.strip_prefix('=').unwrap_or_else(|| {
+        miss(at, Expectation::Token("="));
+        at
+    });
+    DefinitionHead {
+        name,
+        params,
+        rest,
+        missing,
+    }
+}
+
+/// The arguments of a use, after its `(` : the texts of the arguments
+/// up to the matching `)`, and the rest after it ; or the end of the
+/// input if the `)` is missing.
+pub(crate) fn split_arguments(input: &str) -> Result<(Vec<&str>, &str), &str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, c) in input.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ']' if depth > 0 => depth -= 1,
+            ')' if depth > 0 => depth -= 1,
+            ',' | ')' if depth == 0 => {
+                arguments.push(&input[start..index]);
+                if c == ')' {
+                    // `f()` has no argument
+                    if arguments.len() == 1 && arguments[0].trim().is_empty() {
+                        arguments.clear();
+                    }
+                    return Ok((arguments, &input[index + 1..]));
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    Err(&input[input.len()..])
+}
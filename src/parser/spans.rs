@@ -0,0 +1,177 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The byte ranges of the input from which the sub-formulae of a parsed
+//! formula were read, as returned by
+//! [CtlFormulaParser::parse_complete_ctl_formula_with_spans](crate::parser::CtlFormulaParser::parse_complete_ctl_formula_with_spans),
+//! so that a sub-formula found by a position (e.g. a vacuous one, see
+//! [crate::vacuity]) can be pointed at in the text :
+//!
+//! ```
+//! # use citreelo::parser::CtlFormulaParser;
+//! # use citreelo::parser::identifiers::IdentifierParser;
+//! # let parser = IdentifierParser::new(|name: &str| Some(name.to_string()));
+//! let input = "AG (req => AF grant)";
+//! let (formula, spans) = parser.parse_complete_ctl_formula_with_spans(input).unwrap();
+//! assert_eq!(&input[spans.span_at(&[]).unwrap()], "AG (req => AF grant)");
+//! assert_eq!(&input[spans.span_at(&[0]).unwrap()], "(req => AF grant)");
+//! assert_eq!(&input[spans.span_at(&[0, 1]).unwrap()], "AF grant");
+//! assert_eq!(spans.span_at(&[1]), None);
+//! ```
+//!
+//! The text of a sub-formula includes its enclosing parentheses, and the
+//! `let` bindings it is in the scope of. The expansion of a named formula
+//! has the text of its use, name and arguments ; within it, sub-formulae
+//! have the text they were read from (in the body of a `let`, or in an
+//! argument), the uses of parameters and of other named formulae keeping
+//! the text of what they expand to. Sub-formulae which were not read from
+//! the input (e.g. from the body of a
+//! [CtlDefinitions](crate::parser::macros::CtlDefinitions) entry, or from
+//! the formula returned for an atomic proposition) have the text of their
+//! closest ancestor which was.
+
+use std::ops::Range;
+
+use crate::ctl::{
+    BinaryCTLOperator, CTLFormula, CTLFormulaBuilder, CTLFormulaFolder, CTLFormulaLeaf,
+    CTLFormulaPosition, UnaryCTLOperator,
+};
+
+/// The byte ranges of the sub-formulae of a parsed formula in its input,
+/// by [position](CTLFormulaPosition).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtlSpans {
+    root: SpanNode,
+}
+
+/// a node of the syntax tree, with its text if it is part of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpanNode {
+    span: Option<Range<usize>>,
+    children: Vec<SpanNode>,
+}
+
+impl CtlSpans {
+    /// The byte range of the sub-formula at `position` (see the
+    /// [module documentation](self)), or `None` if there is no such
+    /// sub-formula.
+    pub fn span_at(&self, position: &[usize]) -> Option<Range<usize>> {
+        let mut node = &self.root;
+        let mut span = node.span.clone();
+        for child_index in position {
+            node = node.children.get(*child_index)?;
+            span = node.span.clone().or(span);
+        }
+        span
+    }
+
+    /// The positions of all the sub-formulae with their byte ranges, in
+    /// the order of [CTLFormula::positioned_subformulas].
+    pub fn iter(&self) -> impl Iterator<Item = (CTLFormulaPosition, Range<usize>)> + '_ {
+        let mut to_visit = vec![(Vec::new(), &self.root, self.root.span.clone())];
+        std::iter::from_fn(move || {
+            let (position, node, inherited) = to_visit.pop()?;
+            let span = node.span.clone().or(inherited);
+            for (child_index, child) in node.children.iter().enumerate().rev() {
+                let mut child_position = position.clone();
+                child_position.push(child_index);
+                to_visit.push((child_position, child, span.clone()));
+            }
+            Some((position, span.unwrap_or_default()))
+        })
+    }
+}
+
+/// The [CTLFormulaBuilder] of formula trees along with their spans.
+pub(crate) struct SpanBuilder;
+
+impl SpanBuilder {
+    pub(crate) fn finish<AP>(formula: (CTLFormula<AP>, SpanNode)) -> (CTLFormula<AP>, CtlSpans) {
+        let (formula, root) = formula;
+        (formula, CtlSpans { root })
+    }
+}
+
+fn unspanned(children: Vec<SpanNode>) -> SpanNode {
+    SpanNode {
+        span: None,
+        children,
+    }
+}
+
+impl<AP> CTLFormulaBuilder<AP> for SpanBuilder {
+    type Formula = (CTLFormula<AP>, SpanNode);
+
+    fn build_leaf(&mut self, leaf: CTLFormulaLeaf<AP>) -> Self::Formula {
+        (CTLFormula::Leaf(leaf), unspanned(Vec::new()))
+    }
+
+    fn build_unary(&mut self, op: UnaryCTLOperator, sub: Self::Formula) -> Self::Formula {
+        let (sub, sub_spans) = sub;
+        (
+            CTLFormula::Unary(op, Box::new(sub)),
+            unspanned(vec![sub_spans]),
+        )
+    }
+
+    fn build_binary(
+        &mut self,
+        op: BinaryCTLOperator,
+        left: Self::Formula,
+        right: Self::Formula,
+    ) -> Self::Formula {
+        let ((left, left_spans), (right, right_spans)) = (left, right);
+        (
+            CTLFormula::Binary(op, Box::new(left), Box::new(right)),
+            unspanned(vec![left_spans, right_spans]),
+        )
+    }
+
+    fn build_formula(&mut self, formula: CTLFormula<AP>) -> Self::Formula {
+        let spans = formula.fold(&mut Unspanned);
+        (formula, spans)
+    }
+
+    fn with_span(&mut self, formula: Self::Formula, span: Range<usize>) -> Self::Formula {
+        let (formula, spans) = formula;
+        (
+            formula,
+            SpanNode {
+                span: Some(span),
+                ..spans
+            },
+        )
+    }
+}
+
+/// the span tree of a formula without spans
+struct Unspanned;
+
+impl<AP> CTLFormulaFolder<AP> for Unspanned {
+    type Output = SpanNode;
+
+    fn fold_leaf(&mut self, _: &CTLFormulaLeaf<AP>) -> SpanNode {
+        unspanned(Vec::new())
+    }
+
+    fn fold_unary(&mut self, _: &UnaryCTLOperator, sub: SpanNode) -> SpanNode {
+        unspanned(vec![sub])
+    }
+
+    fn fold_binary(&mut self, _: &BinaryCTLOperator, left: SpanNode, right: SpanNode) -> SpanNode {
+        unspanned(vec![left, right])
+    }
+}
@@ -0,0 +1,249 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Syntax profiles : the spellings of the boolean constants and
+//! connectives accepted by the parser (see
+//! [CtlFormulaParser::syntax](crate::parser::CtlFormulaParser::syntax))
+//! and used by the printer (see
+//! [CtlFormulaPrinter::syntax](crate::printer::CtlFormulaPrinter::syntax)).
+//!
+//! | profile      | constants          | `¬`        | `∧`  | `∨`  | `⇒`        | `⇔`          | other          |
+//! |--------------|--------------------|------------|------|------|------------|--------------|----------------|
+//! | `Citreelo`   | `true` `false`     | `!`        | `&`  | `\|` | `=>`       | `<=>`        |                |
+//! | `Unicode`    | `⊤` `⊥` `true` `false` | `¬`    | `∧`  | `∨`  | `→` `⇒`    | `↔` `⇔`      |                |
+//! | `CStyle`     | `true` `false`     | `!` `~`    | `&&` | `\|\|` | `->`     | `<->`        |                |
+//! | `NuSmv`      | `TRUE` `FALSE`     | `!`        | `&`  | `\|` | `->`       | `<->`        | `xor` `xnor`   |
+//! | `Permissive` | all of the above   |            |      |      |            |              |                |
+//!
+//! The first spelling of each column is the one printed. The temporal
+//! operators and the until notation `A[φ U ψ]` are common to all
+//! profiles, and so are the precedences of the
+//! [module documentation](crate::parser) : `xor` and `xnor` bind as `|`,
+//! and, unlike in NuSMV, `<->` binds weaker than `->`.
+//!
+//! `φ xnor ψ` is read as `φ <=> ψ` and `φ xor ψ` as `!(φ <=> ψ)`.
+
+use crate::ctl::{BinaryCTLOperator, UnaryCTLOperator};
+
+/// A set of spellings for the boolean constants and connectives.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub enum CtlSyntax {
+    /// `true`, `false`, `!`, `&`, `|`, `=>`, `<=>`
+    #[default]
+    Citreelo,
+    /// `⊤`, `⊥`, `¬`, `∧`, `∨`, `→`, `↔`, as in papers
+    Unicode,
+    /// `true`, `false`, `!` or `~`, `&&`, `||`, `->`, `<->`
+    CStyle,
+    /// `TRUE`, `FALSE`, `!`, `&`, `|`, `->`, `<->`, `xor`, `xnor`, as in
+    /// NuSMV specifications
+    NuSmv,
+    /// every spelling of the other profiles
+    Permissive,
+}
+
+/// A binary connective as written in a formula : `xor` and `xnor` have
+/// no counterpart in [BinaryCTLOperator].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Connective {
+    And,
+    Or,
+    Imply,
+    Iff,
+    Xor,
+    Xnor,
+}
+
+impl Connective {
+    /// the operator of the node built for this connective (under a
+    /// negation for `xor`)
+    pub(crate) fn operator(self) -> BinaryCTLOperator {
+        match self {
+            Connective::And => BinaryCTLOperator::And,
+            Connective::Or => BinaryCTLOperator::Or,
+            Connective::Imply => BinaryCTLOperator::Imply,
+            Connective::Iff | Connective::Xor | Connective::Xnor => BinaryCTLOperator::Iff,
+        }
+    }
+
+    /// whether the node built for this connective is negated
+    pub(crate) fn is_negated(self) -> bool {
+        self == Connective::Xor
+    }
+}
+
+const CITREELO_CONNECTIVES: &[(&str, Connective)] = &[
+    ("<=>", Connective::Iff),
+    ("=>", Connective::Imply),
+    ("|", Connective::Or),
+    ("&", Connective::And),
+];
+
+const UNICODE_CONNECTIVES: &[(&str, Connective)] = &[
+    ("↔", Connective::Iff),
+    ("⇔", Connective::Iff),
+    ("→", Connective::Imply),
+    ("⇒", Connective::Imply),
+    ("∨", Connective::Or),
+    ("∧", Connective::And),
+];
+
+const C_STYLE_CONNECTIVES: &[(&str, Connective)] = &[
+    ("<->", Connective::Iff),
+    ("->", Connective::Imply),
+    ("||", Connective::Or),
+    ("&&", Connective::And),
+];
+
+const NUSMV_CONNECTIVES: &[(&str, Connective)] = &[
+    ("<->", Connective::Iff),
+    ("->", Connective::Imply),
+    ("|", Connective::Or),
+    ("&", Connective::And),
+    ("xnor", Connective::Xnor),
+    ("xor", Connective::Xor),
+];
+
+// a spelling comes before the shorter spellings it starts with (`||`
+// before `|`), so that the first match is the longest one
+const PERMISSIVE_CONNECTIVES: &[(&str, Connective)] = &[
+    ("<=>", Connective::Iff),
+    ("<->", Connective::Iff),
+    ("↔", Connective::Iff),
+    ("⇔", Connective::Iff),
+    ("=>", Connective::Imply),
+    ("->", Connective::Imply),
+    ("→", Connective::Imply),
+    ("⇒", Connective::Imply),
+    ("||", Connective::Or),
+    ("|", Connective::Or),
+    ("∨", Connective::Or),
+    ("&&", Connective::And),
+    ("&", Connective::And),
+    ("∧", Connective::And),
+    ("xnor", Connective::Xnor),
+    ("xor", Connective::Xor),
+];
+
+impl CtlSyntax {
+    /// All the profiles.
+    pub const ALL: [CtlSyntax; 5] = [
+        CtlSyntax::Citreelo,
+        CtlSyntax::Unicode,
+        CtlSyntax::CStyle,
+        CtlSyntax::NuSmv,
+        CtlSyntax::Permissive,
+    ];
+
+    /// The accepted spellings of `true`, the printed one first.
+    pub fn true_spellings(self) -> &'static [&'static str] {
+        match self {
+            CtlSyntax::Citreelo | CtlSyntax::CStyle => &["true"],
+            CtlSyntax::Unicode => &["⊤", "true"],
+            CtlSyntax::NuSmv => &["TRUE"],
+            CtlSyntax::Permissive => &["true", "TRUE", "⊤"],
+        }
+    }
+
+    /// The accepted spellings of `false`, the printed one first.
+    pub fn false_spellings(self) -> &'static [&'static str] {
+        match self {
+            CtlSyntax::Citreelo | CtlSyntax::CStyle => &["false"],
+            CtlSyntax::Unicode => &["⊥", "false"],
+            CtlSyntax::NuSmv => &["FALSE"],
+            CtlSyntax::Permissive => &["false", "FALSE", "⊥"],
+        }
+    }
+
+    /// The accepted spellings of the negation, the printed one first.
+    pub fn not_spellings(self) -> &'static [&'static str] {
+        match self {
+            CtlSyntax::Citreelo | CtlSyntax::NuSmv => &["!"],
+            CtlSyntax::Unicode => &["¬"],
+            CtlSyntax::CStyle => &["!", "~"],
+            CtlSyntax::Permissive => &["!", "~", "¬"],
+        }
+    }
+
+    /// the accepted spellings of the binary connectives, each one before
+    /// the shorter spellings it starts with
+    pub(crate) fn connectives(self) -> &'static [(&'static str, Connective)] {
+        match self {
+            CtlSyntax::Citreelo => CITREELO_CONNECTIVES,
+            CtlSyntax::Unicode => UNICODE_CONNECTIVES,
+            CtlSyntax::CStyle => C_STYLE_CONNECTIVES,
+            CtlSyntax::NuSmv => NUSMV_CONNECTIVES,
+            CtlSyntax::Permissive => PERMISSIVE_CONNECTIVES,
+        }
+    }
+
+    /// the spellings of the negation which other profiles accept but
+    /// this one does not (for hints)
+    pub(crate) fn foreign_not_spellings(self) -> impl Iterator<Item = &'static str> {
+        CtlSyntax::Permissive
+            .not_spellings()
+            .iter()
+            .copied()
+            .filter(move |spelling| !self.not_spellings().contains(spelling))
+    }
+
+    /// the longest spelling of a connective, in any profile, starting
+    /// `input`, if this profile does not accept it (for hints) ; `xor`
+    /// and `xnor`, which have no counterpart in the other profiles, are
+    /// left out
+    pub(crate) fn foreign_connective_at(self, input: &str) -> Option<(&'static str, Connective)> {
+        let (spelling, connective) = CtlSyntax::Permissive
+            .connectives()
+            .iter()
+            .find(|(spelling, _)| input.starts_with(spelling))?;
+        let is_own = self.connectives().iter().any(|(own, _)| own == spelling);
+        if is_own || spelling.starts_with(char::is_alphabetic) {
+            None
+        } else {
+            Some((spelling, *connective))
+        }
+    }
+
+    /// The printed spelling of `op`, which must be one of the boolean
+    /// connectives `&`, `|`, `=>` or `<=>`.
+    pub fn connective_spelling(self, op: &BinaryCTLOperator) -> &'static str {
+        // the permissive profile prints as the default one
+        let printed = match self {
+            CtlSyntax::Permissive => CtlSyntax::Citreelo,
+            other => other,
+        };
+        printed
+            .connectives()
+            .iter()
+            .find(|(_, connective)| connective.operator() == *op && !connective.is_negated())
+            .map(|(spelling, _)| *spelling)
+            .unwrap_or_else(|| panic!("{:?} is not a boolean connective", op))
+    }
+
+    /// The printed spelling of the unary operator `op`, followed by a
+    /// space for the temporal operators.
+    pub fn unary_spelling(self, op: &UnaryCTLOperator) -> &'static str {
+        match op {
+            UnaryCTLOperator::Not => self.not_spellings()[0],
+            UnaryCTLOperator::AX => "AX ",
+            UnaryCTLOperator::EX => "EX ",
+            UnaryCTLOperator::AF => "AF ",
+            UnaryCTLOperator::EF => "EF ",
+            UnaryCTLOperator::AG => "AG ",
+            UnaryCTLOperator::EG => "EG ",
+        }
+    }
+}
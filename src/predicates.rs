@@ -0,0 +1,149 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Atomic propositions given as named closures over the domain, for
+//! exploratory analyses which do not warrant an enumeration of the
+//! propositions :
+//!
+//! ```
+//! use std::collections::HashSet;
+//! use citreelo::ctl::{CTLFormula, UnaryCTLOperator};
+//! use citreelo::kripke::{KripkeState, KripkeStructure};
+//! use citreelo::predicates::{Predicate, PredicatePrinter};
+//! use citreelo::printer::CtlFormulaPrinter;
+//! use citreelo::solve::get_sat_set;
+//!
+//! let kripke = KripkeStructure::new(vec![
+//!     KripkeState::new(vec![1, 2], vec![1]),
+//!     KripkeState::new(vec![1; 12], vec![1]),
+//! ])
+//! .unwrap();
+//! let long_queue = Predicate::atom("long_queue", |queue: &Vec<u8>| queue.len() > 10);
+//! let formula = CTLFormula::Unary(UnaryCTLOperator::EF, Box::new(long_queue));
+//! assert_eq!(get_sat_set(&kripke, &formula), HashSet::from([0, 1]));
+//! assert_eq!(PredicatePrinter.print_ctl_formula(&formula), "EF long_queue");
+//! ```
+//!
+//! A predicate is identified by its name and its closure : its clones
+//! are the same proposition, and are evaluated once per check. The
+//! closures are `Send + Sync`, so that formulae over predicates may be
+//! checked on several threads. Formulae
+//! over predicates can also be parsed, by resolving their names through
+//! a map (see [IdentifierParser](crate::parser::identifiers::IdentifierParser)).
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::ctl::{CTLFormula, CTLFormulaLeaf};
+use crate::kripke::AtomicProposition;
+use crate::printer::CtlFormulaPrinter;
+
+/// A named predicate over the domain `DOAP`.
+pub struct Predicate<DOAP> {
+    name: Arc<str>,
+    test: Arc<dyn Fn(&DOAP) -> bool + Send + Sync>,
+}
+
+impl<DOAP> Predicate<DOAP> {
+    pub fn new(name: &str, test: impl Fn(&DOAP) -> bool + Send + Sync + 'static) -> Self {
+        Predicate {
+            name: Arc::from(name),
+            test: Arc::new(test),
+        }
+    }
+
+    /// The formula made of the predicate `test` named `name`.
+    pub fn atom(
+        name: &str,
+        test: impl Fn(&DOAP) -> bool + Send + Sync + 'static,
+    ) -> CTLFormula<Self> {
+        CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(Predicate::new(name, test)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<DOAP> Clone for Predicate<DOAP> {
+    fn clone(&self) -> Self {
+        Predicate {
+            name: self.name.clone(),
+            test: self.test.clone(),
+        }
+    }
+}
+
+/// Predicates of the same name with distinct closures are distinct.
+impl<DOAP> PartialEq for Predicate<DOAP> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.test, &other.test)
+    }
+}
+
+impl<DOAP> Eq for Predicate<DOAP> {}
+
+impl<DOAP> Hash for Predicate<DOAP> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<DOAP> fmt::Debug for Predicate<DOAP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Predicate").field(&self.name).finish()
+    }
+}
+
+impl<DOAP> fmt::Display for Predicate<DOAP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<DOAP> AtomicProposition<DOAP> for Predicate<DOAP> {
+    fn is_satisfied_on_state_domain(&self, state_domain: &DOAP) -> bool {
+        (self.test)(state_domain)
+    }
+}
+
+/// the names which would be read as keywords
+const KEYWORDS: [&str; 13] = [
+    "true", "false", "AX", "EX", "AF", "EF", "AG", "EG", "A", "E", "U", "let", "in",
+];
+
+/// Prints formulae over [Predicate]s by their names, quoted when they are
+/// not identifiers, so that they parse back with an
+/// [IdentifierParser](crate::parser::identifiers::IdentifierParser).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PredicatePrinter;
+
+impl<DOAP> CtlFormulaPrinter<Predicate<DOAP>> for PredicatePrinter {
+    fn print_atomic_proposition(&self, predicate: &Predicate<DOAP>) -> String {
+        let is_identifier = !KEYWORDS.contains(&predicate.name())
+            && predicate.name.split('.').all(|part| {
+                let mut chars = part.chars();
+                chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                    && chars.all(|c| c.is_alphanumeric() || c == '_')
+            });
+        if is_identifier {
+            return predicate.name.to_string();
+        }
+        let escaped = predicate.name.replace('\\', "\\\\").replace('"', "\\\"");
+        format!("\"{}\"", escaped)
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Printing CTL formulae in the concrete syntax of the [parser](crate::parser),
+//! in any of its [syntax profiles](crate::parser::syntax).
+//!
+//! Parentheses are only inserted where the precedences of the grammar
+//! require them, so that a printed formula parses back, with the same
+//! profile, to the same formula.
+
+use crate::ctl::*;
+use crate::parser::binding_power;
+use crate::parser::syntax::CtlSyntax;
+
+/// A printer for CTL formulae over atomic propositions of type `AP`.
+///
+/// Implementors only provide [Self::print_atomic_proposition], which
+/// should produce what the matching
+/// [CtlFormulaParser::parse_atomic_proposition](crate::parser::CtlFormulaParser::parse_atomic_proposition)
+/// parses back.
+pub trait CtlFormulaPrinter<AP> {
+    /// Prints a single atomic proposition.
+    fn print_atomic_proposition(&self, ap: &AP) -> String;
+
+    /// The spellings of the boolean constants and connectives used by the
+    /// printer ; the default is [CtlSyntax::Citreelo].
+    fn syntax(&self) -> CtlSyntax {
+        CtlSyntax::default()
+    }
+
+    /// Prints `formula` with as few parentheses as possible.
+    fn print_ctl_formula(&self, formula: &CTLFormula<AP>) -> String {
+        let (printed, _) = formula.fold(&mut FormulaPrinter {
+            printer: self,
+            syntax: self.syntax(),
+        });
+        printed
+    }
+}
+
+/// the binding power of a printed formula : 5 for prefix operators, 6
+/// for self-delimiting formulae (leaves and untils), that of the
+/// parser's binary connectives otherwise
+type Level = u8;
+
+struct FormulaPrinter<'a, P: ?Sized> {
+    printer: &'a P,
+    syntax: CtlSyntax,
+}
+
+/// `printed`, parenthesized if it binds weaker than `min_level`
+fn at_level((printed, level): (String, Level), min_level: Level) -> String {
+    if level < min_level {
+        format!("({})", printed)
+    } else {
+        printed
+    }
+}
+
+impl<AP, P: CtlFormulaPrinter<AP> + ?Sized> CTLFormulaFolder<AP> for FormulaPrinter<'_, P> {
+    type Output = (String, Level);
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> (String, Level) {
+        let printed = match leaf {
+            CTLFormulaLeaf::True => self.syntax.true_spellings()[0].to_string(),
+            CTLFormulaLeaf::False => self.syntax.false_spellings()[0].to_string(),
+            CTLFormulaLeaf::AtomicProp(ap) => self.printer.print_atomic_proposition(ap),
+            CTLFormulaLeaf::States(states) => states.to_string(),
+        };
+        (printed, 6)
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: (String, Level)) -> (String, Level) {
+        let printed = format!("{}{}", self.syntax.unary_spelling(op), at_level(sub, 5));
+        (printed, 5)
+    }
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: (String, Level),
+        right: (String, Level),
+    ) -> (String, Level) {
+        match op {
+            // brackets delimit the operands : never parenthesize
+            BinaryCTLOperator::AU | BinaryCTLOperator::EU => {
+                let quantifier = if *op == BinaryCTLOperator::AU {
+                    "A"
+                } else {
+                    "E"
+                };
+                (format!("{}[{} U {}]", quantifier, left.0, right.0), 6)
+            }
+            _ => {
+                // the operand on the associative side may bind as tightly
+                // as the connective, the other one must bind strictly
+                // tighter to parse back to the same tree
+                let (power, left_assoc) = binding_power(op);
+                let (left_level, right_level) = if left_assoc {
+                    (power, power + 1)
+                } else {
+                    (power + 1, power)
+                };
+                let printed = format!(
+                    "{} {} {}",
+                    at_level(left, left_level),
+                    self.syntax.connective_spelling(op),
+                    at_level(right, right_level)
+                );
+                (printed, power)
+            }
+        }
+    }
+}
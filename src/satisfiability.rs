@@ -0,0 +1,725 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Model-independent satisfiability and validity checking of CTL
+//! formulae, with atomic propositions treated as free boolean symbols.
+//!
+//! A formula is satisfiable if some state of some (total) Kripke
+//! structure satisfies it, and valid if every state of every Kripke
+//! structure does. A specification that is unsatisfiable (contradictory)
+//! or valid (trivially true) is almost certainly a specification bug,
+//! which no amount of model checking can reveal.
+//!
+//! # Decision procedure
+//!
+//! We use the elimination tableau of Emerson & Halpern
+//! (<https://doi.org/10.1016/0022-0000(85)90001-7>), run symbolically on
+//! BDDs :
+//!
+//! - the formula is first rewritten over the base `true`, atoms, `!`,
+//!   `&`, `EX`, `E[_ U _]` and `A[_ U _]` ;
+//! - its closure is completed with `EX true`, `EX E[φ U ψ]` for every
+//!   existential until and `EX !A[φ U ψ]` for every universal one ;
+//! - the *elementary* formulae of the closure (atoms and `EX` formulae)
+//!   become BDD variables. Every valuation of these variables determines
+//!   the truth of every other closure formula (e.g. `E[φ U ψ]` holds iff
+//!   `ψ | (φ & EX E[φ U ψ])`), so valuations are exactly the Hintikka
+//!   sets of the closure ;
+//! - `H -> H'` is a tableau edge iff every `EX χ` that is false in `H`
+//!   has `χ` false in `H'` ;
+//! - Hintikka sets are eliminated until stabilization if one of their
+//!   `EX χ` has no surviving successor containing `χ`, or if one of
+//!   their eventualities (`E[φ U ψ]`, `A[φ U ψ]`) cannot be fulfilled
+//!   within the surviving sets (least fixpoints, as in the solver).
+//!
+//! The formula is satisfiable iff a surviving Hintikka set contains it.
+//!
+//! # Model construction
+//!
+//! When satisfiable, a model is unwound from the surviving Hintikka sets.
+//! Its states are pairs (Hintikka set, pursued eventuality) : each state
+//! picks one witness successor per `EX χ` it contains, choosing, for the
+//! eventuality being pursued, successors that strictly decrease its rank
+//! in the fixpoint that fulfilled it. Once fulfilled (or, for an
+//! existential eventuality, on the branches other than its witness), the
+//! next pending eventuality is pursued, in round-robin order, so that
+//! every eventuality is eventually fulfilled. Witnesses are reused whenever
+//! possible to keep the model small.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use biodivine_lib_bdd::{Bdd, BddValuation, BddVariable, BddVariableSet};
+
+use crate::ctl::*;
+use crate::kripke::{AtomicProposition, KripkeState, KripkeStructure};
+
+/// The labelling of the states of the models built by
+/// [check_ctl_satisfiability] : the set of atomic propositions that hold
+/// on the state, all others being false.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AtomValuation<AP: Eq + Hash> {
+    pub true_atoms: HashSet<AP>,
+}
+
+/// An atomic proposition of a formula, checked against the models built
+/// by [check_ctl_satisfiability] : it holds on the states whose
+/// [AtomValuation] contains it. Use [CtlSatisfyingModel::lift] to check a
+/// formula over `AP` on such a model.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ValuationAtom<AP>(pub AP);
+
+impl<AP: Eq + Hash> AtomicProposition<AtomValuation<AP>> for ValuationAtom<AP> {
+    fn is_satisfied_on_state_domain(&self, state_domain: &AtomValuation<AP>) -> bool {
+        state_domain.true_atoms.contains(&self.0)
+    }
+}
+
+/// A Kripke structure together with a state on which some formula holds,
+/// as produced by [check_ctl_satisfiability] (a model) and
+/// [check_ctl_validity] (a counter-model).
+pub struct CtlSatisfyingModel<AP: Eq + Hash> {
+    pub kripke: KripkeStructure<AtomValuation<AP>>,
+    pub initial_state: usize,
+}
+
+impl<AP: Eq + Hash + Clone> CtlSatisfyingModel<AP> {
+    /// Rewrites `formula` over the atoms of the model, so that it can be
+    /// checked on [CtlSatisfyingModel::kripke].
+    pub fn lift(formula: &CTLFormula<AP>) -> CTLFormula<ValuationAtom<AP>> {
+        formula.map_atoms(|ap| ValuationAtom(ap.clone()))
+    }
+}
+
+/// The outcome of [check_ctl_satisfiability].
+pub enum CtlSatisfiabilityVerdict<AP: Eq + Hash> {
+    /// Some state of some Kripke structure satisfies the formula,
+    /// e.g. the initial state of the given model.
+    Satisfiable(CtlSatisfyingModel<AP>),
+    /// No state of any Kripke structure satisfies the formula.
+    Unsatisfiable,
+}
+
+impl<AP: Eq + Hash> CtlSatisfiabilityVerdict<AP> {
+    pub fn is_satisfiable(&self) -> bool {
+        matches!(self, CtlSatisfiabilityVerdict::Satisfiable(_))
+    }
+}
+
+/// The outcome of [check_ctl_validity].
+pub enum CtlValidityVerdict<AP: Eq + Hash> {
+    /// Every state of every Kripke structure satisfies the formula.
+    Valid,
+    /// The initial state of the given counter-model does not satisfy
+    /// the formula.
+    NotValid(CtlSatisfyingModel<AP>),
+}
+
+impl<AP: Eq + Hash> CtlValidityVerdict<AP> {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, CtlValidityVerdict::Valid)
+    }
+}
+
+/// Decides whether `formula` is satisfiable, i.e., whether it holds on
+/// some state of some Kripke structure, and if so builds a small model.
+///
+/// The atomic propositions are treated as independent boolean symbols :
+/// constraints between them that hold in the intended domain (e.g. two
+/// propositions that are mutually exclusive) are not known to the
+/// procedure, and should be added to the formula (e.g. `AG !(p & q)`)
+/// if they matter.
+///
+/// The procedure is exponential in the number of atoms and temporal
+/// operators of the formula (CTL satisfiability is EXPTIME-complete) :
+/// it is intended for specifications, not for generated formulae of
+/// arbitrary size.
+///
+/// Sets of states (see [CTLFormulaLeaf::States]) are decided as atoms
+/// independent of one another, which the states of the model do not
+/// record.
+///
+/// Panics if the formula has more than 32767 atoms and `EX` formulae in
+/// its closure, the BDD library indexing its variables on 16 bits.
+pub fn check_ctl_satisfiability<AP: Clone + PartialEq + Eq + Hash>(
+    formula: &CTLFormula<AP>,
+) -> CtlSatisfiabilityVerdict<AP> {
+    let mut closure = Closure::new();
+    let root = formula.fold(&mut closure);
+    closure.complete();
+    let tableau = SymbolicTableau::new(&closure);
+    let alive = tableau.eliminate();
+    let candidates = alive.and(&tableau.current[root]);
+    if candidates.is_false() {
+        CtlSatisfiabilityVerdict::Unsatisfiable
+    } else {
+        CtlSatisfiabilityVerdict::Satisfiable(tableau.extract_model(&closure, &alive, &candidates))
+    }
+}
+
+/// Decides whether `formula` is valid, i.e., whether it holds on every
+/// state of every Kripke structure, and if not builds a small
+/// counter-model. This is the satisfiability of `!formula` : see
+/// [check_ctl_satisfiability].
+pub fn check_ctl_validity<AP: Clone + PartialEq + Eq + Hash>(
+    formula: &CTLFormula<AP>,
+) -> CtlValidityVerdict<AP> {
+    let negated = CTLFormula::Unary(UnaryCTLOperator::Not, Box::new(formula.clone()));
+    match check_ctl_satisfiability(&negated) {
+        CtlSatisfiabilityVerdict::Satisfiable(counter_model) => {
+            CtlValidityVerdict::NotValid(counter_model)
+        }
+        CtlSatisfiabilityVerdict::Unsatisfiable => CtlValidityVerdict::Valid,
+    }
+}
+
+/// A formula of the closure, over the base connectives.
+/// Children are indices into [Closure::nodes].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum ClosureNode {
+    True,
+    Atom(usize),
+    Not(usize),
+    And(usize, usize),
+    EX(usize),
+    EU(usize, usize),
+    AU(usize, usize),
+}
+
+/// The hash-consed closure of the formula : structurally equal
+/// sub-formulae share one node, and children always precede their
+/// parents.
+struct Closure<AP> {
+    /// the atomic propositions and the sets of states, which are
+    /// independent symbols as well
+    atoms: Vec<CTLFormulaLeaf<AP>>,
+    atom_ids: HashMap<CTLFormulaLeaf<AP>, usize>,
+    nodes: Vec<ClosureNode>,
+    node_ids: HashMap<ClosureNode, usize>,
+}
+
+impl<AP: Clone + PartialEq + Eq + Hash> Closure<AP> {
+    fn new() -> Self {
+        Self {
+            atoms: Vec::new(),
+            atom_ids: HashMap::new(),
+            nodes: Vec::new(),
+            node_ids: HashMap::new(),
+        }
+    }
+
+    fn mk(&mut self, node: ClosureNode) -> usize {
+        if let Some(id) = self.node_ids.get(&node) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.node_ids.insert(node, id);
+        id
+    }
+
+    /// negation, with double negations eliminated
+    fn mk_not(&mut self, phi: usize) -> usize {
+        match self.nodes[phi] {
+            ClosureNode::Not(inner) => inner,
+            _ => self.mk(ClosureNode::Not(phi)),
+        }
+    }
+
+    fn mk_or(&mut self, phi1: usize, phi2: usize) -> usize {
+        let not1 = self.mk_not(phi1);
+        let not2 = self.mk_not(phi2);
+        let both_false = self.mk(ClosureNode::And(not1, not2));
+        self.mk_not(both_false)
+    }
+
+    fn mk_imply(&mut self, phi1: usize, phi2: usize) -> usize {
+        let not2 = self.mk_not(phi2);
+        let counter_example = self.mk(ClosureNode::And(phi1, not2));
+        self.mk_not(counter_example)
+    }
+
+    /// Adds `EX true` (so that every Hintikka set has a successor) and
+    /// the `EX` formulae through which the untils unfold.
+    fn complete(&mut self) {
+        let true_id = self.mk(ClosureNode::True);
+        self.mk(ClosureNode::EX(true_id));
+        // the nodes added here are neither EU nor AU,
+        // so a single pass over the current nodes suffices
+        for id in 0..self.nodes.len() {
+            match self.nodes[id] {
+                ClosureNode::EU(_, _) => {
+                    self.mk(ClosureNode::EX(id));
+                }
+                ClosureNode::AU(_, _) => {
+                    let not_au = self.mk_not(id);
+                    self.mk(ClosureNode::EX(not_au));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The translation of a formula into its closure, over the base
+/// connectives ; the output is the id of the translated node.
+impl<AP: Clone + PartialEq + Eq + Hash> CTLFormulaFolder<AP> for Closure<AP> {
+    type Output = usize;
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> usize {
+        match leaf {
+            CTLFormulaLeaf::True => self.mk(ClosureNode::True),
+            CTLFormulaLeaf::False => {
+                let true_id = self.mk(ClosureNode::True);
+                self.mk_not(true_id)
+            }
+            CTLFormulaLeaf::AtomicProp(_) | CTLFormulaLeaf::States(_) => {
+                let atom_id = match self.atom_ids.get(leaf) {
+                    Some(atom_id) => *atom_id,
+                    None => {
+                        self.atoms.push(leaf.clone());
+                        self.atom_ids.insert(leaf.clone(), self.atoms.len() - 1);
+                        self.atoms.len() - 1
+                    }
+                };
+                self.mk(ClosureNode::Atom(atom_id))
+            }
+        }
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: usize) -> usize {
+        let true_id = self.mk(ClosureNode::True);
+        match op {
+            UnaryCTLOperator::Not => self.mk_not(sub),
+            UnaryCTLOperator::EX => self.mk(ClosureNode::EX(sub)),
+            // AX φ = !EX !φ
+            UnaryCTLOperator::AX => {
+                let not_sub = self.mk_not(sub);
+                let ex = self.mk(ClosureNode::EX(not_sub));
+                self.mk_not(ex)
+            }
+            // EF φ = E[true U φ]
+            UnaryCTLOperator::EF => self.mk(ClosureNode::EU(true_id, sub)),
+            // AF φ = A[true U φ]
+            UnaryCTLOperator::AF => self.mk(ClosureNode::AU(true_id, sub)),
+            // EG φ = !A[true U !φ]
+            UnaryCTLOperator::EG => {
+                let not_sub = self.mk_not(sub);
+                let au = self.mk(ClosureNode::AU(true_id, not_sub));
+                self.mk_not(au)
+            }
+            // AG φ = !E[true U !φ]
+            UnaryCTLOperator::AG => {
+                let not_sub = self.mk_not(sub);
+                let eu = self.mk(ClosureNode::EU(true_id, not_sub));
+                self.mk_not(eu)
+            }
+        }
+    }
+
+    fn fold_binary(&mut self, op: &BinaryCTLOperator, sub1: usize, sub2: usize) -> usize {
+        match op {
+            BinaryCTLOperator::And => self.mk(ClosureNode::And(sub1, sub2)),
+            BinaryCTLOperator::Or => self.mk_or(sub1, sub2),
+            BinaryCTLOperator::Imply => self.mk_imply(sub1, sub2),
+            BinaryCTLOperator::Iff => {
+                let forward = self.mk_imply(sub1, sub2);
+                let backward = self.mk_imply(sub2, sub1);
+                self.mk(ClosureNode::And(forward, backward))
+            }
+            BinaryCTLOperator::EU => self.mk(ClosureNode::EU(sub1, sub2)),
+            BinaryCTLOperator::AU => self.mk(ClosureNode::AU(sub1, sub2)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EventualityKind {
+    Existential,
+    Universal,
+}
+
+/// An until of the closure, whose right operand must eventually hold.
+struct Eventuality {
+    kind: EventualityKind,
+    /// the until itself
+    node: usize,
+    /// its right operand
+    goal: usize,
+}
+
+/// The Hintikka sets of a [Closure], encoded over the BDD variables of
+/// its elementary formulae, with the same interleaved current / next
+/// variable layout as [crate::bdd::KripkeStructureBddRepresentation].
+struct SymbolicTableau {
+    var_set: BddVariableSet,
+    current_vars: Vec<BddVariable>,
+    next_vars: Vec<BddVariable>,
+    /// for each closure node : whether it belongs to the Hintikka set
+    /// encoded by the current-state variables
+    current: Vec<Bdd>,
+    /// the tableau edges, over current and next variables
+    relation: Bdd,
+    next_iff_current: Bdd,
+    /// the `EX χ` formulae of the closure, as (variable index, χ)
+    next_requirements: Vec<(usize, usize)>,
+    eventualities: Vec<Eventuality>,
+    /// variable index of each atom
+    atom_vars: Vec<usize>,
+    /// variable index of `EX true`
+    ex_true_var: usize,
+}
+
+impl SymbolicTableau {
+    fn new<AP>(closure: &Closure<AP>) -> Self {
+        // one variable per elementary formula
+        let mut elementary_index = HashMap::new();
+        for (id, node) in closure.nodes.iter().enumerate() {
+            if matches!(node, ClosureNode::Atom(_) | ClosureNode::EX(_)) {
+                elementary_index.insert(id, elementary_index.len());
+            }
+        }
+        let num_vars = u16::try_from(elementary_index.len() * 2).unwrap_or_else(|_| {
+            panic!(
+                "the closure has {} elementary formulae, \
+                 more than the {} the BDD variables can encode",
+                elementary_index.len(),
+                u16::MAX / 2
+            )
+        });
+        let var_set = BddVariableSet::new_anonymous(num_vars);
+        let all_vars = var_set.variables();
+        let current_vars: Vec<BddVariable> = all_vars.iter().step_by(2).copied().collect();
+        let next_vars: Vec<BddVariable> = all_vars.iter().skip(1).step_by(2).copied().collect();
+        // ***
+        let membership = |vars: &[BddVariable]| {
+            let mut member: Vec<Bdd> = Vec::with_capacity(closure.nodes.len());
+            for (id, node) in closure.nodes.iter().enumerate() {
+                let bdd = match node {
+                    ClosureNode::True => var_set.mk_true(),
+                    ClosureNode::Atom(_) | ClosureNode::EX(_) => {
+                        var_set.mk_var(vars[elementary_index[&id]])
+                    }
+                    ClosureNode::Not(phi) => member[*phi].not(),
+                    ClosureNode::And(phi1, phi2) => member[*phi1].and(&member[*phi2]),
+                    // E[φ U ψ] = ψ | (φ & EX E[φ U ψ])
+                    ClosureNode::EU(phi1, phi2) => {
+                        let ex = ClosureNode::EX(id);
+                        let unfold = var_set.mk_var(vars[elementary_index[&closure.node_ids[&ex]]]);
+                        member[*phi2].or(&member[*phi1].and(&unfold))
+                    }
+                    // A[φ U ψ] = ψ | (φ & !EX !A[φ U ψ])
+                    ClosureNode::AU(phi1, phi2) => {
+                        let ex = ClosureNode::EX(closure.node_ids[&ClosureNode::Not(id)]);
+                        let unfold = var_set.mk_var(vars[elementary_index[&closure.node_ids[&ex]]]);
+                        member[*phi2].or(&member[*phi1].and(&unfold.not()))
+                    }
+                };
+                member.push(bdd);
+            }
+            member
+        };
+        let current = membership(&current_vars);
+        let next = membership(&next_vars);
+        // ***
+        let mut next_requirements = Vec::new();
+        let mut eventualities = Vec::new();
+        let mut atom_vars = vec![0; closure.atoms.len()];
+        for (id, node) in closure.nodes.iter().enumerate() {
+            match node {
+                ClosureNode::EX(chi) => next_requirements.push((elementary_index[&id], *chi)),
+                ClosureNode::Atom(atom_id) => atom_vars[*atom_id] = elementary_index[&id],
+                ClosureNode::EU(_, goal) => eventualities.push(Eventuality {
+                    kind: EventualityKind::Existential,
+                    node: id,
+                    goal: *goal,
+                }),
+                ClosureNode::AU(_, goal) => eventualities.push(Eventuality {
+                    kind: EventualityKind::Universal,
+                    node: id,
+                    goal: *goal,
+                }),
+                _ => {}
+            }
+        }
+        let ex_true = ClosureNode::EX(closure.node_ids[&ClosureNode::True]);
+        let ex_true_var = elementary_index[&closure.node_ids[&ex_true]];
+        // ***
+        // H -> H' iff every EX χ false in H has χ false in H'
+        let mut relation = var_set.mk_true();
+        for (var, chi) in &next_requirements {
+            relation = relation.and(&var_set.mk_var(current_vars[*var]).or(&next[*chi].not()));
+        }
+        let mut next_iff_current = var_set.mk_true();
+        for (current_var, next_var) in current_vars.iter().zip(next_vars.iter()) {
+            next_iff_current =
+                next_iff_current.and(&var_set.mk_var(*current_var).iff(&var_set.mk_var(*next_var)));
+        }
+        // ***
+        Self {
+            var_set,
+            current_vars,
+            next_vars,
+            current,
+            relation,
+            next_iff_current,
+            next_requirements,
+            eventualities,
+            atom_vars,
+            ex_true_var,
+        }
+    }
+
+    fn mk_current_var(&self, var: usize) -> Bdd {
+        self.var_set.mk_var(self.current_vars[var])
+    }
+
+    /// the Hintikka sets having a tableau successor in `targets`
+    fn pre_image(&self, targets: &Bdd) -> Bdd {
+        targets
+            .and(&self.next_iff_current)
+            .exists(&self.current_vars)
+            .and(&self.relation)
+            .exists(&self.next_vars)
+    }
+
+    /// the Hintikka sets of `alive` all of whose `EX χ` have a tableau
+    /// successor in `targets` containing χ
+    fn fulfils_next_requirements_within(&self, alive: &Bdd, targets: &Bdd) -> Bdd {
+        let mut result = alive.clone();
+        for (var, chi) in &self.next_requirements {
+            let witnessed = self.pre_image(&targets.and(&self.current[*chi]));
+            result = result.and(&self.mk_current_var(*var).imp(&witnessed));
+        }
+        result
+    }
+
+    /// The cumulative layers of the least fixpoint fulfilling an
+    /// eventuality within `alive` : layer k holds the Hintikka sets
+    /// fulfilling it in at most k steps.
+    fn fulfilment_layers(&self, alive: &Bdd, eventuality: &Eventuality) -> Vec<Bdd> {
+        let containing = alive.and(&self.current[eventuality.node]);
+        let mut layers = vec![alive.and(&self.current[eventuality.goal])];
+        loop {
+            let last = layers.last().unwrap();
+            let step = match eventuality.kind {
+                EventualityKind::Existential => containing.and(&self.pre_image(last)),
+                EventualityKind::Universal => {
+                    self.fulfils_next_requirements_within(&containing, last)
+                }
+            };
+            let next = last.or(&step);
+            if next == *last {
+                break;
+            }
+            layers.push(next);
+        }
+        layers
+    }
+
+    /// The greatest set of Hintikka sets closed under the elimination
+    /// rules.
+    fn eliminate(&self) -> Bdd {
+        let mut alive = self.mk_current_var(self.ex_true_var);
+        loop {
+            let mut next = self.fulfils_next_requirements_within(&alive, &alive);
+            for eventuality in &self.eventualities {
+                let fulfilled = self.fulfilment_layers(&next, eventuality).pop().unwrap();
+                next = next.and(&self.current[eventuality.node].imp(&fulfilled));
+            }
+            if next == alive {
+                return alive;
+            }
+            alive = next;
+        }
+    }
+
+    fn valuation_of(&self, hintikka_set: &[bool]) -> BddValuation {
+        let mut valuation = BddValuation::all_false(self.var_set.num_vars());
+        for (var, value) in self.current_vars.iter().zip(hintikka_set) {
+            valuation.set_value(*var, *value);
+        }
+        valuation
+    }
+
+    fn pick(&self, candidates: &Bdd) -> Vec<bool> {
+        let witness = candidates
+            .sat_witness()
+            .expect("the elimination guarantees a witness");
+        self.current_vars
+            .iter()
+            .map(|var| witness.value(*var))
+            .collect()
+    }
+
+    /// the tableau successors of a Hintikka set, over current variables
+    fn successors(&self, hintikka_set: &[bool]) -> Bdd {
+        let fixed: Vec<(BddVariable, bool)> = self
+            .current_vars
+            .iter()
+            .copied()
+            .zip(hintikka_set.iter().copied())
+            .collect();
+        self.relation
+            .restrict(&fixed)
+            .and(&self.next_iff_current)
+            .exists(&self.next_vars)
+    }
+
+    fn is_pending(&self, eventuality: usize, valuation: &BddValuation) -> bool {
+        let eventuality = &self.eventualities[eventuality];
+        self.current[eventuality.node].eval_in(valuation)
+            && !self.current[eventuality.goal].eval_in(valuation)
+    }
+
+    /// The eventuality to pursue on a Hintikka set reached while
+    /// pursuing `pursued` : the same one if still pending, otherwise the
+    /// next pending one in round-robin order (0 if none is pending).
+    fn next_pursued(&self, pursued: usize, valuation: &BddValuation) -> usize {
+        let count = self.eventualities.len();
+        (0..count)
+            .map(|offset| (pursued + offset) % count)
+            .find(|candidate| self.is_pending(*candidate, valuation))
+            .unwrap_or(0)
+    }
+
+    fn extract_model<AP: Clone + PartialEq + Eq + Hash>(
+        &self,
+        closure: &Closure<AP>,
+        alive: &Bdd,
+        initial_candidates: &Bdd,
+    ) -> CtlSatisfyingModel<AP> {
+        let layers: Vec<Vec<Bdd>> = self
+            .eventualities
+            .iter()
+            .map(|eventuality| self.fulfilment_layers(alive, eventuality))
+            .collect();
+        // a state of the model : (Hintikka set, pursued eventuality)
+        let mut states: Vec<(Vec<bool>, usize)> = Vec::new();
+        let mut state_ids: HashMap<(Vec<bool>, usize), usize> = HashMap::new();
+        let mut successors: Vec<Vec<usize>> = Vec::new();
+        let mut to_expand = VecDeque::new();
+        let mut add_state = |hintikka_set: Vec<bool>,
+                             pursued: usize,
+                             states: &mut Vec<(Vec<bool>, usize)>,
+                             successors: &mut Vec<Vec<usize>>,
+                             to_expand: &mut VecDeque<usize>| {
+            let key = (hintikka_set, pursued);
+            if let Some(id) = state_ids.get(&key) {
+                return *id;
+            }
+            let id = states.len();
+            state_ids.insert(key.clone(), id);
+            states.push(key);
+            successors.push(Vec::new());
+            to_expand.push_back(id);
+            id
+        };
+        // ***
+        let initial_set = self.pick(initial_candidates);
+        let initial_pursued = self.next_pursued(0, &self.valuation_of(&initial_set));
+        let initial_state = add_state(
+            initial_set,
+            initial_pursued,
+            &mut states,
+            &mut successors,
+            &mut to_expand,
+        );
+        while let Some(state_id) = to_expand.pop_front() {
+            let (hintikka_set, pursued) = states[state_id].clone();
+            let valuation = self.valuation_of(&hintikka_set);
+            let pursuing = !self.eventualities.is_empty() && self.is_pending(pursued, &valuation);
+            let alive_successors = self.successors(&hintikka_set).and(alive);
+            let mut targets = Vec::new();
+            for (var, chi) in &self.next_requirements {
+                if !hintikka_set[*var] {
+                    continue;
+                }
+                let mut candidates = alive_successors.and(&self.current[*chi]);
+                // where to look for the eventuality pursued by the successor
+                let mut pursuit_from = pursued;
+                if pursuing {
+                    let eventuality = &self.eventualities[pursued];
+                    let constrained = match eventuality.kind {
+                        EventualityKind::Universal => true,
+                        EventualityKind::Existential => *chi == eventuality.node,
+                    };
+                    if constrained {
+                        // pending, hence of rank >= 1
+                        let rank = layers[pursued]
+                            .iter()
+                            .position(|layer| layer.eval_in(&valuation))
+                            .expect("alive sets fulfil their eventualities");
+                        candidates = candidates.and(&layers[pursued][rank - 1]);
+                    } else {
+                        // an existential eventuality is only fulfilled along
+                        // its witness : the other branches move on to the next
+                        // one, otherwise they could pursue it forever
+                        pursuit_from = (pursued + 1) % self.eventualities.len();
+                    }
+                }
+                // prefer an existing state, to keep the model small
+                let reused = states.iter().position(|(other_set, other_pursued)| {
+                    let other_valuation = self.valuation_of(other_set);
+                    candidates.eval_in(&other_valuation)
+                        && self.next_pursued(pursuit_from, &other_valuation) == *other_pursued
+                });
+                let target = match reused {
+                    Some(target) => target,
+                    None => {
+                        let target_set = self.pick(&candidates);
+                        let target_pursued =
+                            self.next_pursued(pursuit_from, &self.valuation_of(&target_set));
+                        add_state(
+                            target_set,
+                            target_pursued,
+                            &mut states,
+                            &mut successors,
+                            &mut to_expand,
+                        )
+                    }
+                };
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            successors[state_id] = targets;
+        }
+        // ***
+        let kripke_states = states
+            .iter()
+            .zip(successors)
+            .map(|((hintikka_set, _), targets)| {
+                let true_atoms = closure
+                    .atoms
+                    .iter()
+                    .zip(&self.atom_vars)
+                    .filter(|(_, var)| hintikka_set[**var])
+                    .filter_map(|(atom, _)| match atom {
+                        CTLFormulaLeaf::AtomicProp(ap) => Some(ap.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                KripkeState::new(AtomValuation { true_atoms }, targets)
+            })
+            .collect();
+        CtlSatisfyingModel {
+            kripke: KripkeStructure::new(kripke_states)
+                .expect("every Hintikka set contains EX true, hence has a successor"),
+            initial_state,
+        }
+    }
+}
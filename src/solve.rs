@@ -14,16 +14,17 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-use std::collections::HashMap;
 use std::collections::HashSet;
 use std::hash::Hash;
-use std::rc::Rc;
 
 use biodivine_lib_bdd::*;
 
+use crate::arena::{CTLFormulaArena, CTLFormulaNode, FormulaId};
 use crate::bdd::KripkeStructureBddRepresentation;
-use crate::bdd::PreImageKind;
+use crate::bdd::{PreImageKind, TransitionPartitioning};
+use crate::cache::{DEFAULT_CACHE_CAPACITY, SatSetCache};
 use crate::ctl::*;
+use crate::fixpoint::{FixpointStrategy, global_fixpoint, until_fixpoint};
 use crate::kripke::*;
 
 /// The reasons for which [CtlModelChecker::is_ctl_formula_sat]
@@ -37,6 +38,10 @@ pub enum CtlModelCheckingError {
         initial_state_id: usize,
         num_states: usize,
     },
+    /// An initial state id is that of a state which is not reachable
+    /// from the initial states of a checker restricted to them (see
+    /// [CtlModelChecker::restricted_to_reachable]).
+    UnreachableInitialState { initial_state_id: usize },
 }
 
 impl std::fmt::Display for CtlModelCheckingError {
@@ -53,13 +58,107 @@ impl std::fmt::Display for CtlModelCheckingError {
                     initial_state_id, num_states
                 )
             }
+            CtlModelCheckingError::UnreachableInitialState { initial_state_id } => {
+                write!(
+                    f,
+                    "initial state {} is not reachable : \
+                     the checker is restricted to the states reachable from its initial states",
+                    initial_state_id
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for CtlModelCheckingError {}
 
-/// A CTL model checker for a given Kripke structure.
+/// A [CTLFormula] in which every sub-formula is annotated with its
+/// satisfaction set, as returned by
+/// [CtlModelChecker::get_annotated_sat_sets].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AnnotatedCTLFormula<AP> {
+    pub node: AnnotatedCTLNode<AP>,
+    /// the ids of the states satisfying this sub-formula
+    pub sat_set: HashSet<usize>,
+}
+
+/// The operator and annotated operands of an [AnnotatedCTLFormula].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AnnotatedCTLNode<AP> {
+    Leaf(CTLFormulaLeaf<AP>),
+    // ***
+    Unary(UnaryCTLOperator, Box<AnnotatedCTLFormula<AP>>),
+    Binary(
+        BinaryCTLOperator,
+        Box<AnnotatedCTLFormula<AP>>,
+        Box<AnnotatedCTLFormula<AP>>,
+    ),
+}
+
+impl<AP: Clone> AnnotatedCTLFormula<AP> {
+    /// The number of states satisfying this sub-formula.
+    pub fn sat_set_size(&self) -> usize {
+        self.sat_set.len()
+    }
+
+    /// The annotated sub-formula occurring at `position`, if any (see
+    /// [CTLFormula::subformula_at]).
+    pub fn subformula_at(&self, position: &[usize]) -> Option<&AnnotatedCTLFormula<AP>> {
+        let mut current = self;
+        for child_index in position {
+            current = match (&current.node, child_index) {
+                (AnnotatedCTLNode::Unary(_, phi1), 0) => phi1,
+                (AnnotatedCTLNode::Binary(_, phi1, _), 0) => phi1,
+                (AnnotatedCTLNode::Binary(_, _, phi2), 1) => phi2,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// The formula without its annotations.
+    ///
+    /// As [CTLFormula::fold], the traversal uses an explicit stack
+    /// rather than recursion.
+    pub fn formula(&self) -> CTLFormula<AP> {
+        // (node, whether its operands have already been rebuilt)
+        let mut to_visit = vec![(self, false)];
+        let mut outputs: Vec<CTLFormula<AP>> = Vec::new();
+        while let Some((phi, operands_rebuilt)) = to_visit.pop() {
+            match &phi.node {
+                AnnotatedCTLNode::Leaf(leaf) => outputs.push(CTLFormula::Leaf(leaf.clone())),
+                AnnotatedCTLNode::Unary(op, phi1) => {
+                    if operands_rebuilt {
+                        let sub = outputs.pop().unwrap();
+                        outputs.push(CTLFormula::Unary(op.clone(), Box::new(sub)));
+                    } else {
+                        to_visit.push((phi, true));
+                        to_visit.push((phi1, false));
+                    }
+                }
+                AnnotatedCTLNode::Binary(op, phi1, phi2) => {
+                    if operands_rebuilt {
+                        let right = outputs.pop().unwrap();
+                        let left = outputs.pop().unwrap();
+                        outputs.push(CTLFormula::Binary(
+                            op.clone(),
+                            Box::new(left),
+                            Box::new(right),
+                        ));
+                    } else {
+                        to_visit.push((phi, true));
+                        to_visit.push((phi2, false));
+                        to_visit.push((phi1, false));
+                    }
+                }
+            }
+        }
+        outputs.pop().unwrap()
+    }
+}
+
+/// A CTL model checker for a given Kripke structure, answering queries
+/// over atomic propositions of type `AP`.
 ///
 /// Building the checker precomputes the BDD representation of the
 /// structure (in particular its transition relation), which is the
@@ -70,52 +169,283 @@ impl std::error::Error for CtlModelCheckingError {}
 /// build a fresh checker on every call: prefer constructing a
 /// [CtlModelChecker] when checking several formulae against the same
 /// structure.
-pub struct CtlModelChecker<'a, DOAP> {
+///
+/// A checker built by [CtlModelChecker::restricted_to_reachable] only
+/// considers the states reachable from given initial states.
+///
+/// The checker keeps the satisfaction sets of the sub-formulae of its
+/// queries (up to [CtlModelChecker::with_cache_capacity] of them), so
+/// that the sub-formulae and atoms shared by several queries are only
+/// computed once (see the [cache](crate::cache) module). The atomic
+/// propositions are thus assumed to give the same verdict on a state at
+/// every evaluation.
+///
+/// The checker is `Send` and `Sync` when the domain is `Sync` and the
+/// atomic propositions are `Send`, so that it can answer queries from
+/// several threads ; see also [CtlModelChecker::check_all_in_parallel].
+pub struct CtlModelChecker<'a, DOAP, AP> {
+    core: CheckerCore<'a, DOAP>,
+    cache: SatSetCache<AP>,
+}
+
+/// The part of a checker which does not depend on the atomic
+/// propositions, and which the threads of a parallel query share : the
+/// cache is only used by the thread issuing the query.
+pub(crate) struct CheckerCore<'a, DOAP> {
     kripke: &'a KripkeStructure<DOAP>,
     bdd_repr: KripkeStructureBddRepresentation,
+    reachable: Option<ReachableStates>,
+    fixpoint_strategy: FixpointStrategy,
 }
 
-impl<'a, DOAP> CtlModelChecker<'a, DOAP> {
+/// The states to which a checker is restricted.
+struct ReachableStates {
+    bdd: Bdd,
+    /// the ids of the states, in increasing order
+    state_ids: Vec<usize>,
+}
+
+impl<'a, DOAP, AP: Clone + PartialEq + Eq + Hash> CtlModelChecker<'a, DOAP, AP> {
     pub fn new(kripke: &'a KripkeStructure<DOAP>) -> Self {
-        let bdd_repr = KripkeStructureBddRepresentation::from_kripke_structure(kripke);
-        Self { kripke, bdd_repr }
+        Self::new_partitioned(kripke, TransitionPartitioning::Monolithic)
+    }
+
+    /// A checker storing the transition relation as given by
+    /// `partitioning` : [TransitionPartitioning::SourceBlocks] never
+    /// builds the BDD of the whole relation, which pays off on large
+    /// structures.
+    pub fn new_partitioned(
+        kripke: &'a KripkeStructure<DOAP>,
+        partitioning: TransitionPartitioning,
+    ) -> Self {
+        let bdd_repr =
+            KripkeStructureBddRepresentation::from_kripke_structure(kripke, partitioning);
+        Self {
+            core: CheckerCore {
+                kripke,
+                bdd_repr,
+                reachable: None,
+                fixpoint_strategy: FixpointStrategy::default(),
+            },
+            cache: SatSetCache::new(DEFAULT_CACHE_CAPACITY),
+        }
+    }
+
+    /// Sets how the fixpoints of the temporal operators are iterated
+    /// ([FixpointStrategy::Naive] by default) ; both strategies give
+    /// the same satisfaction sets.
+    pub fn with_fixpoint_strategy(mut self, strategy: FixpointStrategy) -> Self {
+        self.core.fixpoint_strategy = strategy;
+        self
+    }
+
+    /// How the checker iterates the fixpoints of the temporal operators.
+    pub fn fixpoint_strategy(&self) -> FixpointStrategy {
+        self.core.fixpoint_strategy
+    }
+
+    /// Sets the maximal number of satisfaction sets the checker keeps
+    /// from one query to the next, evicting the least recently used
+    /// ones beyond it ; `0` disables the cache.
+    pub fn with_cache_capacity(self, capacity: usize) -> Self {
+        self.cache.set_capacity(capacity);
+        self
+    }
+
+    /// The maximal number of satisfaction sets the checker keeps.
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    /// The number of satisfaction sets the checker currently keeps.
+    pub fn cache_len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Forgets all the satisfaction sets kept from the previous queries.
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// A checker restricted to the states reachable from
+    /// `initial_states`, which are computed symbolically once : all the
+    /// fixpoints then range over these states only, the atomic
+    /// propositions are evaluated on them only, and the satisfaction
+    /// sets contain them only.
+    ///
+    /// As the reachable states are closed under the transition relation,
+    /// a reachable state satisfies a formula in the restricted checker
+    /// if and only if it satisfies it in the whole structure.
+    ///
+    /// Fails with [CtlModelCheckingError::OutOfRangeInitialState] if
+    /// `initial_states` contains an id that does not correspond to a
+    /// state of the Kripke structure.
+    pub fn restricted_to_reachable(
+        kripke: &'a KripkeStructure<DOAP>,
+        initial_states: &HashSet<usize>,
+    ) -> Result<Self, CtlModelCheckingError> {
+        Self::new(kripke).restrict_to_reachable(initial_states)
+    }
+
+    /// As [Self::restricted_to_reachable], storing the transition
+    /// relation as given by `partitioning` (see [Self::new_partitioned]).
+    pub fn restricted_to_reachable_partitioned(
+        kripke: &'a KripkeStructure<DOAP>,
+        initial_states: &HashSet<usize>,
+        partitioning: TransitionPartitioning,
+    ) -> Result<Self, CtlModelCheckingError> {
+        Self::new_partitioned(kripke, partitioning).restrict_to_reachable(initial_states)
+    }
+
+    fn restrict_to_reachable(
+        mut self,
+        initial_states: &HashSet<usize>,
+    ) -> Result<Self, CtlModelCheckingError> {
+        self.core.restrict_to_reachable(initial_states)?;
+        Ok(self)
+    }
+
+    /// The ids of the states the checker considers : those reachable
+    /// from its initial states if it is restricted to them, all the
+    /// states otherwise.
+    pub fn reachable_states(&self) -> HashSet<usize> {
+        match &self.core.reachable {
+            Some(reachable) => reachable.state_ids.iter().copied().collect(),
+            None => (0..self.core.kripke.states().len()).collect(),
+        }
     }
 
     /// The Kripke structure this checker operates on.
     pub fn kripke(&self) -> &'a KripkeStructure<DOAP> {
-        self.kripke
+        self.core.kripke
+    }
+
+    pub(crate) fn core(&self) -> &CheckerCore<'a, DOAP> {
+        &self.core
+    }
+}
+
+impl<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>
+    CtlModelChecker<'_, DOAP, AP>
+{
+    /// Computes the satisfaction sets of the sub-formulae of the formulae
+    /// interned by `intern`, reusing and filling the cache, and passes
+    /// them to `then`, with the arena in which they are indexed and the
+    /// ids of the formulae. Those of the operands of a cached formula
+    /// are only given with `every_subformula`.
+    pub(crate) fn with_sat_sets<R>(
+        &self,
+        intern: impl FnOnce(&mut CTLFormulaArena<AP>) -> Vec<FormulaId>,
+        every_subformula: bool,
+        then: impl FnOnce(&CTLFormulaArena<AP>, &[FormulaId], &mut [Option<Bdd>]) -> R,
+    ) -> R {
+        self.with_sat_sets_computed_by(
+            intern,
+            |arena, roots, known| {
+                get_ctl_subformulae_sat_sets(&self.core, arena, roots, known, every_subformula)
+            },
+            then,
+        )
+    }
+
+    /// As [Self::with_sat_sets], computing independent sub-formulae on
+    /// `num_threads` threads.
+    pub(crate) fn with_sat_sets_in_parallel<R>(
+        &self,
+        intern: impl FnOnce(&mut CTLFormulaArena<AP>) -> Vec<FormulaId>,
+        num_threads: usize,
+        then: impl FnOnce(&CTLFormulaArena<AP>, &[FormulaId], &mut [Option<Bdd>]) -> R,
+    ) -> R
+    where
+        AP: Sync,
+        DOAP: Sync,
+    {
+        self.with_sat_sets_computed_by(
+            intern,
+            |arena, roots, known| {
+                get_ctl_subformulae_sat_sets_in_parallel(
+                    &self.core,
+                    arena,
+                    roots,
+                    known,
+                    num_threads,
+                )
+            },
+            then,
+        )
+    }
+
+    /// The cache is only locked to look up the known satisfaction sets,
+    /// before `compute`, and to store the computed ones, after it.
+    fn with_sat_sets_computed_by<R>(
+        &self,
+        intern: impl FnOnce(&mut CTLFormulaArena<AP>) -> Vec<FormulaId>,
+        compute: impl FnOnce(&CTLFormulaArena<AP>, &[FormulaId], Vec<Option<Bdd>>) -> Vec<Option<Bdd>>,
+        then: impl FnOnce(&CTLFormulaArena<AP>, &[FormulaId], &mut [Option<Bdd>]) -> R,
+    ) -> R {
+        let mut arena = CTLFormulaArena::new();
+        let roots = intern(&mut arena);
+        let known = self.cache.lookup(&arena);
+        let mut sub_formulae_memoizer = compute(&arena, &roots, known);
+        self.cache.store(&arena, &sub_formulae_memoizer);
+        then(&arena, &roots, &mut sub_formulae_memoizer)
     }
 
     /// BDD over the current-state variables representing the set of
     /// states satisfying `formula`.
-    fn get_sat_set_as_bdd<AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>(
-        &self,
-        formula: &CTLFormula<AP>,
-    ) -> Rc<Bdd> {
-        let (mut sub_formulae_memoizer, true_bdd) =
-            initialize_memoizer_at_leaves(self.kripke, &self.bdd_repr, formula.collect_leaves());
-        get_ctl_subformula_sat_set_rec(
-            &self.bdd_repr,
-            &true_bdd,
-            &mut sub_formulae_memoizer,
-            formula,
+    fn get_sat_set_as_bdd(&self, formula: &CTLFormula<AP>) -> Bdd {
+        self.with_sat_sets(|arena| vec![arena.intern(formula)], false, take_root)
+    }
+
+    /// BDD over the current-state variables representing the set of
+    /// states satisfying the formula designated by `root` in `arena`.
+    fn get_sat_set_in_arena_as_bdd(&self, arena: &CTLFormulaArena<AP>, root: FormulaId) -> Bdd {
+        self.with_sat_sets(
+            |query_arena| vec![query_arena.import(arena, root)],
+            false,
+            take_root,
+        )
+    }
+
+    /// Computes the satisfaction set of every sub-formula of `formula`,
+    /// as a tree mirroring it, in a single labelling pass.
+    ///
+    /// Sub-formulae occurring several times are computed once, but their
+    /// satisfaction set is repeated at each occurrence.
+    pub fn get_annotated_sat_sets(&self, formula: &CTLFormula<AP>) -> AnnotatedCTLFormula<AP> {
+        self.with_sat_sets(
+            |arena| vec![arena.intern(formula)],
+            true,
+            |arena, _, sub_formulae_memoizer| {
+                let mut annotator = SatSetAnnotator {
+                    core: &self.core,
+                    arena,
+                    sub_formulae_memoizer,
+                    state_sets: vec![None; sub_formulae_memoizer.len()],
+                };
+                let (_, annotated) = formula.fold(&mut annotator);
+                annotated
+            },
         )
     }
 
     /// Computes the set of ids of the states satisfying `formula`.
-    pub fn get_sat_set<AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>(
+    pub fn get_sat_set(&self, formula: &CTLFormula<AP>) -> HashSet<usize> {
+        let sat_set_bdd = self.get_sat_set_as_bdd(formula);
+        self.core.bdd_to_states(&sat_set_bdd)
+    }
+
+    /// As [Self::get_sat_set], for the formula designated by `root` in
+    /// `arena`.
+    ///
+    /// Panics if `root` was not produced by `arena`.
+    pub fn get_sat_set_in_arena(
         &self,
-        formula: &CTLFormula<AP>,
+        arena: &CTLFormulaArena<AP>,
+        root: FormulaId,
     ) -> HashSet<usize> {
-        let sat_set_bdd = self.get_sat_set_as_bdd(formula);
-        let mut states = HashSet::new();
-        for st_id in 0..self.kripke.states().len() {
-            let bdd_with_only_that_state = self.bdd_repr.get_state_formula(st_id);
-            if !sat_set_bdd.and(&bdd_with_only_that_state).is_false() {
-                states.insert(st_id);
-            }
-        }
-        states
+        let sat_set_bdd = self.get_sat_set_in_arena_as_bdd(arena, root);
+        self.core.bdd_to_states(&sat_set_bdd)
     }
 
     /// Checks whether `formula` is satisfied from the given set of
@@ -128,11 +458,74 @@ impl<'a, DOAP> CtlModelChecker<'a, DOAP> {
     ///
     /// Note that with an empty `initial_states` set the result is
     /// vacuously `true`, whatever the formula (including `false`).
-    pub fn is_ctl_formula_sat<AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>(
+    pub fn is_ctl_formula_sat(
         &self,
         initial_states: &HashSet<usize>,
         formula: &CTLFormula<AP>,
     ) -> Result<bool, CtlModelCheckingError> {
+        self.core.check_initial_states(initial_states)?;
+        let sat_set_bdd = self.get_sat_set_as_bdd(formula);
+        Ok(self
+            .core
+            .holds_on_initial_states(initial_states, &sat_set_bdd))
+    }
+
+    /// As [Self::is_ctl_formula_sat], for the formula designated by
+    /// `root` in `arena`.
+    ///
+    /// Panics if `root` was not produced by `arena`.
+    pub fn is_ctl_formula_sat_in_arena(
+        &self,
+        initial_states: &HashSet<usize>,
+        arena: &CTLFormulaArena<AP>,
+        root: FormulaId,
+    ) -> Result<bool, CtlModelCheckingError> {
+        self.core.check_initial_states(initial_states)?;
+        let sat_set_bdd = self.get_sat_set_in_arena_as_bdd(arena, root);
+        Ok(self
+            .core
+            .holds_on_initial_states(initial_states, &sat_set_bdd))
+    }
+}
+
+impl<DOAP> CheckerCore<'_, DOAP> {
+    fn restrict_to_reachable(
+        &mut self,
+        initial_states: &HashSet<usize>,
+    ) -> Result<(), CtlModelCheckingError> {
+        self.check_initial_states(initial_states)?;
+        let initial_states_bdd = self.bdd_repr.get_states_set_formula(initial_states);
+        let bdd = self
+            .bdd_repr
+            .get_forward_reachable_states(&initial_states_bdd);
+        let mut state_ids: Vec<usize> = self.bdd_to_states(&bdd).into_iter().collect();
+        state_ids.sort_unstable();
+        self.reachable = Some(ReachableStates { bdd, state_ids });
+        Ok(())
+    }
+
+    pub(crate) fn bdd_to_states(&self, sat_set_bdd: &Bdd) -> HashSet<usize> {
+        let is_in_sat_set = |st_id: usize| {
+            let bdd_with_only_that_state = self.bdd_repr.get_state_formula(st_id);
+            !sat_set_bdd.and(&bdd_with_only_that_state).is_false()
+        };
+        match &self.reachable {
+            Some(reachable) => reachable
+                .state_ids
+                .iter()
+                .copied()
+                .filter(|st_id| is_in_sat_set(*st_id))
+                .collect(),
+            None => (0..self.kripke.states().len())
+                .filter(|st_id| is_in_sat_set(*st_id))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn check_initial_states(
+        &self,
+        initial_states: &HashSet<usize>,
+    ) -> Result<(), CtlModelCheckingError> {
         let num_states = self.kripke.states().len();
         // report the smallest offending id so that the error is
         // deterministic whatever the iteration order of the set
@@ -142,10 +535,106 @@ impl<'a, DOAP> CtlModelChecker<'a, DOAP> {
                 num_states,
             });
         }
-        let sat_set_bdd = self.get_sat_set_as_bdd(formula);
+        if let Some(reachable) = &self.reachable
+            && let Some(unreachable_id) = initial_states
+                .iter()
+                .filter(|id| reachable.state_ids.binary_search(id).is_err())
+                .min()
+        {
+            return Err(CtlModelCheckingError::UnreachableInitialState {
+                initial_state_id: *unreachable_id,
+            });
+        }
+        Ok(())
+    }
+
+    fn holds_on_initial_states(&self, initial_states: &HashSet<usize>, sat_set_bdd: &Bdd) -> bool {
         let initial_states_bdd = self.bdd_repr.get_states_set_formula(initial_states);
-        let implication = initial_states_bdd.imp(&sat_set_bdd);
-        Ok(implication.is_true())
+        let implication = initial_states_bdd.imp(sat_set_bdd);
+        implication.is_true()
+    }
+}
+
+/// The satisfaction set of the single formula of `roots`.
+fn take_root<AP>(
+    _: &CTLFormulaArena<AP>,
+    roots: &[FormulaId],
+    sub_formulae_memoizer: &mut [Option<Bdd>],
+) -> Bdd {
+    sub_formulae_memoizer[roots[0].index()]
+        .take()
+        .expect("the root is reachable from itself")
+}
+
+/// Rebuilds a formula bottom-up, annotating each node with the
+/// satisfaction set memoized for its id.
+struct SatSetAnnotator<'c, 'k, DOAP, AP> {
+    core: &'c CheckerCore<'k, DOAP>,
+    arena: &'c CTLFormulaArena<AP>,
+    sub_formulae_memoizer: &'c [Option<Bdd>],
+    /// the state sets already extracted from the memoized BDDs
+    state_sets: Vec<Option<HashSet<usize>>>,
+}
+
+impl<DOAP, AP: PartialEq + Eq + Clone + Hash> SatSetAnnotator<'_, '_, DOAP, AP> {
+    fn annotate(
+        &mut self,
+        node: CTLFormulaNode<AP>,
+        annotated_node: AnnotatedCTLNode<AP>,
+    ) -> (FormulaId, AnnotatedCTLFormula<AP>) {
+        let id = self
+            .arena
+            .id_of(&node)
+            .expect("the formula was interned in the arena");
+        let sat_set = self.state_sets[id.index()]
+            .get_or_insert_with(|| {
+                let sat_set_bdd = self.sub_formulae_memoizer[id.index()]
+                    .as_ref()
+                    .expect("every sub-formula was labelled");
+                self.core.bdd_to_states(sat_set_bdd)
+            })
+            .clone();
+        (
+            id,
+            AnnotatedCTLFormula {
+                node: annotated_node,
+                sat_set,
+            },
+        )
+    }
+}
+
+impl<DOAP, AP: PartialEq + Eq + Clone + Hash> CTLFormulaFolder<AP>
+    for SatSetAnnotator<'_, '_, DOAP, AP>
+{
+    type Output = (FormulaId, AnnotatedCTLFormula<AP>);
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<AP>) -> Self::Output {
+        self.annotate(
+            CTLFormulaNode::Leaf(leaf.clone()),
+            AnnotatedCTLNode::Leaf(leaf.clone()),
+        )
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, sub: Self::Output) -> Self::Output {
+        let (id1, phi1) = sub;
+        self.annotate(
+            CTLFormulaNode::Unary(op.clone(), id1),
+            AnnotatedCTLNode::Unary(op.clone(), Box::new(phi1)),
+        )
+    }
+
+    fn fold_binary(
+        &mut self,
+        op: &BinaryCTLOperator,
+        left: Self::Output,
+        right: Self::Output,
+    ) -> Self::Output {
+        let ((id1, phi1), (id2, phi2)) = (left, right);
+        self.annotate(
+            CTLFormulaNode::Binary(op.clone(), id1, id2),
+            AnnotatedCTLNode::Binary(op.clone(), Box::new(phi1), Box::new(phi2)),
+        )
     }
 }
 
@@ -168,133 +657,275 @@ pub fn is_ctl_formula_sat<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + C
     CtlModelChecker::new(kripke).is_ctl_formula_sat(initial_states, formula)
 }
 
-fn initialize_memoizer_at_leaves<
-    'a,
+/// Computes the satisfaction sets of all the sub-formulae of the
+/// formulae designated by `roots`, in a single sweep over the states for
+/// all their atoms : the returned memoizer is indexed by
+/// [FormulaId::index], with `None` for the formulae of `arena` that do
+/// not occur below `roots`. The satisfaction sets given by `known` are
+/// taken as is, and the operands of their formulae are not visited
+/// unless `every_subformula` holds.
+///
+/// With a checker restricted to the reachable states, every
+/// satisfaction set is restricted to them.
+fn get_ctl_subformulae_sat_sets<
     DOAP,
     AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash,
 >(
-    kripke: &KripkeStructure<DOAP>,
-    mc: &KripkeStructureBddRepresentation,
-    leaves: CollectedLeaves<'a, AP>,
-) -> (HashMap<&'a CTLFormula<AP>, Rc<Bdd>>, Rc<Bdd>) {
-    // ***
-    let mut atoms_memoizer = HashMap::new();
-    for atom in leaves.atoms {
-        atoms_memoizer.insert(atom, mc.var_set.mk_false());
+    core: &CheckerCore<'_, DOAP>,
+    arena: &CTLFormulaArena<AP>,
+    roots: &[FormulaId],
+    known: Vec<Option<Bdd>>,
+    every_subformula: bool,
+) -> Vec<Option<Bdd>> {
+    let (mut sub_formulae_memoizer, to_compute) =
+        get_subformulae_to_compute(arena, roots, known, every_subformula);
+    let (atom_ids, other_ids): (Vec<FormulaId>, Vec<FormulaId>) = to_compute
+        .into_iter()
+        .partition(|id| is_atomic_proposition(arena.node(*id)));
+    let atom_bdds = label_atomic_propositions(core, arena, &atom_ids);
+    for (atom_id, bdd) in atom_ids.into_iter().zip(atom_bdds) {
+        sub_formulae_memoizer[atom_id.index()] = Some(bdd);
+    }
+    // operands have smaller ids than the formulae containing them
+    let domain_bdd = get_domain_bdd(core);
+    for id in other_ids {
+        let phi_bdd =
+            get_sat_set_from_operands(core, &domain_bdd, arena.node(id), &sub_formulae_memoizer);
+        sub_formulae_memoizer[id.index()] = Some(phi_bdd);
     }
-    for (stid, state) in kripke.states().iter().enumerate() {
+    sub_formulae_memoizer
+}
+
+/// As [get_ctl_subformulae_sat_sets], with every sub-formula, on
+/// `num_threads` threads : the atoms are split between the threads, then
+/// the other sub-formulae are computed by increasing height, those of
+/// the same height being independent.
+fn get_ctl_subformulae_sat_sets_in_parallel<
+    DOAP: Sync,
+    AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash + Sync,
+>(
+    core: &CheckerCore<'_, DOAP>,
+    arena: &CTLFormulaArena<AP>,
+    roots: &[FormulaId],
+    known: Vec<Option<Bdd>>,
+    num_threads: usize,
+) -> Vec<Option<Bdd>> {
+    let num_threads = num_threads.max(1);
+    let (mut sub_formulae_memoizer, to_compute) =
+        get_subformulae_to_compute(arena, roots, known, false);
+    let (atom_ids, other_ids): (Vec<FormulaId>, Vec<FormulaId>) = to_compute
+        .into_iter()
+        .partition(|id| is_atomic_proposition(arena.node(*id)));
+    let atom_bdds: Vec<Bdd> = in_parallel(&atom_ids, num_threads, |chunk| {
+        label_atomic_propositions(core, arena, chunk)
+    });
+    for (atom_id, bdd) in atom_ids.into_iter().zip(atom_bdds) {
+        sub_formulae_memoizer[atom_id.index()] = Some(bdd);
+    }
+    // the height of a formula above the known ones and the atoms
+    let mut heights = vec![0; sub_formulae_memoizer.len()];
+    let mut by_height: Vec<Vec<FormulaId>> = Vec::new();
+    for id in other_ids {
+        let height = 1 + arena
+            .node(id)
+            .children()
+            .map(|child| heights[child.index()])
+            .max()
+            .unwrap_or(0);
+        heights[id.index()] = height;
+        if by_height.len() < height {
+            by_height.resize(height, Vec::new());
+        }
+        by_height[height - 1].push(id);
+    }
+    let domain_bdd = get_domain_bdd(core);
+    for ids in by_height {
+        let memoizer = &sub_formulae_memoizer;
+        let bdds = in_parallel(&ids, num_threads, |chunk| {
+            chunk
+                .iter()
+                .map(|id| get_sat_set_from_operands(core, &domain_bdd, arena.node(*id), memoizer))
+                .collect()
+        });
+        for (id, bdd) in ids.into_iter().zip(bdds) {
+            sub_formulae_memoizer[id.index()] = Some(bdd);
+        }
+    }
+    sub_formulae_memoizer
+}
+
+/// The concatenation of the results of `job` on the chunks of `items`,
+/// which are processed on at most `num_threads` threads.
+pub(crate) fn in_parallel<T: Sync, R: Send>(
+    items: &[T],
+    num_threads: usize,
+    job: impl Fn(&[T]) -> Vec<R> + Sync,
+) -> Vec<R> {
+    if num_threads <= 1 || items.len() <= 1 {
+        return job(items);
+    }
+    let chunk_size = items.len().div_ceil(num_threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let job = &job;
+                scope.spawn(move || job(chunk))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic_payload| std::panic::resume_unwind(panic_payload))
+            })
+            .collect()
+    })
+}
+
+/// The memoizer with the `known` satisfaction sets of the sub-formulae
+/// of `roots`, and the ids of those to compute, in increasing order.
+fn get_subformulae_to_compute<AP: PartialEq + Eq + Clone + Hash>(
+    arena: &CTLFormulaArena<AP>,
+    roots: &[FormulaId],
+    mut known: Vec<Option<Bdd>>,
+    every_subformula: bool,
+) -> (Vec<Option<Bdd>>, Vec<FormulaId>) {
+    let len = roots.iter().map(|root| root.index() + 1).max().unwrap_or(0);
+    let mut sub_formulae_memoizer: Vec<Option<Bdd>> = vec![None; len];
+    let mut to_compute = Vec::new();
+    let mut visited = vec![false; len];
+    let mut to_visit = roots.to_vec();
+    while let Some(id) = to_visit.pop() {
+        if std::mem::replace(&mut visited[id.index()], true) {
+            continue;
+        }
+        if let Some(sat_set) = known.get_mut(id.index()).and_then(Option::take) {
+            sub_formulae_memoizer[id.index()] = Some(sat_set);
+            if !every_subformula {
+                continue;
+            }
+        } else {
+            to_compute.push(id);
+        }
+        to_visit.extend(arena.node(id).children());
+    }
+    to_compute.sort_unstable();
+    (sub_formulae_memoizer, to_compute)
+}
+
+fn is_atomic_proposition<AP>(node: &CTLFormulaNode<AP>) -> bool {
+    matches!(node, CTLFormulaNode::Leaf(CTLFormulaLeaf::AtomicProp(_)))
+}
+
+/// The satisfaction sets of the atomic propositions `atom_ids`,
+/// evaluated in a single sweep over the states the checker considers.
+fn label_atomic_propositions<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>(
+    core: &CheckerCore<'_, DOAP>,
+    arena: &CTLFormulaArena<AP>,
+    atom_ids: &[FormulaId],
+) -> Vec<Bdd> {
+    let (kripke, mc) = (core.kripke, &core.bdd_repr);
+    let mut atom_bdds = vec![mc.var_set.mk_false(); atom_ids.len()];
+    if atom_ids.is_empty() {
+        return atom_bdds;
+    }
+    let states: Box<dyn Iterator<Item = (usize, &KripkeState<DOAP>)>> = match &core.reachable {
+        Some(domain) => Box::new(
+            domain
+                .state_ids
+                .iter()
+                .map(|stid| (*stid, &kripke.states()[*stid])),
+        ),
+        None => Box::new(kripke.states().iter().enumerate()),
+    };
+    for (stid, state) in states {
         let state_bdd = mc.get_state_formula(stid);
-        for (atom, bdd) in atoms_memoizer.iter_mut() {
-            if let CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(ap)) = atom
+        for (atom_id, bdd) in atom_ids.iter().zip(atom_bdds.iter_mut()) {
+            if let CTLFormulaNode::Leaf(CTLFormulaLeaf::AtomicProp(ap)) = arena.node(*atom_id)
                 && ap.is_satisfied_on_state_domain(&state.value_in_domain)
             {
                 *bdd = bdd.or(&state_bdd);
             }
         }
     }
-    // ***
-    let mut sub_formulae_memoizer: HashMap<&'a CTLFormula<AP>, Rc<Bdd>> = atoms_memoizer
-        .into_iter()
-        .map(|(k, v)| (k, Rc::new(v)))
-        .collect();
-    let true_bdd = Rc::new(mc.var_set.mk_true());
-    if let Some(x) = leaves.true_formula {
-        sub_formulae_memoizer.insert(x, true_bdd.clone());
-    }
-    if let Some(x) = leaves.false_formula {
-        sub_formulae_memoizer.insert(x, Rc::new(mc.var_set.mk_false()));
+    atom_bdds
+}
+
+/// The set of the states the checker is restricted to, or of all the
+/// states.
+fn get_domain_bdd<DOAP>(core: &CheckerCore<'_, DOAP>) -> Bdd {
+    match &core.reachable {
+        Some(reachable) => reachable.bdd.clone(),
+        None => core.bdd_repr.var_set.mk_true(),
     }
-    // ***
-    (sub_formulae_memoizer, true_bdd)
 }
 
-fn get_ctl_subformula_sat_set_rec<
-    'a,
-    DOAP,
-    AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash,
->(
-    mc: &KripkeStructureBddRepresentation,
-    true_bdd: &Rc<Bdd>,
-    sub_formulae_memoizer: &mut HashMap<&'a CTLFormula<AP>, Rc<Bdd>>,
-    phi: &'a CTLFormula<AP>,
-) -> Rc<Bdd> {
-    if let Some(got_bdd) = sub_formulae_memoizer.get(phi) {
-        return got_bdd.clone();
-    }
-    let phi_bdd = match phi {
-        CTLFormula::Unary(un_op, phi1) => {
-            let bdd1 = get_ctl_subformula_sat_set_rec(mc, true_bdd, sub_formulae_memoizer, phi1);
+/// The satisfaction set of `node`, other than an atomic proposition,
+/// from those of its operands, included in the domain `true_bdd` : the
+/// complements (negation, implication, equivalence) and the preimages
+/// (`AX`, `EX`) are the operations which may leave it, and are
+/// intersected with it ; the fixpoints stay within their operands.
+fn get_sat_set_from_operands<DOAP, AP>(
+    core: &CheckerCore<'_, DOAP>,
+    true_bdd: &Bdd,
+    node: &CTLFormulaNode<AP>,
+    sub_formulae_memoizer: &[Option<Bdd>],
+) -> Bdd {
+    let (kripke, mc) = (core.kripke, &core.bdd_repr);
+    let strategy = core.fixpoint_strategy;
+    let sat_set_of = |id: &FormulaId| {
+        sub_formulae_memoizer[id.index()]
+            .as_ref()
+            .expect("operands are computed first")
+    };
+    match node {
+        CTLFormulaNode::Leaf(CTLFormulaLeaf::AtomicProp(_)) => {
+            unreachable!("atomic propositions are labelled in a sweep over the states")
+        }
+        CTLFormulaNode::Leaf(CTLFormulaLeaf::True) => true_bdd.clone(),
+        CTLFormulaNode::Leaf(CTLFormulaLeaf::False) => mc.var_set.mk_false(),
+        CTLFormulaNode::Leaf(CTLFormulaLeaf::States(states)) => {
+            let num_states = kripke.states().len();
+            let states = states.iter().filter(|id| *id < num_states).collect();
+            mc.get_states_set_formula(&states).and(true_bdd)
+        }
+        CTLFormulaNode::Unary(un_op, id1) => {
+            let bdd1 = sat_set_of(id1);
             match un_op {
-                UnaryCTLOperator::Not => bdd1.not(),
-                UnaryCTLOperator::AX => {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Strong, &bdd1)
+                UnaryCTLOperator::Not => bdd1.not().and(true_bdd),
+                UnaryCTLOperator::AX => mc
+                    .get_pre_image_by_transition_relation(PreImageKind::Strong, bdd1)
+                    .and(true_bdd),
+                UnaryCTLOperator::EX => mc
+                    .get_pre_image_by_transition_relation(PreImageKind::Weak, bdd1)
+                    .and(true_bdd),
+                UnaryCTLOperator::AF => {
+                    until_fixpoint(strategy, mc, PreImageKind::Strong, true_bdd, bdd1)
                 }
-                UnaryCTLOperator::EX => {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Weak, &bdd1)
+                UnaryCTLOperator::EF => {
+                    until_fixpoint(strategy, mc, PreImageKind::Weak, true_bdd, bdd1)
                 }
-                UnaryCTLOperator::AF => until_fixpoint(true_bdd, bdd1, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Strong, x)
-                }),
-                UnaryCTLOperator::EF => until_fixpoint(true_bdd, bdd1, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Weak, x)
-                }),
-                UnaryCTLOperator::AG => global_fixpoint(bdd1, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Strong, x)
-                }),
-                UnaryCTLOperator::EG => global_fixpoint(bdd1, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Weak, x)
-                }),
+                UnaryCTLOperator::AG => global_fixpoint(strategy, mc, PreImageKind::Strong, bdd1),
+                UnaryCTLOperator::EG => global_fixpoint(strategy, mc, PreImageKind::Weak, bdd1),
             }
         }
-        CTLFormula::Binary(bi_op, phi1, phi2) => {
-            let bdd1 = get_ctl_subformula_sat_set_rec(mc, true_bdd, sub_formulae_memoizer, phi1);
-            let bdd2 = get_ctl_subformula_sat_set_rec(mc, true_bdd, sub_formulae_memoizer, phi2);
+        CTLFormulaNode::Binary(bi_op, id1, id2) => {
+            let bdd1 = sat_set_of(id1);
+            let bdd2 = sat_set_of(id2);
             match bi_op {
-                BinaryCTLOperator::And => bdd1.and(&bdd2),
-                BinaryCTLOperator::Or => bdd1.or(&bdd2),
-                BinaryCTLOperator::Imply => bdd1.imp(&bdd2),
-                BinaryCTLOperator::Iff => bdd1.iff(&bdd2),
-                BinaryCTLOperator::AU => until_fixpoint(&bdd1, bdd2, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Strong, x)
-                }),
-                BinaryCTLOperator::EU => until_fixpoint(&bdd1, bdd2, |x| {
-                    mc.get_pre_image_by_transition_relation(PreImageKind::Weak, x)
-                }),
+                BinaryCTLOperator::And => bdd1.and(bdd2),
+                BinaryCTLOperator::Or => bdd1.or(bdd2),
+                BinaryCTLOperator::Imply => bdd1.imp(bdd2).and(true_bdd),
+                BinaryCTLOperator::Iff => bdd1.iff(bdd2).and(true_bdd),
+                BinaryCTLOperator::AU => {
+                    until_fixpoint(strategy, mc, PreImageKind::Strong, bdd1, bdd2)
+                }
+                BinaryCTLOperator::EU => {
+                    until_fixpoint(strategy, mc, PreImageKind::Weak, bdd1, bdd2)
+                }
             }
         }
-        CTLFormula::Leaf(_) => {
-            panic!("leaf should have been preprocessed")
-        }
-    };
-    sub_formulae_memoizer
-        .entry(phi)
-        .insert_entry(Rc::new(phi_bdd))
-        .get()
-        .clone()
-}
-
-fn global_fixpoint(bdd: Rc<Bdd>, step_fn: impl Fn(&Bdd) -> Bdd) -> Bdd {
-    let mut current = (*bdd).clone();
-    loop {
-        let next = current.and(&step_fn(&current));
-        if next == current {
-            break;
-        } else {
-            current = next;
-        }
-    }
-    current
-}
-
-fn until_fixpoint(before: &Rc<Bdd>, after: Rc<Bdd>, step_fn: impl Fn(&Bdd) -> Bdd) -> Bdd {
-    let mut current = (*after).clone();
-    loop {
-        let next = current.or(&before.and(&step_fn(&current)));
-        if next == current {
-            break;
-        } else {
-            current = next;
-        }
     }
-    current
 }
@@ -0,0 +1,794 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Specification files : named CTL properties, with their expected
+//! verdicts, checked together against a model.
+//!
+//! ```text
+//! // shared definitions
+//! include "common.ctl";
+//!
+//! /* the critical sections
+//!    never overlap */
+//! define resp(a, b) = AG (a => AF b);
+//! spec mutex expect true : AG !(crit1 & crit2);
+//! spec liveness : resp(req1, crit1);
+//! ```
+//!
+//! A file is a sequence of :
+//! - entries `spec name : φ ;`, where the name is made of letters,
+//!   digits, `_`, `-` and `.`, and φ is a formula of the
+//!   [parser](crate::parser) in use (with its
+//!   [syntax profile](crate::parser::syntax)) ; an entry may announce
+//!   its verdict, as in `spec name expect false : φ ;`
+//! - definitions `define name(params) = φ ;` of [named formulae](crate::parser::macros),
+//!   which the formulae of all the files may use, before or after the
+//!   definition, as in `spec resp1 : resp(req1, grant1) ;` with
+//!   `define resp(a, b) = AG (a => AF b) ;` (the parameters and their
+//!   parentheses are optional)
+//! - directives `include "path" ;`, where the path is relative to the
+//!   directory of the including file.
+//!
+//! Line comments `// ...` and block comments `/* ... */` may occur
+//! anywhere, including inside formulae. A file included several times
+//! is read once, and the names of the specifications, as those of the
+//! definitions, must be unique across all the files.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::ctl::{CTLFormula, CTLFormulaTreeBuilder};
+use crate::kripke::AtomicProposition;
+use crate::parser::diagnostics::{CtlDiagnostic, locate, token_at};
+use crate::parser::macros::{CtlDefinitions, Scope, parse_definition_head};
+use crate::parser::{CtlFormulaParser, parse_formula_with_diagnostics};
+use crate::solve::{CtlModelChecker, CtlModelCheckingError};
+
+/// A position in a specification file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpecLocation {
+    /// the file, unless the specification was parsed from a string
+    pub path: Option<PathBuf>,
+    /// the line, starting from 1
+    pub line: usize,
+    /// the column, in characters, starting from 1
+    pub column: usize,
+}
+
+impl fmt::Display for SpecLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A named property read from a specification file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NamedSpec<AP> {
+    pub name: String,
+    pub formula: CTLFormula<AP>,
+    /// the verdict announced by `expect true` / `expect false`, if any
+    pub expected: Option<bool>,
+    /// the location of the name
+    pub location: SpecLocation,
+}
+
+/// The reasons for which a specification file may be rejected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SpecErrorKind {
+    /// the file is malformed, its formulae aside
+    Syntax(String),
+    /// a formula is malformed
+    Formula(CtlDiagnostic),
+    /// a name is declared twice
+    DuplicateName {
+        name: String,
+        previous: SpecLocation,
+    },
+    /// a named formula is defined twice
+    DuplicateDefinition {
+        name: String,
+        previous: SpecLocation,
+    },
+    /// a file could not be read
+    Io { path: PathBuf, message: String },
+    /// a file includes itself, directly or not
+    IncludeCycle { path: PathBuf },
+}
+
+/// An error in a specification file : its location is that of the
+/// faulty input, or that of the `include` directive (or the start of the
+/// file) for errors on files.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpecError {
+    pub location: SpecLocation,
+    pub kind: SpecErrorKind,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} : ", self.location)?;
+        match &self.kind {
+            SpecErrorKind::Syntax(message) => write!(f, "{}", message),
+            SpecErrorKind::Formula(diagnostic) => {
+                write!(f, "{}", diagnostic.message)?;
+                if let Some(hint) = &diagnostic.hint {
+                    write!(f, " ({})", hint)?;
+                }
+                Ok(())
+            }
+            SpecErrorKind::DuplicateName { name, previous } => write!(
+                f,
+                "the specification `{}` is already declared at {}",
+                name, previous
+            ),
+            SpecErrorKind::DuplicateDefinition { name, previous } => write!(
+                f,
+                "the formula `{}` is already defined at {}",
+                name, previous
+            ),
+            SpecErrorKind::Io { path, message } => {
+                write!(f, "cannot read \"{}\" : {}", path.display(), message)
+            }
+            SpecErrorKind::IncludeCycle { path } => {
+                write!(f, "\"{}\" includes itself", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Reads the specification file at `path` and the files it includes,
+/// parsing their formulae with `formula_parser`.
+///
+/// Reports all the errors of the files rather than the first one.
+pub fn parse_spec_file<AP, P: CtlFormulaParser<AP>>(
+    formula_parser: &P,
+    path: impl AsRef<Path>,
+) -> Result<Vec<NamedSpec<AP>>, Vec<SpecError>> {
+    let path = path.as_ref();
+    let mut loader = SpecLoader::new(formula_parser);
+    let start = SpecLocation {
+        path: Some(path.to_path_buf()),
+        line: 1,
+        column: 1,
+    };
+    loader.load_file(path, start);
+    loader.finish()
+}
+
+/// As [parse_spec_file], for a specification given as a string ; its
+/// includes are relative to the current directory.
+pub fn parse_spec_str<AP, P: CtlFormulaParser<AP>>(
+    formula_parser: &P,
+    source: &str,
+) -> Result<Vec<NamedSpec<AP>>, Vec<SpecError>> {
+    let mut loader = SpecLoader::new(formula_parser);
+    loader.load_source(source.to_string(), None, Path::new(""));
+    loader.finish()
+}
+
+/// The text of a file being read.
+struct SpecSource<'s> {
+    path: Option<&'s Path>,
+    source: &'s str,
+    /// the source, comments blanked out (see [blank_comments])
+    text: &'s str,
+}
+
+impl SpecSource<'_> {
+    /// the location of `at`, a sub-slice of the text
+    fn location(&self, at: &str) -> SpecLocation {
+        self.location_of(self.offset(at))
+    }
+
+    fn location_of(&self, offset: usize) -> SpecLocation {
+        // the source and the text have the same byte offsets, but
+        // columns are counted in the characters of the source
+        let (line, column) = locate(self.source, offset);
+        SpecLocation {
+            path: self.path.map(Path::to_path_buf),
+            line,
+            column,
+        }
+    }
+
+    /// the byte offset of `at`, a sub-slice of the text
+    fn offset(&self, at: &str) -> usize {
+        at.as_ptr() as usize - self.text.as_ptr() as usize
+    }
+
+    /// the byte range of `at`, a sub-slice of the text
+    fn range(&self, at: &str) -> Range<usize> {
+        let start = self.offset(at);
+        start..start + at.len()
+    }
+}
+
+/// A syntax error, at a sub-slice of the text.
+type Malformed<'s> = (&'s str, String);
+
+/// A file read, kept until its formulae are parsed.
+#[derive(Default)]
+struct SpecFile {
+    path: Option<PathBuf>,
+    source: String,
+    text: String,
+}
+
+impl SpecFile {
+    fn view(&self) -> SpecSource<'_> {
+        SpecSource {
+            path: self.path.as_deref(),
+            source: &self.source,
+            text: &self.text,
+        }
+    }
+}
+
+/// An entry of a file, whose formula is parsed once all the files are
+/// read, so that definitions may be used before they are declared.
+enum Entry {
+    Spec {
+        name: String,
+        location: SpecLocation,
+        expected: Option<bool>,
+        /// the index of the file, and the range of the formula in its text
+        file: usize,
+        formula: Range<usize>,
+    },
+    Define {
+        name: String,
+        location: SpecLocation,
+        params: Vec<String>,
+        file: usize,
+        body: Range<usize>,
+    },
+    /// an error found while reading the files
+    Error(SpecError),
+}
+
+struct SpecLoader<'p, P> {
+    formula_parser: &'p P,
+    /// the files read, by order of first include
+    files: Vec<SpecFile>,
+    /// the entries of all the files, includes replaced by the entries of
+    /// the included files
+    entries: Vec<Entry>,
+    /// the files being read, the innermost include last
+    reading: Vec<PathBuf>,
+    /// the files read, or being read
+    read: HashSet<PathBuf>,
+}
+
+impl<'p, P> SpecLoader<'p, P> {
+    fn new(formula_parser: &'p P) -> Self {
+        Self {
+            formula_parser,
+            files: Vec::new(),
+            entries: Vec::new(),
+            reading: Vec::new(),
+            read: HashSet::new(),
+        }
+    }
+
+    /// Parses the formulae of the entries, with the definitions of all
+    /// the files.
+    fn finish<AP>(self) -> Result<Vec<NamedSpec<AP>>, Vec<SpecError>>
+    where
+        P: CtlFormulaParser<AP>,
+    {
+        let mut errors = Vec::new();
+        let mut error = |location, kind| errors.push(SpecError { location, kind });
+        let formula_error = |file: &SpecSource<'_>, mut diagnostic: CtlDiagnostic| {
+            let location = file.location_of(diagnostic.offset);
+            (diagnostic.line, diagnostic.column) = (location.line, location.column);
+            (location, SpecErrorKind::Formula(diagnostic))
+        };
+        // the first definition of each name holds
+        let mut definitions = CtlDefinitions::new();
+        let mut defined_at: HashMap<&str, &SpecLocation> = HashMap::new();
+        let mut duplicates = HashSet::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if let Entry::Define {
+                name,
+                location,
+                params,
+                file,
+                body,
+            } = entry
+            {
+                if defined_at.contains_key(name.as_str()) {
+                    duplicates.insert(index);
+                } else {
+                    let params: Vec<&str> = params.iter().map(String::as_str).collect();
+                    definitions.define(name, &params, &self.files[*file].text[body.clone()]);
+                    defined_at.insert(name, location);
+                }
+            }
+        }
+        let mut specs = Vec::new();
+        let mut names: HashMap<&str, &SpecLocation> = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            match entry {
+                Entry::Error(e) => error(e.location.clone(), e.kind.clone()),
+                Entry::Define {
+                    name,
+                    location,
+                    params,
+                    file,
+                    body,
+                } => {
+                    if duplicates.contains(&index) {
+                        let kind = SpecErrorKind::DuplicateDefinition {
+                            name: name.clone(),
+                            previous: defined_at[name.as_str()].clone(),
+                        };
+                        error(location.clone(), kind);
+                        continue;
+                    }
+                    let file = self.files[*file].view();
+                    let params: Vec<&str> = params.iter().map(String::as_str).collect();
+                    let scope = Scope::checking(file.text, &definitions, name, &params);
+                    if let Err(diagnostics) = parse_formula_with_diagnostics(
+                        self.formula_parser,
+                        &mut CTLFormulaTreeBuilder,
+                        &file.text[body.clone()],
+                        scope,
+                    ) {
+                        for diagnostic in diagnostics {
+                            let (location, kind) = formula_error(&file, diagnostic);
+                            error(location, kind);
+                        }
+                    }
+                }
+                Entry::Spec {
+                    name,
+                    location,
+                    expected,
+                    file,
+                    formula,
+                } => {
+                    let file = self.files[*file].view();
+                    let scope = Scope::new(file.text, Some(&definitions));
+                    match parse_formula_with_diagnostics(
+                        self.formula_parser,
+                        &mut CTLFormulaTreeBuilder,
+                        &file.text[formula.clone()],
+                        scope,
+                    ) {
+                        Ok(formula) => {
+                            if let Some(previous) = names.get(name.as_str()) {
+                                let kind = SpecErrorKind::DuplicateName {
+                                    name: name.clone(),
+                                    previous: (*previous).clone(),
+                                };
+                                error(location.clone(), kind);
+                            } else {
+                                names.insert(name, location);
+                                specs.push(NamedSpec {
+                                    name: name.clone(),
+                                    formula,
+                                    expected: *expected,
+                                    location: location.clone(),
+                                });
+                            }
+                        }
+                        Err(diagnostics) => {
+                            for diagnostic in diagnostics {
+                                let (location, kind) = formula_error(&file, diagnostic);
+                                error(location, kind);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(specs)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn error(&mut self, location: SpecLocation, kind: SpecErrorKind) {
+        self.entries
+            .push(Entry::Error(SpecError { location, kind }));
+    }
+
+    /// reads the file at `path`, included at `location`
+    fn load_file(&mut self, path: &Path, location: SpecLocation) {
+        let io_error = |e: std::io::Error| SpecErrorKind::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        };
+        let canonical = match fs::canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(e) => return self.error(location, io_error(e)),
+        };
+        if self.reading.contains(&canonical) {
+            let path = path.to_path_buf();
+            return self.error(location, SpecErrorKind::IncludeCycle { path });
+        }
+        if !self.read.insert(canonical.clone()) {
+            return;
+        }
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => return self.error(location, io_error(e)),
+        };
+        self.reading.push(canonical);
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        self.load_source(source, Some(path), base_dir);
+        self.reading.pop();
+    }
+
+    fn load_source(&mut self, source: String, path: Option<&Path>, base_dir: &Path) {
+        let (text, unterminated_comment) = blank_comments(&source);
+        // the index of the file is that of its first include
+        let index = self.files.len();
+        self.files.push(SpecFile::default());
+        let file = SpecSource {
+            path,
+            source: &source,
+            text: &text,
+        };
+        if let Some(offset) = unterminated_comment {
+            let message = "unterminated block comment".to_string();
+            self.error(file.location_of(offset), SpecErrorKind::Syntax(message));
+        }
+        let mut rest = file.text;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            let parsed = if let Some(after) = parse_keyword("include", rest) {
+                self.parse_include(&file, base_dir, after)
+            } else if let Some(after) = parse_keyword("spec", rest) {
+                self.parse_spec(&file, index, after)
+            } else if let Some(after) = parse_keyword("define", rest) {
+                self.parse_define(&file, index, after)
+            } else {
+                Err((
+                    rest,
+                    format!(
+                        "expected `spec`, `define` or `include`, found {}",
+                        found(rest)
+                    ),
+                ))
+            };
+            rest = match parsed {
+                Ok(after) => after,
+                // resume after the end of the faulty entry
+                Err((at, message)) => {
+                    self.error(file.location(at), SpecErrorKind::Syntax(message));
+                    at.find(';').map_or("", |end| &at[end + 1..])
+                }
+            };
+        }
+        self.files[index] = SpecFile {
+            path: path.map(Path::to_path_buf),
+            source,
+            text,
+        };
+    }
+
+    /// `include "path" ;`, after the keyword
+    fn parse_include<'s>(
+        &mut self,
+        file: &SpecSource<'s>,
+        base_dir: &Path,
+        input: &'s str,
+    ) -> Result<&'s str, Malformed<'s>> {
+        let at = input.trim_start();
+        let Some(quoted) = at.strip_prefix('"') else {
+            return Err((at, format!("expected a quoted path, found {}", found(at))));
+        };
+        let Some(end) = quoted
+            .find(['"', '\n'])
+            .filter(|end| quoted[*end..].starts_with('"'))
+        else {
+            return Err((at, "unterminated string".to_string()));
+        };
+        let rest = parse_semicolon(&quoted[end + 1..])?;
+        self.load_file(&base_dir.join(&quoted[..end]), file.location(at));
+        Ok(rest)
+    }
+
+    /// `spec name [expect true|false] : φ ;`, after the keyword
+    fn parse_spec<'s>(
+        &mut self,
+        file: &SpecSource<'s>,
+        index: usize,
+        input: &'s str,
+    ) -> Result<&'s str, Malformed<'s>> {
+        let at = input.trim_start();
+        let is_name_char = |c: char| c.is_alphanumeric() || "_-.".contains(c);
+        let (name, rest) = at.split_at(at.find(|c| !is_name_char(c)).unwrap_or(at.len()));
+        if name.is_empty() {
+            let message = format!(
+                "expected the name of the specification, found {}",
+                found(at)
+            );
+            return Err((at, message));
+        }
+        let mut rest = rest.trim_start();
+        let mut expected = None;
+        if let Some(after) = parse_keyword("expect", rest) {
+            let at = after.trim_start();
+            let verdicts = [("true", true), ("false", false)];
+            let Some((after, verdict)) = verdicts
+                .into_iter()
+                .find_map(|(keyword, verdict)| Some((parse_keyword(keyword, at)?, verdict)))
+            else {
+                return Err((
+                    at,
+                    format!("expected `true` or `false`, found {}", found(at)),
+                ));
+            };
+            expected = Some(verdict);
+            rest = after.trim_start();
+        }
+        let Some(formula_text) = rest.strip_prefix(':') else {
+            let expectation = if expected.is_none() {
+                "`expect` or `:`"
+            } else {
+                "`:`"
+            };
+            return Err((
+                rest,
+                format!("expected {}, found {}", expectation, found(rest)),
+            ));
+        };
+        let (formula, rest) = parse_formula_text(formula_text)?;
+        self.entries.push(Entry::Spec {
+            name: name.to_string(),
+            location: file.location(name),
+            expected,
+            file: index,
+            formula: file.range(formula),
+        });
+        Ok(rest)
+    }
+
+    /// `define name(params) = φ ;`, after the keyword
+    fn parse_define<'s>(
+        &mut self,
+        file: &SpecSource<'s>,
+        index: usize,
+        input: &'s str,
+    ) -> Result<&'s str, Malformed<'s>> {
+        let head = parse_definition_head(input);
+        if let Some((at, expected)) = head.missing {
+            let message = format!("expected {}, found {}", expected.describe(), found(at));
+            return Err((at, message));
+        }
+        let (body, rest) = parse_formula_text(head.rest)?;
+        self.entries.push(Entry::Define {
+            name: head.name.to_string(),
+            location: file.location(head.name),
+            params: head.params.iter().map(|param| param.to_string()).collect(),
+            file: index,
+            body: file.range(body),
+        });
+        Ok(rest)
+    }
+}
+
+/// the formula of an entry, up to the `;` ending it, and the rest after it
+fn parse_formula_text(input: &str) -> Result<(&str, &str), Malformed<'_>> {
+    let Some(end) = find_unquoted_semicolon(input) else {
+        let at = &input[input.len()..];
+        let message = "expected `;` after the formula, found the end of the input";
+        return Err((at, message.to_string()));
+    };
+    Ok((&input[..end], &input[end + 1..]))
+}
+
+/// the offset of the first `;` of `input` outside of quoted strings, which
+/// may name atoms (e.g. `"a;b"`)
+fn find_unquoted_semicolon(input: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(c) = input[offset..].chars().next() {
+        match c {
+            ';' => return Some(offset),
+            '"' => offset += quoted_len(&input[offset..]),
+            _ => offset += c.len_utf8(),
+        }
+    }
+    None
+}
+
+/// the length of the quoted string starting `input`, up to its closing
+/// quote, or to the end of the line if it is unterminated
+fn quoted_len(input: &str) -> usize {
+    input[1..]
+        .find(['"', '\n'])
+        .map_or(input.len(), |end| end + 2)
+        .min(input.len())
+}
+
+/// `source` with its comments replaced by spaces (newlines aside), so
+/// that byte offsets and lines are preserved, and the offset of an
+/// unterminated block comment if any
+fn blank_comments(source: &str) -> (String, Option<usize>) {
+    let mut blanked = String::with_capacity(source.len());
+    let mut unterminated = None;
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            comment.find("*/").map_or_else(
+                || {
+                    unterminated = Some(source.len() - rest.len());
+                    rest.len()
+                },
+                |end| end + 4,
+            )
+        } else {
+            // strings (paths, quoted atoms) are kept verbatim, even if they
+            // contain "//"
+            let len = if c == '"' {
+                quoted_len(rest)
+            } else {
+                c.len_utf8()
+            };
+            blanked.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        };
+        for c in rest[..len].chars() {
+            if c == '\n' {
+                blanked.push('\n');
+            } else {
+                blanked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+        rest = &rest[len..];
+    }
+    (blanked, unterminated)
+}
+
+/// matches `keyword` up to a word boundary
+fn parse_keyword<'s>(keyword: &str, input: &'s str) -> Option<&'s str> {
+    let rest = input.strip_prefix(keyword)?;
+    if rest.starts_with(|c: char| c.is_alphanumeric() || "_-.".contains(c)) {
+        None
+    } else {
+        Some(rest)
+    }
+}
+
+fn parse_semicolon(input: &str) -> Result<&str, Malformed<'_>> {
+    let at = input.trim_start();
+    at.strip_prefix(';')
+        .ok_or_else(|| (at, format!("expected `;`, found {}", found(at))))
+}
+
+fn found(at: &str) -> String {
+    if at.is_empty() {
+        "the end of the input".to_string()
+    } else {
+        format!("`{}`", token_at(at))
+    }
+}
+
+/// The verdict of a [NamedSpec] on a model.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpecVerdict {
+    pub name: String,
+    pub location: SpecLocation,
+    pub expected: Option<bool>,
+    /// whether the formula holds on every initial state
+    pub holds: bool,
+    /// the smallest initial state on which the formula does not hold
+    pub failing_state: Option<usize>,
+}
+
+impl SpecVerdict {
+    /// Whether the verdict contradicts the announced one.
+    pub fn is_mismatch(&self) -> bool {
+        self.expected.is_some_and(|expected| expected != self.holds)
+    }
+}
+
+impl fmt::Display for SpecVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} : `{}` ", self.location, self.name)?;
+        match self.failing_state {
+            None => write!(f, "holds")?,
+            Some(state) => write!(f, "fails on state {}", state)?,
+        }
+        match self.expected {
+            Some(true) if !self.holds => write!(f, ", but was expected to hold"),
+            Some(false) if self.holds => write!(f, ", but was expected to fail"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The verdicts of the specifications of a file, in file order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpecReport {
+    pub verdicts: Vec<SpecVerdict>,
+}
+
+impl SpecReport {
+    /// The verdicts contradicting the announced ones.
+    pub fn mismatches(&self) -> impl Iterator<Item = &SpecVerdict> {
+        self.verdicts.iter().filter(|verdict| verdict.is_mismatch())
+    }
+
+    /// Whether every verdict agrees with the announced one.
+    pub fn is_success(&self) -> bool {
+        self.mismatches().next().is_none()
+    }
+}
+
+/// Lists the mismatches, then counts the specifications.
+impl fmt::Display for SpecReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for mismatch in self.mismatches() {
+            writeln!(f, "{}", mismatch)?;
+        }
+        write!(
+            f,
+            "{} specifications checked, {} mismatches",
+            self.verdicts.len(),
+            self.mismatches().count()
+        )
+    }
+}
+
+impl<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>
+    CtlModelChecker<'_, DOAP, AP>
+{
+    /// Checks every specification of `specs` from `initial_states`, as a
+    /// batch (see [CtlModelChecker::check_all]), comparing the verdicts
+    /// with the announced ones.
+    ///
+    /// Fails with [CtlModelCheckingError::OutOfRangeInitialState] as
+    /// [CtlModelChecker::is_ctl_formula_sat] does.
+    pub fn check_specs(
+        &self,
+        initial_states: &HashSet<usize>,
+        specs: &[NamedSpec<AP>],
+    ) -> Result<SpecReport, CtlModelCheckingError> {
+        let formulas: Vec<CTLFormula<AP>> = specs.iter().map(|spec| spec.formula.clone()).collect();
+        let verdicts = self
+            .check_all(initial_states, &formulas)?
+            .into_iter()
+            .zip(specs)
+            .map(|(verdict, spec)| SpecVerdict {
+                name: spec.name.clone(),
+                location: spec.location.clone(),
+                expected: spec.expected,
+                holds: verdict.holds,
+                failing_state: verdict.failing_state,
+            })
+            .collect();
+        Ok(SpecReport { verdicts })
+    }
+}
@@ -0,0 +1,596 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Formula templates quantified over ranges of indices, for properties of
+//! parameterized systems :
+//!
+//! ```text
+//! forall i in 0..n: AG (req[i] => AF grant[i])
+//! forall i in 0..n: forall j in i+1..n: AG !(crit[i] & crit[j])
+//! exists i in 0..=2: EF leader[(i + 1) % 3]
+//! ```
+//!
+//! The quantifiers prefix the formula. Their bounds, and the indices
+//! between the brackets following an identifier (as in `req[i]`), are
+//! integer expressions (`+`, `-`, `*`, `/`, `%` and parentheses) over
+//! the variables of the enclosing quantifiers and over named constants ;
+//! ranges are half-open (`0..n`) or inclusive (`0..=n`). Each instance
+//! of the formula is the text of the formula with the indices replaced by
+//! their values (`AG (req[0] => AF grant[0])`), parsed by the formula
+//! parser : indexed atoms are thus read by its
+//! [parse_atomic_proposition](crate::parser::CtlFormulaParser::parse_atomic_proposition).
+//!
+//! A template stands for the conjunction (`forall`) or the disjunction
+//! (`exists`) of its instances, `true` or `false` respectively over an
+//! empty range ; [CtlModelChecker::check_template](crate::solve::CtlModelChecker::check_template) also reports the
+//! verdict of each instance.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::ctl::*;
+use crate::kripke::AtomicProposition;
+use crate::parser::macros::parse_identifier;
+use crate::parser::{CtlFormulaParser, CtlParseError, error_snippet};
+use crate::solve::{CtlModelChecker, CtlModelCheckingError};
+
+/// The maximal number of instances of a template, beyond which
+/// [parse_ctl_template] fails with [CtlTemplateError::TooManyInstances].
+pub const MAX_TEMPLATE_INSTANCES: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Quantifier {
+    ForAll,
+    Exists,
+}
+
+/// A quantifier of a template, with its variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QuantifiedIndex {
+    pub quantifier: Quantifier,
+    pub variable: String,
+}
+
+/// An instance of a template : its formula for given values of the
+/// variables.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CtlInstance<AP> {
+    /// the values of the variables, in the order of the quantifiers
+    pub indices: Vec<i64>,
+    /// the text of the formula, with the indices replaced by their values
+    pub text: String,
+    pub formula: CTLFormula<AP>,
+}
+
+/// A parsed template : its instances, and the formula they make up.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CtlTemplate<AP> {
+    pub quantifiers: Vec<QuantifiedIndex>,
+    /// in the lexicographic order of their indices
+    pub instances: Vec<CtlInstance<AP>>,
+    /// the conjunctions and disjunctions of the instances
+    pub formula: CTLFormula<AP>,
+}
+
+/// The reasons for which [parse_ctl_template] may reject a template.
+/// Offsets are byte offsets into the template.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CtlTemplateError {
+    /// a quantifier or an integer expression is malformed
+    SyntaxError { offset: usize, near: String },
+    /// an integer expression uses a name which is neither a variable in
+    /// scope nor a constant
+    UndefinedIndex { offset: usize, name: String },
+    /// an integer expression overflows or divides by zero
+    InvalidIndex { offset: usize },
+    /// the template has more than `limit` instances (see
+    /// [MAX_TEMPLATE_INSTANCES])
+    TooManyInstances { limit: usize },
+    /// an instance is not a valid formula ; the error is located in its
+    /// text
+    InvalidInstance {
+        indices: Vec<i64>,
+        text: String,
+        error: CtlParseError,
+    },
+}
+
+impl fmt::Display for CtlTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CtlTemplateError::SyntaxError { offset, near } => write!(
+                f,
+                "template syntax error at offset {} near \"{}\"",
+                offset, near
+            ),
+            CtlTemplateError::UndefinedIndex { offset, name } => write!(
+                f,
+                "`{}` is neither an index variable nor a constant, at offset {}",
+                name, offset
+            ),
+            CtlTemplateError::InvalidIndex { offset } => write!(
+                f,
+                "the integer expression at offset {} overflows or divides by zero",
+                offset
+            ),
+            CtlTemplateError::TooManyInstances { limit } => {
+                write!(f, "the template has more than {} instances", limit)
+            }
+            CtlTemplateError::InvalidInstance {
+                indices,
+                text,
+                error,
+            } => write!(
+                f,
+                "the instance {:?} of the template, `{}`, is invalid : {}",
+                indices, text, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CtlTemplateError {}
+
+/// Parses `input`, a template whose bounds and indices may use the named
+/// `constants`, and its instances with `formula_parser`.
+pub fn parse_ctl_template<AP: Clone, P: CtlFormulaParser<AP>>(
+    formula_parser: &P,
+    input: &str,
+    constants: &HashMap<String, i64>,
+) -> Result<CtlTemplate<AP>, CtlTemplateError> {
+    let mut cursor = Cursor::checking(input, 0, constants);
+    let mut ranges = Vec::new();
+    loop {
+        cursor.skip_whitespace();
+        let quantifier = if cursor.eat_keyword("forall") {
+            Quantifier::ForAll
+        } else if cursor.eat_keyword("exists") {
+            Quantifier::Exists
+        } else {
+            break;
+        };
+        cursor.skip_whitespace();
+        let variable = cursor.identifier().ok_or_else(|| cursor.syntax_error())?;
+        cursor.skip_whitespace();
+        if !cursor.eat_keyword("in") {
+            return Err(cursor.syntax_error());
+        }
+        let low_start = cursor.position;
+        cursor.expression()?;
+        let low = low_start..cursor.position;
+        if !cursor.eat("..") {
+            return Err(cursor.syntax_error());
+        }
+        let is_inclusive = cursor.eat("=");
+        let high_start = cursor.position;
+        cursor.expression()?;
+        let high = high_start..cursor.position;
+        cursor.skip_whitespace();
+        if !cursor.eat(":") {
+            return Err(cursor.syntax_error());
+        }
+        ranges.push(IndexRange {
+            quantifier,
+            variable,
+            low,
+            high,
+            is_inclusive,
+        });
+        cursor.variables.push((variable, 0));
+    }
+    let body = cursor.position;
+    let indices = index_brackets(input, body);
+    // the indices are checked once, even if a range is empty
+    for index in &indices {
+        let mut index_cursor = Cursor::checking(&input[..index.end], index.start, constants);
+        index_cursor.variables = cursor.variables.clone();
+        index_cursor.whole_expression()?;
+    }
+    let mut instantiation = Instantiation {
+        formula_parser,
+        input,
+        body,
+        ranges: &ranges,
+        indices: &indices,
+        constants,
+        instances: Vec::new(),
+        steps: 0,
+    };
+    let formula = instantiation.instantiate(&mut Vec::new())?;
+    Ok(CtlTemplate {
+        quantifiers: ranges
+            .iter()
+            .map(|range| QuantifiedIndex {
+                quantifier: range.quantifier,
+                variable: range.variable.to_string(),
+            })
+            .collect(),
+        instances: instantiation.instances,
+        formula,
+    })
+}
+
+/// a quantifier, and the byte ranges of its bounds
+struct IndexRange<'t> {
+    quantifier: Quantifier,
+    variable: &'t str,
+    low: Range<usize>,
+    high: Range<usize>,
+    is_inclusive: bool,
+}
+
+/// the byte ranges of the indices in the body starting at `body` : the
+/// contents of the brackets following an identifier or another index,
+/// but not those of the untils `A[..]` / `E[..]`
+fn index_brackets(input: &str, body: usize) -> Vec<Range<usize>> {
+    let mut indices = Vec::new();
+    let mut position = body;
+    while let Some(offset) = input[position..].find('[') {
+        let open = position + offset;
+        position = open + 1;
+        let word_start = input[body..open]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(body, |index| body + index + 1);
+        let word = &input[word_start..open];
+        let follows_index = input[body..open].ends_with(']');
+        if (word.is_empty() && !follows_index) || word == "A" || word == "E" {
+            continue;
+        }
+        let Some(close) = input[position..].find(']') else {
+            break;
+        };
+        indices.push(position..position + close);
+        position += close + 1;
+    }
+    indices
+}
+
+/// The expansion of a template into its instances.
+struct Instantiation<'t, P, AP> {
+    formula_parser: &'t P,
+    input: &'t str,
+    body: usize,
+    ranges: &'t [IndexRange<'t>],
+    indices: &'t [Range<usize>],
+    constants: &'t HashMap<String, i64>,
+    instances: Vec<CtlInstance<AP>>,
+    /// the number of values of the variables enumerated so far
+    steps: usize,
+}
+
+impl<'t, AP: Clone, P: CtlFormulaParser<AP>> Instantiation<'t, P, AP> {
+    /// the value of the expression at `range`, for the `values` of the
+    /// outer variables
+    fn evaluate(&self, range: &Range<usize>, values: &[i64]) -> Result<i64, CtlTemplateError> {
+        let mut cursor = Cursor::checking(&self.input[..range.end], range.start, self.constants);
+        cursor.is_checking = false;
+        cursor.variables = self
+            .ranges
+            .iter()
+            .zip(values)
+            .map(|(range, value)| (range.variable, *value))
+            .collect();
+        cursor.whole_expression()
+    }
+
+    /// the formula made of the instances for the `values` of the outer
+    /// variables
+    fn instantiate(&mut self, values: &mut Vec<i64>) -> Result<CTLFormula<AP>, CtlTemplateError> {
+        let Some(range) = self.ranges.get(values.len()) else {
+            return self.instance(values);
+        };
+        let low = self.evaluate(&range.low, values)?;
+        let mut high = self.evaluate(&range.high, values)?;
+        if range.is_inclusive {
+            high = high.checked_add(1).ok_or(CtlTemplateError::InvalidIndex {
+                offset: range.high.start,
+            })?;
+        }
+        let mut formulas = Vec::new();
+        for value in low..high {
+            self.steps += 1;
+            if self.steps > MAX_TEMPLATE_INSTANCES {
+                return Err(CtlTemplateError::TooManyInstances {
+                    limit: MAX_TEMPLATE_INSTANCES,
+                });
+            }
+            values.push(value);
+            formulas.push(self.instantiate(values)?);
+            values.pop();
+        }
+        Ok(match range.quantifier {
+            Quantifier::ForAll => balanced(BinaryCTLOperator::And, CTLFormulaLeaf::True, formulas),
+            Quantifier::Exists => balanced(BinaryCTLOperator::Or, CTLFormulaLeaf::False, formulas),
+        })
+    }
+
+    /// the instance for the `values` of all the variables
+    fn instance(&mut self, values: &[i64]) -> Result<CTLFormula<AP>, CtlTemplateError> {
+        let mut text = String::new();
+        let mut copied = self.body;
+        for index in self.indices {
+            text.push_str(&self.input[copied..index.start]);
+            text.push_str(&self.evaluate(index, values)?.to_string());
+            copied = index.end;
+        }
+        text.push_str(&self.input[copied..]);
+        let text = text.trim().to_string();
+        match self.formula_parser.parse_complete_ctl_formula(&text) {
+            Ok(formula) => {
+                self.instances.push(CtlInstance {
+                    indices: values.to_vec(),
+                    text,
+                    formula: formula.clone(),
+                });
+                Ok(formula)
+            }
+            Err(error) => Err(CtlTemplateError::InvalidInstance {
+                indices: values.to_vec(),
+                text,
+                error,
+            }),
+        }
+    }
+}
+
+/// the balanced tree of the `op` of `formulas`, so that long ranges do
+/// not nest deeply ; `empty` if there is none
+fn balanced<AP>(
+    op: BinaryCTLOperator,
+    empty: CTLFormulaLeaf<AP>,
+    mut formulas: Vec<CTLFormula<AP>>,
+) -> CTLFormula<AP> {
+    if formulas.is_empty() {
+        return CTLFormula::Leaf(empty);
+    }
+    while formulas.len() > 1 {
+        let mut pairs = Vec::with_capacity(formulas.len().div_ceil(2));
+        let mut iter = formulas.into_iter();
+        while let Some(left) = iter.next() {
+            pairs.push(match iter.next() {
+                Some(right) => CTLFormula::Binary(op.clone(), Box::new(left), Box::new(right)),
+                None => left,
+            });
+        }
+        formulas = pairs;
+    }
+    formulas.pop().expect("one formula is left")
+}
+
+/// A reader of the quantifiers and of the integer expressions of a
+/// template, which ends at the end of `input`.
+struct Cursor<'t> {
+    input: &'t str,
+    position: usize,
+    constants: &'t HashMap<String, i64>,
+    /// the variables in scope, the innermost last
+    variables: Vec<(&'t str, i64)>,
+    /// whether the expressions are only checked, and not evaluated
+    is_checking: bool,
+}
+
+impl<'t> Cursor<'t> {
+    fn checking(input: &'t str, position: usize, constants: &'t HashMap<String, i64>) -> Self {
+        Cursor {
+            input,
+            position,
+            constants,
+            variables: Vec::new(),
+            is_checking: true,
+        }
+    }
+
+    fn rest(&self) -> &'t str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.input.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let is_next = self.rest().starts_with(token);
+        if is_next {
+            self.position += token.len();
+        }
+        is_next
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match parse_identifier(self.rest()) {
+            Some((word, _)) if word == keyword => self.eat(keyword),
+            _ => false,
+        }
+    }
+
+    fn identifier(&mut self) -> Option<&'t str> {
+        let (word, _) = parse_identifier(self.rest())?;
+        self.position += word.len();
+        Some(word)
+    }
+
+    fn syntax_error(&self) -> CtlTemplateError {
+        CtlTemplateError::SyntaxError {
+            offset: self.position,
+            near: error_snippet(self.rest()),
+        }
+    }
+
+    /// an arithmetic result, checked when evaluating
+    fn checked(&self, at: usize, result: Option<i64>) -> Result<i64, CtlTemplateError> {
+        if self.is_checking {
+            return Ok(0);
+        }
+        result.ok_or(CtlTemplateError::InvalidIndex { offset: at })
+    }
+
+    /// an expression spanning the rest of the input
+    fn whole_expression(&mut self) -> Result<i64, CtlTemplateError> {
+        let value = self.expression()?;
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(self.syntax_error());
+        }
+        Ok(value)
+    }
+
+    fn expression(&mut self) -> Result<i64, CtlTemplateError> {
+        let start = self.position;
+        let mut value = self.term()?;
+        loop {
+            self.skip_whitespace();
+            if self.eat("+") {
+                let right = self.term()?;
+                value = self.checked(start, value.checked_add(right))?;
+            } else if self.eat("-") {
+                let right = self.term()?;
+                value = self.checked(start, value.checked_sub(right))?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<i64, CtlTemplateError> {
+        let start = self.position;
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            // `rem_euclid` so that `(i - 1) % n` stays within `0..n`
+            let operation: fn(i64, i64) -> Option<i64> = if self.eat("*") {
+                i64::checked_mul
+            } else if self.eat("/") {
+                i64::checked_div_euclid
+            } else if self.eat("%") {
+                i64::checked_rem_euclid
+            } else {
+                return Ok(value);
+            };
+            let right = self.factor()?;
+            value = self.checked(start, operation(value, right))?;
+        }
+    }
+
+    fn factor(&mut self) -> Result<i64, CtlTemplateError> {
+        self.skip_whitespace();
+        let start = self.position;
+        if self.eat("-") {
+            let value = self.factor()?;
+            return self.checked(start, value.checked_neg());
+        }
+        if self.eat("(") {
+            let value = self.expression()?;
+            self.skip_whitespace();
+            if !self.eat(")") {
+                return Err(self.syntax_error());
+            }
+            return Ok(value);
+        }
+        let digits = self.rest().len()
+            - self
+                .rest()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        if digits > 0 {
+            let literal = &self.rest()[..digits];
+            self.position += digits;
+            return self.checked(start, literal.parse().ok());
+        }
+        let Some(name) = self.identifier() else {
+            return Err(self.syntax_error());
+        };
+        let variable = self
+            .variables
+            .iter()
+            .rev()
+            .find(|(variable, _)| *variable == name);
+        match variable
+            .map(|(_, value)| value)
+            .or_else(|| self.constants.get(name))
+        {
+            Some(value) => Ok(*value),
+            None => Err(CtlTemplateError::UndefinedIndex {
+                offset: start,
+                name: name.to_string(),
+            }),
+        }
+    }
+}
+
+/// The verdict of an instance of a template on a model.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InstanceVerdict {
+    pub indices: Vec<i64>,
+    pub text: String,
+    /// whether the instance holds on every initial state
+    pub holds: bool,
+    /// the smallest initial state on which the instance does not hold
+    pub failing_state: Option<usize>,
+}
+
+/// The verdict of a template on a model, and those of its instances.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TemplateVerdict {
+    /// whether the formula of the template holds on every initial state
+    pub holds: bool,
+    /// in the order of [CtlTemplate::instances]
+    pub instances: Vec<InstanceVerdict>,
+}
+
+impl TemplateVerdict {
+    /// The instances which do not hold on every initial state.
+    pub fn failing_instances(&self) -> impl Iterator<Item = &InstanceVerdict> {
+        self.instances.iter().filter(|instance| !instance.holds)
+    }
+}
+
+impl<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>
+    CtlModelChecker<'_, DOAP, AP>
+{
+    /// Checks the formula of `template`, and each of its instances, from
+    /// `initial_states`.
+    ///
+    /// Fails with [CtlModelCheckingError::OutOfRangeInitialState] as
+    /// [CtlModelChecker::is_ctl_formula_sat] does.
+    pub fn check_template(
+        &self,
+        initial_states: &HashSet<usize>,
+        template: &CtlTemplate<AP>,
+    ) -> Result<TemplateVerdict, CtlModelCheckingError> {
+        let holds = self.is_ctl_formula_sat(initial_states, &template.formula)?;
+        let instances = template
+            .instances
+            .iter()
+            .map(|instance| {
+                let sat_set = self.get_sat_set(&instance.formula);
+                let failing_state = initial_states
+                    .iter()
+                    .filter(|state| !sat_set.contains(state))
+                    .min()
+                    .copied();
+                InstanceVerdict {
+                    indices: instance.indices.clone(),
+                    text: instance.text.clone(),
+                    holds: failing_state.is_none(),
+                    failing_state,
+                }
+            })
+            .collect();
+        Ok(TemplateVerdict { holds, instances })
+    }
+}
@@ -0,0 +1,217 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Vacuity detection for satisfied properties.
+//!
+//! A property may hold for a bad reason : `AG (req => AF grant)` holds on
+//! a model in which `req` never holds, whatever `grant` does. Following
+//! Beer et al. (<https://doi.org/10.1023/A:1008779610539>) and Kupferman &
+//! Vardi (<https://doi.org/10.1007/s10009-002-0093-0>), a passing formula
+//! is *vacuous in an occurrence* of an atomic proposition if replacing
+//! that occurrence by the constant that makes the formula hardest to
+//! satisfy (`false` under an even number of negations, `true` under an
+//! odd one) still passes. By monotonicity, the occurrence could then be
+//! replaced by *any* formula without changing the verdict.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::ctl::*;
+use crate::kripke::AtomicProposition;
+use crate::solve::{CtlModelChecker, CtlModelCheckingError};
+
+/// Whether a sub-formula occurs under an even (positive) or odd
+/// (negative) number of negations. Occurrences under `<=>` are both.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Polarity {
+    Positive,
+    Negative,
+    Mixed,
+}
+
+impl Polarity {
+    fn flipped(self) -> Self {
+        match self {
+            Polarity::Positive => Polarity::Negative,
+            Polarity::Negative => Polarity::Positive,
+            Polarity::Mixed => Polarity::Mixed,
+        }
+    }
+}
+
+/// An occurrence of an atomic proposition in a formula.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AtomOccurrence<AP> {
+    pub position: CTLFormulaPosition,
+    pub atom: AP,
+    pub polarity: Polarity,
+}
+
+/// The vacuity verdict for one [AtomOccurrence] of a passing formula.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AtomOccurrenceVacuity<AP> {
+    pub occurrence: AtomOccurrence<AP>,
+    /// Whether replacing the occurrence by a constant of the opposite
+    /// polarity makes the formula fail ; if not, the formula is vacuous
+    /// in that occurrence.
+    pub affects_verdict: bool,
+    /// When the occurrence affects the verdict : the smallest initial
+    /// state from which the formula holds but would fail without the
+    /// occurrence, i.e., a state on which the property is exercised
+    /// non-vacuously.
+    pub interesting_witness: Option<usize>,
+}
+
+/// The result of [CtlModelChecker::check_vacuity] : one verdict per atom
+/// occurrence, in left-to-right order of occurrence in the formula.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VacuityReport<AP> {
+    pub occurrences: Vec<AtomOccurrenceVacuity<AP>>,
+}
+
+impl<AP> VacuityReport<AP> {
+    /// Whether the formula passes vacuously in at least one occurrence.
+    pub fn is_vacuous(&self) -> bool {
+        self.occurrences.iter().any(|occ| !occ.affects_verdict)
+    }
+
+    /// The occurrences that do not affect the verdict.
+    pub fn vacuous_occurrences(&self) -> impl Iterator<Item = &AtomOccurrence<AP>> {
+        self.occurrences
+            .iter()
+            .filter(|occ| !occ.affects_verdict)
+            .map(|occ| &occ.occurrence)
+    }
+}
+
+impl<AP: Clone> CTLFormula<AP> {
+    /// The occurrences of atomic propositions in this formula, with their
+    /// polarity, in left-to-right order.
+    pub fn atom_occurrences(&self) -> Vec<AtomOccurrence<AP>> {
+        let mut occurrences = Vec::new();
+        // with an explicit stack rather than native recursion, so that
+        // arbitrarily deep formulae are supported
+        let mut position: CTLFormulaPosition = Vec::new();
+        let mut to_visit = vec![OccurrenceStep::Enter(None, self, Polarity::Positive)];
+        while let Some(step) = to_visit.pop() {
+            let (child_index, phi, polarity) = match step {
+                OccurrenceStep::Enter(child_index, phi, polarity) => (child_index, phi, polarity),
+                OccurrenceStep::Exit => {
+                    position.pop();
+                    continue;
+                }
+            };
+            if let Some(child_index) = child_index {
+                position.push(child_index);
+                to_visit.push(OccurrenceStep::Exit);
+            }
+            match phi {
+                CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(ap)) => {
+                    occurrences.push(AtomOccurrence {
+                        position: position.clone(),
+                        atom: ap.clone(),
+                        polarity,
+                    });
+                }
+                CTLFormula::Leaf(_) => {}
+                CTLFormula::Unary(op, phi1) => {
+                    let sub_polarity = match op {
+                        UnaryCTLOperator::Not => polarity.flipped(),
+                        _ => polarity,
+                    };
+                    to_visit.push(OccurrenceStep::Enter(Some(0), phi1, sub_polarity));
+                }
+                CTLFormula::Binary(op, phi1, phi2) => {
+                    let (polarity1, polarity2) = match op {
+                        BinaryCTLOperator::Imply => (polarity.flipped(), polarity),
+                        BinaryCTLOperator::Iff => (Polarity::Mixed, Polarity::Mixed),
+                        _ => (polarity, polarity),
+                    };
+                    // the left operand is visited first
+                    to_visit.push(OccurrenceStep::Enter(Some(1), phi2, polarity2));
+                    to_visit.push(OccurrenceStep::Enter(Some(0), phi1, polarity1));
+                }
+            }
+        }
+        occurrences
+    }
+}
+
+/// A step of the traversal of [CTLFormula::atom_occurrences] : entering
+/// a sub-formula, at a given child index of its parent (except for the
+/// root) and with a given polarity, or leaving it.
+enum OccurrenceStep<'f, AP> {
+    Enter(Option<usize>, &'f CTLFormula<AP>, Polarity),
+    Exit,
+}
+
+impl<DOAP, AP: AtomicProposition<DOAP> + PartialEq + Eq + Clone + Hash>
+    CtlModelChecker<'_, DOAP, AP>
+{
+    /// Checks whether `formula`, satisfied from `initial_states`, is
+    /// satisfied vacuously : for every occurrence of an atomic
+    /// proposition, the occurrence is replaced by `false` if it is
+    /// positive and by `true` if it is negative, and the occurrence does
+    /// not affect the verdict if the formula still holds.
+    /// Mixed occurrences (under `<=>`) are replaced by both constants in
+    /// turn, and affect the verdict if either replacement makes the
+    /// formula fail. Since the formula is not monotonic in such an
+    /// occurrence, a mixed occurrence that does not affect the verdict
+    /// may still matter for replacements other than the constants.
+    ///
+    /// Returns `Ok(None)` when `formula` is not satisfied from
+    /// `initial_states` : vacuity is only defined for passing properties.
+    ///
+    /// Fails with [CtlModelCheckingError::OutOfRangeInitialState] as
+    /// [CtlModelChecker::is_ctl_formula_sat] does.
+    pub fn check_vacuity(
+        &self,
+        initial_states: &HashSet<usize>,
+        formula: &CTLFormula<AP>,
+    ) -> Result<Option<VacuityReport<AP>>, CtlModelCheckingError> {
+        if !self.is_ctl_formula_sat(initial_states, formula)? {
+            return Ok(None);
+        }
+        let occurrences = formula
+            .atom_occurrences()
+            .into_iter()
+            .map(|occurrence| {
+                let replacements: &[CTLFormulaLeaf<AP>] = match occurrence.polarity {
+                    Polarity::Positive => &[CTLFormulaLeaf::False],
+                    Polarity::Negative => &[CTLFormulaLeaf::True],
+                    Polarity::Mixed => &[CTLFormulaLeaf::False, CTLFormulaLeaf::True],
+                };
+                let interesting_witness = replacements.iter().find_map(|constant| {
+                    let mutated = formula
+                        .replace_at(&occurrence.position, CTLFormula::Leaf(constant.clone()))
+                        .expect("occurrence positions are valid positions");
+                    let sat_set = self.get_sat_set(&mutated);
+                    initial_states
+                        .iter()
+                        .filter(|st_id| !sat_set.contains(st_id))
+                        .min()
+                        .copied()
+                });
+                AtomOccurrenceVacuity {
+                    occurrence,
+                    affects_verdict: interesting_witness.is_some(),
+                    interesting_witness,
+                }
+            })
+            .collect();
+        Ok(Some(VacuityReport { occurrences }))
+    }
+}
@@ -65,7 +65,7 @@ pub fn assert_same_sat_set(model_name: &str, kripke: &Kripke, lhs: &str, rhs: &s
 /// sweeping many formulae over one model reuses its BDD representation.
 pub fn assert_matches_oracle(
     model_name: &str,
-    checker: &CtlModelChecker<TestDomainOfAp>,
+    checker: &CtlModelChecker<TestDomainOfAp, TestAtomicProp>,
     phi: &CTLFormula<TestAtomicProp>,
 ) {
     let got = checker.get_sat_set(phi);
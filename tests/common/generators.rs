@@ -245,6 +245,7 @@ fn fmt_at_level(phi: &CTLFormula<TestAtomicProp>, min_level: u8) -> String {
             CTLFormulaLeaf::AtomicProp(TestAtomicProp::P) => "p".to_string(),
             CTLFormulaLeaf::AtomicProp(TestAtomicProp::Q) => "q".to_string(),
             CTLFormulaLeaf::AtomicProp(TestAtomicProp::R) => "r".to_string(),
+            CTLFormulaLeaf::States(states) => states.to_string(),
         },
         CTLFormula::Unary(op, phi1) => {
             let op_str = match op {
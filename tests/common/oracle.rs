@@ -118,6 +118,7 @@ pub fn oracle_sat_set(kripke: &Kripke, phi: &CTLFormula<TestAtomicProp>) -> Stat
                 .filter(|(_, state)| ap.is_satisfied_on_state_domain(&state.value_in_domain))
                 .map(|(i, _)| i)
                 .collect(),
+            CTLFormulaLeaf::States(states) => states.iter().filter(|id| all.contains(id)).collect(),
         },
         CTLFormula::Unary(op, phi1) => {
             let s1 = oracle_sat_set(kripke, phi1);
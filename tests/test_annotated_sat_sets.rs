@@ -0,0 +1,74 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the per-sub-formula satisfaction report : every node of the
+//! annotated tree must carry the satisfaction set of its sub-formula.
+
+use citreelo::ctl::{BinaryCTLOperator, UnaryCTLOperator};
+use citreelo::solve::{AnnotatedCTLNode, CtlModelChecker};
+use map_macro::hash_set;
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::parser::parse;
+use common::zoo::{all_total_models, readme_ex1};
+
+#[test]
+fn annotated_readme_example() {
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    let annotated = checker.get_annotated_sat_sets(&parse("EX (p & !q) | AG q"));
+    assert_eq!(annotated.sat_set, hash_set! {1, 2});
+    assert_eq!(annotated.sat_set_size(), 2);
+    let AnnotatedCTLNode::Binary(BinaryCTLOperator::Or, ex, ag) = &annotated.node else {
+        panic!("unexpected shape {:?}", annotated.node)
+    };
+    assert_eq!(ex.sat_set, hash_set! {2});
+    assert_eq!(ag.sat_set, hash_set! {1});
+    assert!(matches!(
+        ag.node,
+        AnnotatedCTLNode::Unary(UnaryCTLOperator::AG, _)
+    ));
+    let at = |position: &[usize]| &annotated.subformula_at(position).unwrap().sat_set;
+    assert_eq!(at(&[0, 0]), &hash_set! {0});
+    assert_eq!(at(&[0, 0, 0]), &hash_set! {0, 2});
+    assert_eq!(at(&[0, 0, 1]), &hash_set! {0});
+    assert_eq!(at(&[0, 0, 1, 0]), &hash_set! {1, 2});
+    assert_eq!(at(&[1, 0]), &hash_set! {1, 2});
+    assert!(annotated.subformula_at(&[2]).is_none());
+}
+
+#[test]
+fn every_node_carries_the_sat_set_of_its_subformula() {
+    for (name, kripke) in all_total_models() {
+        let checker = CtlModelChecker::new(&kripke);
+        for phi in random_formulas(0xA77, 20, 3) {
+            let annotated = checker.get_annotated_sat_sets(&phi);
+            assert_eq!(annotated.formula(), phi);
+            for (position, sub) in phi.positioned_subformulas() {
+                assert_eq!(
+                    annotated.subformula_at(&position).unwrap().sat_set,
+                    checker.get_sat_set(sub),
+                    "sub-formula '{}' of '{}' on model '{}'",
+                    formula_to_string(sub),
+                    formula_to_string(&phi),
+                    name
+                );
+            }
+        }
+    }
+}
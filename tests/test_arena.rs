@@ -0,0 +1,165 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of hash-consed formulae : sharing, round trips between trees
+//! and arenas, parsing directly into an arena, and model checking by id.
+
+use citreelo::arena::{CTLFormulaArena, CTLFormulaNode};
+use citreelo::ctl::{BinaryCTLOperator, CTLFormulaLeaf, UnaryCTLOperator};
+use citreelo::parser::CtlFormulaParser;
+use citreelo::solve::CtlModelChecker;
+use map_macro::hash_set;
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::model::TestAtomicProp;
+use common::oracle::oracle_sat_set;
+use common::parser::{CtlConcreteParser, parse};
+use common::zoo::{all_total_models, readme_ex1};
+
+#[test]
+fn structurally_equal_formulas_share_their_id() {
+    let mut arena = CTLFormulaArena::new();
+    let phi = arena.intern(&parse("AG (p & q) | EF (p & q)"));
+    // p, q, p & q, AG, EF, |
+    assert_eq!(arena.len(), 6);
+    let p_and_q = arena.intern(&parse("p & q"));
+    assert_eq!(arena.len(), 6);
+    let ag = arena.mk_unary(UnaryCTLOperator::AG, p_and_q);
+    let ef = arena.mk_unary(UnaryCTLOperator::EF, p_and_q);
+    assert_eq!(arena.mk_binary(BinaryCTLOperator::Or, ag, ef), phi);
+    assert_eq!(arena.get(&parse("EF (p & q)")), Some(ef));
+    assert_eq!(arena.get(&parse("EF (q & p)")), None);
+    assert_eq!(arena.len(), 6);
+}
+
+#[test]
+fn operands_have_smaller_ids() {
+    let mut arena = CTLFormulaArena::new();
+    for phi in random_formulas(0xA7E, 50, 4) {
+        arena.intern(&phi);
+    }
+    for id in arena.ids() {
+        for child in arena.node(id).children() {
+            assert!(child < id);
+        }
+    }
+}
+
+#[test]
+fn round_trip_through_the_arena() {
+    let mut arena = CTLFormulaArena::new();
+    let formulas = random_formulas(0x12E, 100, 4);
+    let ids: Vec<_> = formulas.iter().map(|phi| arena.intern(phi)).collect();
+    for (phi, id) in formulas.iter().zip(ids) {
+        assert_eq!(
+            arena.to_formula(id),
+            *phi,
+            "'{}' is not preserved by interning",
+            formula_to_string(phi)
+        );
+        assert_eq!(arena.get(phi), Some(id));
+        let sub_ids = arena.reachable_from(id);
+        assert_eq!(sub_ids.last(), Some(&id));
+        assert_eq!(
+            sub_ids.len(),
+            phi.subformulas()
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        );
+    }
+}
+
+#[test]
+fn parsing_directly_into_an_arena() {
+    let parser = CtlConcreteParser {};
+    let mut arena = CTLFormulaArena::new();
+    let id = parser
+        .parse_complete_ctl_formula_with(&mut arena, "A[p U q] & !A[p U q] | true")
+        .unwrap();
+    assert_eq!(arena.to_formula(id), parse("A[p U q] & !A[p U q] | true"));
+    // p, q, A[p U q], its negation, &, true, |
+    assert_eq!(arena.reachable_from(id).len(), 7);
+    assert_eq!(arena.intern(&parse("!A[p U q]")), {
+        let until = arena.get(&parse("A[p U q]")).unwrap();
+        arena.mk_unary(UnaryCTLOperator::Not, until)
+    });
+    assert!(
+        parser
+            .parse_complete_ctl_formula_with(&mut arena, "p q")
+            .is_err()
+    );
+}
+
+#[test]
+fn checking_by_id_agrees_with_checking_trees() {
+    for (name, kripke) in all_total_models() {
+        let checker = CtlModelChecker::new(&kripke);
+        let mut arena = CTLFormulaArena::new();
+        for phi in random_formulas(0x1D5, 30, 3) {
+            let id = arena.intern(&phi);
+            assert_eq!(
+                checker.get_sat_set_in_arena(&arena, id),
+                checker.get_sat_set(&phi),
+                "'{}' on model '{}'",
+                formula_to_string(&phi),
+                name
+            );
+        }
+    }
+}
+
+#[test]
+fn shared_subformulas_are_checked_once() {
+    // φ_0 = p, φ_{n+1} = EX φ_n & AX φ_n : the tree of φ_40 has more
+    // than 2^40 nodes, the DAG 1 + 3 * 40
+    let mut arena = CTLFormulaArena::new();
+    let mut phi = arena.mk_leaf(CTLFormulaLeaf::AtomicProp(TestAtomicProp::P));
+    for _ in 0..40 {
+        let ex = arena.mk_unary(UnaryCTLOperator::EX, phi);
+        let ax = arena.mk_unary(UnaryCTLOperator::AX, phi);
+        phi = arena.mk_binary(BinaryCTLOperator::And, ex, ax);
+    }
+    assert_eq!(arena.reachable_from(phi).len(), 1 + 3 * 40);
+    assert!(matches!(
+        arena.node(phi),
+        CTLFormulaNode::Binary(BinaryCTLOperator::And, _, _)
+    ));
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    // the same formula, unrolled to a small depth, as a cross-check
+    let mut small = parse("p");
+    for _ in 0..3 {
+        let small_str = formula_to_string(&small);
+        small = parse(&format!("EX ({}) & AX ({})", small_str, small_str));
+    }
+    let small_id = arena.intern(&small);
+    assert_eq!(
+        checker.get_sat_set_in_arena(&arena, small_id),
+        oracle_sat_set(&kripke, &small)
+    );
+    let sat_set = checker.get_sat_set_in_arena(&arena, phi);
+    assert_eq!(
+        checker.is_ctl_formula_sat_in_arena(&hash_set! {0}, &arena, phi),
+        Ok(sat_set.contains(&0))
+    );
+    assert!(
+        checker
+            .is_ctl_formula_sat_in_arena(&hash_set! {7}, &arena, phi)
+            .is_err()
+    );
+}
@@ -0,0 +1,89 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the batch checking of many formulae.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use citreelo::predicates::Predicate;
+use citreelo::solve::{CtlModelChecker, CtlModelCheckingError};
+use citreelo_macros::ctl;
+
+mod common;
+
+use common::generators::{random_formulas, random_total_kripke};
+use common::model::{TestAtomicProp, TestDomainOfAp};
+use common::oracle::oracle_sat_set;
+use common::parser::parse;
+use common::zoo::readme_ex1;
+
+#[test]
+fn batch_verdicts_match_oracle_in_input_order() {
+    let formulas = random_formulas(47, 80, 4);
+    for seed in 0..6 {
+        let kripke = random_total_kripke(seed, 8, 2);
+        let initial_states = HashSet::from([0, 5]);
+        let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
+        let verdicts = checker.check_all(&initial_states, &formulas).unwrap();
+        assert_eq!(verdicts.len(), formulas.len());
+        for (verdict, formula) in verdicts.iter().zip(&formulas) {
+            let expected = oracle_sat_set(&kripke, formula);
+            assert_eq!(verdict.sat_set, expected, "{:?}", formula);
+            assert_eq!(verdict.holds, initial_states.is_subset(&expected));
+            let failing_state = initial_states.difference(&expected).min().copied();
+            assert_eq!(verdict.failing_state, failing_state);
+        }
+    }
+}
+
+#[test]
+fn atoms_are_evaluated_once_per_batch() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    let formulas = [
+        ctl!(AG p.clone()),
+        ctl!(EF p.clone() & EX p.clone()),
+        ctl!(AG p.clone()),
+    ];
+    let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
+    let verdicts = checker.check_all(&HashSet::from([0]), &formulas).unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+    assert_eq!(verdicts[0], verdicts[2]);
+    assert!(!verdicts[0].holds);
+    assert_eq!(verdicts[0].failing_state, Some(0));
+    assert!(verdicts[1].holds);
+}
+
+#[test]
+fn batch_initial_states_are_validated() {
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::<_, TestAtomicProp>::new(&kripke);
+    assert_eq!(checker.check_all(&HashSet::from([0]), &[]), Ok(vec![]));
+    assert_eq!(
+        checker.check_all(&HashSet::from([4]), &[parse("p")]),
+        Err(CtlModelCheckingError::OutOfRangeInitialState {
+            initial_state_id: 4,
+            num_states: 3
+        })
+    );
+}
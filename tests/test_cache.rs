@@ -0,0 +1,248 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the satisfaction sets kept by a checker across queries.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use citreelo::arena::CTLFormulaArena;
+use citreelo::ctl::{CTLFormula, UnaryCTLOperator};
+use citreelo::kripke::AtomicProposition;
+use citreelo::predicates::Predicate;
+use citreelo::solve::CtlModelChecker;
+use citreelo_macros::ctl;
+
+mod common;
+
+use common::generators::{random_formulas, random_total_kripke};
+use common::model::{TestAtomicProp, TestDomainOfAp};
+use common::oracle::oracle_sat_set;
+use common::parser::parse;
+use common::zoo::readme_ex1;
+
+#[test]
+fn cached_sat_sets_match_oracle() {
+    let formulas = random_formulas(46, 120, 4);
+    for seed in 0..6 {
+        let kripke = random_total_kripke(seed, 8, 2);
+        for capacity in [0, 5, 10_000] {
+            let checker = CtlModelChecker::new(&kripke).with_cache_capacity(capacity);
+            // twice, so that the second round is answered from the cache
+            for _ in 0..2 {
+                for formula in &formulas {
+                    let expected = oracle_sat_set(&kripke, formula);
+                    assert_eq!(checker.get_sat_set(formula), expected, "{:?}", formula);
+                    let annotated = checker.get_annotated_sat_sets(formula);
+                    assert_eq!(annotated.sat_set, expected, "{:?}", formula);
+                }
+                assert!(checker.cache_len() <= capacity);
+            }
+        }
+    }
+}
+
+#[test]
+fn shared_subformulae_are_computed_once() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    let checker = CtlModelChecker::new(&kripke);
+    checker.get_sat_set(&ctl!(AG p.clone()));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+    checker.get_sat_set(&ctl!(EF p.clone() & AX p.clone()));
+    checker.get_sat_set(&ctl!(AG p.clone()));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+    assert_eq!(checker.cache_len(), 5);
+    checker.clear_cache();
+    assert_eq!(checker.cache_len(), 0);
+    checker.get_sat_set(&ctl!(AG p));
+    assert_eq!(calls.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn least_recently_used_sat_sets_are_evicted() {
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke).with_cache_capacity(3);
+    assert_eq!(checker.cache_capacity(), 3);
+    checker.get_sat_set(&parse("AX p"));
+    checker.get_sat_set(&parse("EX q"));
+    assert_eq!(checker.cache_len(), 3);
+    // the formulae given in an arena share the cache
+    let mut arena = CTLFormulaArena::new();
+    let root = arena.intern(&parse("EX q"));
+    assert_eq!(
+        checker.get_sat_set_in_arena(&arena, root),
+        checker.get_sat_set(&parse("EX q"))
+    );
+    let uncached = CtlModelChecker::new(&kripke).with_cache_capacity(0);
+    assert_eq!(
+        uncached.get_sat_set(&parse("AX p")),
+        checker.get_sat_set(&parse("AX p"))
+    );
+    assert_eq!(uncached.cache_len(), 0);
+    let checker = checker.with_cache_capacity(1);
+    assert_eq!(checker.cache_len(), 1);
+}
+
+#[test]
+fn uncached_queries_compute_every_subformula() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    let checker = CtlModelChecker::new(&kripke).with_cache_capacity(0);
+    checker.get_sat_set(&ctl!(AG p.clone()));
+    checker.get_sat_set(&ctl!(AG p.clone()));
+    assert_eq!(calls.load(Ordering::Relaxed), 6);
+    assert_eq!(checker.cache_len(), 0);
+}
+
+#[test]
+fn vacuity_checks_reuse_the_cache() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    let checker = CtlModelChecker::new(&kripke);
+    let report = checker
+        .check_vacuity(&HashSet::from([0]), &ctl!(p.clone() => EF p.clone()))
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.occurrences.len(), 2);
+    // `p` is evaluated for the formula, not for each of its mutants
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
+
+/// The formulae `op1 op2 q`, for every pair of unary operators.
+fn unary_pairs<AP: Clone>(q: &AP) -> Vec<CTLFormula<AP>> {
+    let ops = [
+        UnaryCTLOperator::Not,
+        UnaryCTLOperator::AX,
+        UnaryCTLOperator::EX,
+        UnaryCTLOperator::AF,
+        UnaryCTLOperator::EF,
+        UnaryCTLOperator::AG,
+        UnaryCTLOperator::EG,
+    ];
+    let unary =
+        |op: &UnaryCTLOperator, phi: CTLFormula<AP>| CTLFormula::Unary(op.clone(), Box::new(phi));
+    ops.iter()
+        .flat_map(|op1| {
+            ops.iter()
+                .map(move |op2| unary(op1, unary(op2, ctl!(q.clone()))))
+        })
+        .collect()
+}
+
+#[test]
+fn sat_sets_survive_the_rebuilds_of_the_arena() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    let q = Predicate::new("q", |domain: &TestDomainOfAp| {
+        domain.atoms.contains(&TestAtomicProp::Q)
+    });
+    let checker = CtlModelChecker::new(&kripke).with_cache_capacity(6);
+    let uncached = CtlModelChecker::new(&kripke).with_cache_capacity(0);
+    let ag_p = ctl!(AG p.clone());
+    let expected = uncached.get_sat_set(&ag_p);
+    // the formulae of the evicted entries pile up in the arena, which is
+    // rebuilt several times, while `AG p` is kept in use
+    for formula in unary_pairs(&q) {
+        assert_eq!(
+            checker.get_sat_set(&formula),
+            uncached.get_sat_set(&formula)
+        );
+        assert_eq!(checker.get_sat_set(&ag_p), expected);
+    }
+    assert_eq!(
+        checker.get_sat_set(&ctl!(EX AG p.clone())),
+        uncached.get_sat_set(&ctl!(EX AG p.clone()))
+    );
+    // once for the checker, and once per query of the uncached one
+    assert_eq!(calls.load(Ordering::Relaxed), 3 + 3 * 2);
+    // so is it when the arena is rebuilt on a change of capacity
+    let checker = checker.with_cache_capacity(10_000);
+    for formula in unary_pairs(&q) {
+        checker.get_sat_set(&formula);
+    }
+    checker.get_sat_set(&ag_p);
+    let checker = checker.with_cache_capacity(2);
+    assert_eq!(checker.get_sat_set(&ag_p), expected);
+    assert_eq!(calls.load(Ordering::Relaxed), 3 + 3 * 2);
+}
+
+/// An atomic proposition which borrows its data and is not `Send`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LocalAtom<'s> {
+    atom: &'s TestAtomicProp,
+    owner: Rc<str>,
+}
+
+impl AtomicProposition<TestDomainOfAp> for LocalAtom<'_> {
+    fn is_satisfied_on_state_domain(&self, state_domain: &TestDomainOfAp) -> bool {
+        state_domain.atoms.contains(self.atom)
+    }
+}
+
+#[test]
+fn atoms_need_not_be_static_nor_send() {
+    let kripke = readme_ex1();
+    let atoms = [TestAtomicProp::P, TestAtomicProp::Q, TestAtomicProp::R];
+    let owner: Rc<str> = Rc::from("local");
+    let local = |formula: &str| {
+        parse(formula).map_atoms(|ap| LocalAtom {
+            atom: atoms.iter().find(|atom| *atom == ap).unwrap(),
+            owner: owner.clone(),
+        })
+    };
+    let initial_states = HashSet::from([0]);
+    let expected = CtlModelChecker::new(&kripke).get_sat_set(&parse("AG (p => EF q)"));
+    let checker = CtlModelChecker::new(&kripke);
+    assert_eq!(checker.get_sat_set(&local("AG (p => EF q)")), expected);
+    assert_eq!(
+        citreelo::solve::get_sat_set(&kripke, &local("AG (p => EF q)")),
+        expected
+    );
+    assert!(
+        checker
+            .is_ctl_formula_sat(&initial_states, &local("EX q"))
+            .unwrap()
+    );
+    assert_eq!(
+        checker.get_annotated_sat_sets(&local("AX p")).sat_set,
+        CtlModelChecker::new(&kripke).get_sat_set(&parse("AX p"))
+    );
+    assert!(!checker.check_all(&initial_states, &[local("q")]).unwrap()[0].holds);
+    assert_eq!(checker.cache_len(), 7);
+}
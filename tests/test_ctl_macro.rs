@@ -0,0 +1,73 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the `ctl!` macro : the formulae it builds at compile time are
+//! those parsed at runtime.
+
+use citreelo::ctl::{CTLFormula, CTLFormulaLeaf};
+use citreelo_macros::ctl;
+
+mod common;
+
+use common::model::TestAtomicProp::{self, P, Q, R};
+use common::parser::parse;
+
+#[test]
+fn macro_follows_the_precedences() {
+    assert_eq!(ctl!(TestAtomicProp::P), parse("p"));
+    assert_eq!(ctl!(true & !false), parse("true & !false"));
+    assert_eq!(ctl!(P & Q | !R), parse("p & q | !r"));
+    assert_eq!(ctl!(P => Q => R), parse("p => (q => r)"));
+    assert_eq!(ctl!(P <=> Q <=> R), parse("(p <=> q) <=> r"));
+    assert_eq!(ctl!(P | Q => R <=> P), parse("((p | q) => r) <=> p"));
+    assert_eq!(ctl!(!AX EF P & AG !Q), parse("(!(AX (EF p))) & (AG (!q))"));
+    assert_eq!(ctl!(AG (P => AF Q)), parse("AG (p => AF q)"));
+    assert_eq!(
+        ctl!(A[P U E[Q & !R U EG P]] | EX (P)),
+        parse("A[p U E[q & !r U EG p]] | EX (p)")
+    );
+    assert_eq!(ctl!(AG(P=>!Q)), parse("AG (p => !q)"));
+}
+
+#[test]
+fn macro_atoms_are_rust_expressions() {
+    let atoms = [P, Q, R];
+    let picked = |index: usize| atoms[index].clone();
+    assert_eq!(
+        ctl!(atoms[0].clone() & picked(1) | { atoms[2].clone() }),
+        parse("p & q | r")
+    );
+    assert_eq!(ctl!(A[1 U {2 + 3}]), numbers());
+    // keywords are atoms between braces
+    #[allow(non_snake_case)]
+    let AX = "ax";
+    assert_eq!(
+        ctl!(AX { AX }),
+        CTLFormula::Unary(
+            citreelo::ctl::UnaryCTLOperator::AX,
+            Box::new(CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp("ax")))
+        )
+    );
+}
+
+/// `A[1 U 5]`
+fn numbers() -> CTLFormula<i32> {
+    CTLFormula::Binary(
+        citreelo::ctl::BinaryCTLOperator::AU,
+        Box::new(CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(1))),
+        Box::new(CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(5))),
+    )
+}
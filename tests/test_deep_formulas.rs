@@ -0,0 +1,185 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests on very deep formulae : parsing and checking must not depend
+//! on the native stack, and the nesting depth limit of the parser must
+//! be reported as a proper error.
+
+use citreelo::arena::CTLFormulaArena;
+use citreelo::ctl::CTLFormula;
+use citreelo::parser::{CtlFormulaParser, CtlParseError, DEFAULT_MAX_NESTING_DEPTH};
+use citreelo::solve::CtlModelChecker;
+use citreelo::vacuity::Polarity;
+
+mod common;
+
+use common::model::TestAtomicProp;
+use common::parser::{CtlConcreteParser, parse, parse_complete};
+use common::zoo::readme_ex1;
+
+/// The test parser, with a custom nesting depth limit.
+struct LimitedParser {
+    limit: usize,
+}
+
+impl CtlFormulaParser<TestAtomicProp> for LimitedParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        CtlConcreteParser {}.parse_atomic_proposition(input)
+    }
+
+    fn max_nesting_depth(&self) -> usize {
+        self.limit
+    }
+}
+
+#[test]
+fn deep_prefix_chains_up_to_the_default_limit() {
+    let depth = DEFAULT_MAX_NESTING_DEPTH - 1;
+    let phi = parse(&format!("{}p", "!".repeat(depth)));
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    // an odd number of negations
+    assert_eq!(checker.get_sat_set(&phi), checker.get_sat_set(&parse("!p")));
+    let phi = parse(&format!("{}p", "AX EX ".repeat(depth / 2)));
+    assert!(matches!(phi, CTLFormula::Unary(..)));
+    assert!(parse_complete(&format!("{}p", "!".repeat(DEFAULT_MAX_NESTING_DEPTH))).is_err());
+}
+
+#[test]
+fn long_binary_chains() {
+    let len = DEFAULT_MAX_NESTING_DEPTH / 2;
+    let conjunction = parse(&vec!["p"; len].join(" & "));
+    let implication = parse(&vec!["q"; len].join(" => "));
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    assert_eq!(
+        checker.get_sat_set(&conjunction),
+        checker.get_sat_set(&parse("p"))
+    );
+    assert_eq!(
+        checker.get_sat_set(&implication),
+        checker.get_sat_set(&parse("q => q"))
+    );
+    // left- and right-associative chains are both as deep as they are long
+    match parse_complete(&vec!["p"; DEFAULT_MAX_NESTING_DEPTH + 1].join(" | ")) {
+        Err(CtlParseError::NestingTooDeep { limit, .. }) => {
+            assert_eq!(limit, DEFAULT_MAX_NESTING_DEPTH)
+        }
+        other => panic!("expected NestingTooDeep, got {:?}", other),
+    }
+}
+
+#[test]
+fn nesting_limit_is_configurable() {
+    let parser = LimitedParser { limit: 3 };
+    assert!(parser.parse_complete_ctl_formula("AX AX p").is_ok());
+    assert!(
+        parser
+            .parse_complete_ctl_formula("(p & q) | A[p U q]")
+            .is_ok()
+    );
+    // the outermost operator is the one exceeding the limit
+    assert_eq!(
+        parser.parse_complete_ctl_formula("AX AX AX p"),
+        Err(CtlParseError::NestingTooDeep {
+            offset: 0,
+            limit: 3
+        })
+    );
+    assert_eq!(
+        parser.parse_complete_ctl_formula("p | E[p U !q]"),
+        Err(CtlParseError::NestingTooDeep {
+            offset: 2,
+            limit: 3
+        })
+    );
+    let msg = parser
+        .parse_complete_ctl_formula("!!!p")
+        .unwrap_err()
+        .to_string();
+    assert!(msg.contains("more than 3"), "unhelpful message: {}", msg);
+    // the combinator-level parser fails without recovery
+    assert!(matches!(
+        parser.parse_ctl_formula::<nom::error::Error<&str>>("!!!p"),
+        Err(nom::Err::Failure(_))
+    ));
+}
+
+#[test]
+fn very_deep_formulas_in_an_arena() {
+    // no tree is ever built : neither parsing nor checking recurses
+    let depth = 200_000;
+    let parser = LimitedParser { limit: usize::MAX };
+    let input = format!(
+        "{}{}p{}",
+        "E[q U ".repeat(depth / 2),
+        "!".repeat(depth / 2),
+        "]".repeat(depth / 2)
+    );
+    let mut arena = CTLFormulaArena::new();
+    let phi = parser
+        .parse_complete_ctl_formula_with(&mut arena, &input)
+        .unwrap();
+    assert_eq!(arena.len(), 2 + depth);
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    // the negations cancel out : E[q U ... E[q U p] ...] = E[q U p]
+    assert_eq!(
+        checker.get_sat_set_in_arena(&arena, phi),
+        checker.get_sat_set(&parse("E[q U p]"))
+    );
+}
+
+#[test]
+fn deep_formulas_in_reports() {
+    let depth = DEFAULT_MAX_NESTING_DEPTH - 1;
+    let phi = parse(&format!("{}p", "!".repeat(depth)));
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    let annotated = checker.get_annotated_sat_sets(&phi);
+    // hash-consing compares the formulae without recursion
+    let mut arena = CTLFormulaArena::new();
+    assert_eq!(arena.intern(&annotated.formula()), arena.intern(&phi));
+    let occurrences = phi.atom_occurrences();
+    assert_eq!(occurrences.len(), 1);
+    assert_eq!(occurrences[0].position, vec![0; depth]);
+    // an odd number of negations
+    assert_eq!(occurrences[0].polarity, Polarity::Negative);
+}
+
+#[test]
+fn vacuity_of_deep_formulas() {
+    let depth = DEFAULT_MAX_NESTING_DEPTH - 1;
+    // an even number of negations, below a conjunction and above a
+    // disjunction
+    let phi = parse(&format!("{}(p | q) & true", "!".repeat(depth - 3)));
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    let initial_states = checker.get_sat_set(&phi);
+    assert!(!initial_states.is_empty());
+    let report = checker
+        .check_vacuity(&initial_states, &phi)
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.occurrences.len(), 2);
+    for occurrence in &report.occurrences {
+        assert_eq!(occurrence.occurrence.position.len(), depth - 1);
+        assert_eq!(occurrence.occurrence.polarity, Polarity::Positive);
+    }
+}
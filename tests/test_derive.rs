@@ -0,0 +1,120 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the derived atomic propositions over domain structs.
+
+use std::collections::HashMap;
+
+use citreelo::kripke::{AtomicProposition, KripkeState, KripkeStructure};
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+use citreelo::solve::get_sat_set;
+use citreelo_macros::AtomicPropositions;
+use map_macro::hash_set;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Idle,
+    Waiting,
+    Critical,
+}
+
+#[derive(Debug, Clone, AtomicPropositions)]
+struct Proc {
+    busy: bool,
+    #[ap(values(Idle, Waiting, Critical))]
+    mode: Mode,
+    /// not a proposition
+    pending: u32,
+    #[ap(skip)]
+    #[allow(dead_code)]
+    traced: bool,
+    #[allow(dead_code)]
+    names: HashMap<String, Vec<u8>>,
+}
+
+#[derive(AtomicPropositions)]
+#[ap(name = Flag)]
+pub struct Flags {
+    pub is_up: bool,
+    pub(crate) r#ref: bool,
+}
+
+fn proc(busy: bool, mode: Mode, targets: Vec<usize>) -> KripkeState<Proc> {
+    KripkeState::new(
+        Proc {
+            busy,
+            mode,
+            pending: targets.len() as u32,
+            traced: false,
+            names: HashMap::new(),
+        },
+        targets,
+    )
+}
+
+#[test]
+fn propositions_follow_the_fields() {
+    assert_eq!(
+        ProcAp::ALL,
+        [
+            ProcAp::Busy,
+            ProcAp::ModeIdle,
+            ProcAp::ModeWaiting,
+            ProcAp::ModeCritical
+        ]
+    );
+    let names: Vec<String> = ProcAp::ALL.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        names,
+        ["busy", "mode.Idle", "mode.Waiting", "mode.Critical"]
+    );
+    assert_eq!(ProcAp::from_name("mode.Waiting"), Some(ProcAp::ModeWaiting));
+    assert_eq!(ProcAp::from_name("pending"), None);
+    assert_eq!(ProcAp::from_name("traced"), None);
+    assert_eq!(Flag::ALL, [Flag::IsUp, Flag::Ref]);
+    assert_eq!(Flag::Ref.name(), "ref");
+
+    let state = proc(true, Mode::Waiting, vec![]).value_in_domain;
+    assert_eq!(state.pending, 0);
+    let holding: Vec<ProcAp> = ProcAp::ALL
+        .into_iter()
+        .filter(|ap| ap.is_satisfied_on_state_domain(&state))
+        .collect();
+    assert_eq!(holding, [ProcAp::Busy, ProcAp::ModeWaiting]);
+}
+
+#[test]
+fn formulae_over_the_fields_are_checked() {
+    let kripke = KripkeStructure::new(vec![
+        proc(false, Mode::Idle, vec![1]),
+        proc(false, Mode::Waiting, vec![1, 2]),
+        proc(true, Mode::Critical, vec![0]),
+    ])
+    .unwrap();
+    let parser = ProcAp::parser();
+    let sat = |input| get_sat_set(&kripke, &parser.parse_complete_ctl_formula(input).unwrap());
+    assert_eq!(sat("busy"), hash_set! {2});
+    assert_eq!(sat("mode.Idle | mode.Critical"), hash_set! {0, 2});
+    assert_eq!(sat("EF (busy & mode.Critical)"), hash_set! {0, 1, 2});
+    assert_eq!(sat("AG (mode.Waiting => AF mode.Critical)"), hash_set! {});
+    assert_eq!(
+        parser.parse_complete_ctl_formula("AG pending"),
+        Err(CtlParseError::UnknownAtom {
+            offset: 3,
+            name: "pending".to_string()
+        })
+    );
+}
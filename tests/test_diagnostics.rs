@@ -0,0 +1,138 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the human-oriented parser diagnostics : positions, expected
+//! tokens, hints, error recovery and rendering.
+
+use citreelo::parser::CtlFormulaParser;
+use citreelo::parser::diagnostics::{CtlDiagnostic, render_diagnostics};
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::parser::{CtlConcreteParser, parse_complete};
+
+fn diagnose(input: &str) -> Vec<CtlDiagnostic> {
+    match (CtlConcreteParser {}).parse_ctl_formula_with_diagnostics(input) {
+        Ok(phi) => panic!("'{}' was parsed as {:?}", input, phi),
+        Err(diagnostics) => diagnostics,
+    }
+}
+
+#[test]
+fn valid_formulas_have_no_diagnostics() {
+    for phi in random_formulas(0xD1A, 100, 4) {
+        let printed = formula_to_string(&phi);
+        assert_eq!(
+            CtlConcreteParser {}.parse_ctl_formula_with_diagnostics(&printed),
+            Ok(phi)
+        );
+    }
+}
+
+#[test]
+fn errors_are_located_by_line_and_column() {
+    let diagnostics = diagnose("AG (p =>\n   EF z)");
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 7));
+    assert_eq!(diagnostic.offset, 15);
+    assert_eq!(diagnostic.length, 1);
+    assert!(
+        diagnostic
+            .expected
+            .contains(&"an atomic proposition".to_string())
+    );
+    assert!(diagnostic.message.ends_with("found `z`"));
+}
+
+#[test]
+fn expected_tokens_depend_on_the_context() {
+    let diagnostics = diagnose("A[p & q)");
+    assert_eq!(diagnostics[0].expected, vec!["`U`", "an operator"]);
+    assert_eq!(diagnostics[0].column, 8);
+    assert_eq!(
+        diagnostics[0].message,
+        "expected `U` or an operator, found `)`"
+    );
+    let diagnostics = diagnose("(p & q");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].expected, vec!["`)`", "an operator"]);
+    assert!(
+        diagnostics[0]
+            .message
+            .ends_with("found the end of the input")
+    );
+    let diagnostics = diagnose("p q");
+    assert_eq!(
+        diagnostics[0].expected,
+        vec!["an operator", "the end of the formula"]
+    );
+}
+
+#[test]
+fn hints_for_common_mistakes() {
+    let hint = |input: &str| diagnose(input)[0].hint.clone().unwrap_or_default();
+    assert!(hint("A(p U q)").contains("A[φ U ψ]"));
+    assert!(hint("p -> q").contains("`=>`"));
+    assert!(hint("p <-> q").contains("`<=>`"));
+    assert!(hint("p && q").contains("`&`"));
+    assert!(hint("ag p").contains("`AG`"));
+    assert!(hint("p U q").contains("until"));
+    // each of these mistakes is reported once, recovery reading the
+    // intended formula
+    for input in ["A(p U q)", "p -> q", "p && q | r", "E(p U q) & r"] {
+        assert_eq!(diagnose(input).len(), 1, "{:?}", diagnose(input));
+    }
+}
+
+#[test]
+fn several_errors_in_one_run() {
+    let input = "AG (p -> EF zz) & (q | ) & A(p U r";
+    let diagnostics = diagnose(input);
+    let columns: Vec<_> = diagnostics.iter().map(|d| d.column).collect();
+    // `->`, `zz`, the missing operand, `A(`, the missing `)`
+    assert_eq!(columns, vec![7, 13, 24, 29, 35]);
+    assert!(parse_complete(input).is_err());
+}
+
+#[test]
+fn renders_a_caret_under_the_faulty_input() {
+    let input = "AG (p & q)\n& EF (p <-> q)";
+    let diagnostics = diagnose(input);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].render(input),
+        concat!(
+            "error: expected an operator or the end of the formula, found `<->`\n",
+            "  --> 2:9\n",
+            "  |\n",
+            "2 | & EF (p <-> q)\n",
+            "  |         ^^^\n",
+            "  = hint: `<->` is written `<=>` in CTL formulae\n",
+        )
+    );
+    let both = render_diagnostics("p q r", &diagnose("p q r"));
+    assert_eq!(both.matches("error:").count(), 2);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        format!(
+            "2:9 : {} ({})",
+            diagnostics[0].message,
+            diagnostics[0].hint.as_ref().unwrap()
+        )
+    );
+}
@@ -0,0 +1,252 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the comparison atoms over the variables of the states.
+
+use std::collections::HashSet;
+
+use citreelo::ctl::{CTLFormula, CTLFormulaLeaf};
+use citreelo::expressions::{
+    Comparison, Expr, ExpressionParser, StateVariables, Value, comparison, expression,
+};
+use citreelo::kripke::{AtomicProposition, KripkeState, KripkeStructure};
+use citreelo::parser::syntax::CtlSyntax;
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+use citreelo::printer::CtlFormulaPrinter;
+use citreelo::solve::get_sat_set;
+use map_macro::hash_set;
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Idle,
+    Busy,
+}
+
+struct Counters {
+    count: i64,
+    mode: Mode,
+    x: i64,
+    y: i64,
+    ready: bool,
+}
+
+impl StateVariables for Counters {
+    fn variable(&self, name: &str) -> Option<Value> {
+        match name {
+            "count" => Some(Value::Int(self.count)),
+            "mode" => Some(Value::Symbol(format!("{:?}", self.mode))),
+            "x" => Some(Value::Int(self.x)),
+            "y" => Some(Value::Int(self.y)),
+            "ready" => Some(Value::Bool(self.ready)),
+            _ => None,
+        }
+    }
+}
+
+const VARIABLES: [&str; 5] = ["count", "mode", "x", "y", "ready"];
+
+/// ```text
+///   s0 -> s1 -> s2 -> s3 (self-loop)
+///   s3 : count 3, Idle, x + y = 9
+/// ```
+fn counters() -> KripkeStructure<Counters> {
+    let state = |count, mode, x, y, ready, next| {
+        let counters = Counters {
+            count,
+            mode,
+            x,
+            y,
+            ready,
+        };
+        KripkeState::new(counters, vec![next])
+    };
+    KripkeStructure::new(vec![
+        state(0, Mode::Idle, 0, 0, true, 1),
+        state(1, Mode::Busy, 5, 6, false, 2),
+        state(2, Mode::Busy, -4, 2, false, 3),
+        state(3, Mode::Idle, 4, 5, true, 3),
+    ])
+    .unwrap()
+}
+
+fn parse(input: &str) -> CTLFormula<Comparison> {
+    ExpressionParser::new(VARIABLES)
+        .parse_complete_ctl_formula(input)
+        .unwrap()
+}
+
+#[test]
+fn comparisons_are_evaluated_on_the_states() {
+    let kripke = counters();
+    let sat = |input| get_sat_set(&kripke, &parse(input));
+    assert_eq!(sat("count >= 3 & mode = Idle & x + y < 10"), hash_set! {3});
+    assert_eq!(sat("mode != Idle"), hash_set! {1, 2});
+    assert_eq!(sat("(x + y) * 2 >= 2 * -y + 12"), hash_set! {1, 3});
+    assert_eq!(sat("x % 4 = 0 | x / 2 = -2"), hash_set! {0, 2, 3});
+    assert_eq!(sat("ready"), hash_set! {0, 3});
+    assert_eq!(sat("ready = false => count != 0"), hash_set! {0, 1, 2, 3});
+    assert_eq!(sat("EF (count == 3 & AG ready)"), hash_set! {0, 1, 2, 3});
+    assert_eq!(sat("A[mode = Idle U mode = Busy]"), hash_set! {0, 1, 2});
+    // undefined variables, mismatched types and arithmetic errors are false
+    assert_eq!(sat("x / (count - 1) = 2 | mode < 1"), hash_set! {3});
+}
+
+#[test]
+fn comparisons_and_connectives() {
+    // comparison operators are not mistaken for connectives
+    assert_eq!(parse("ready <=> x <= 0"), parse("ready <=> (x <= 0)"));
+    assert_eq!(parse("ready=>x=-1"), parse("ready => (x = -1)"));
+    assert_eq!(parse("x<-1|y<-x"), parse("x < -1 | y < -x"));
+    let c_style = ExpressionParser::new(VARIABLES).with_syntax(CtlSyntax::CStyle);
+    assert_eq!(
+        c_style.parse_complete_ctl_formula("x < -1 -> ready <-> x-1 > 0"),
+        Ok(parse("x < -1 => ready <=> x - 1 > 0"))
+    );
+    let unicode = ExpressionParser::new(VARIABLES).with_syntax(CtlSyntax::Unicode);
+    assert_eq!(
+        unicode.parse_complete_ctl_formula("x ≤ 3 ∧ y ≠ 2"),
+        Ok(parse("x <= 3 & y != 2"))
+    );
+    // parentheses open a sub-formula when they do not start an atom
+    assert_eq!(
+        parse("(x < 3 | y > 2) & ready"),
+        parse("(x < 3 | (y > 2)) & ready")
+    );
+    assert_eq!(parse("((x))+1 = y"), parse("x + 1 = y"));
+}
+
+#[test]
+fn ill_formed_comparisons_are_rejected() {
+    let parser = ExpressionParser::new(VARIABLES);
+    for input in [
+        "Idle < 3",
+        "mode = 1 + Idle",
+        "3 = 3",
+        "x + true > 0",
+        "x <",
+        "x = (",
+    ] {
+        assert!(
+            parser.parse_complete_ctl_formula(input).is_err(),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        parser.parse_complete_ctl_formula("AG (count > 0 | cuont + 1 >= 3)"),
+        Err(CtlParseError::UnknownAtom {
+            offset: 16,
+            name: "cuont".to_string()
+        })
+    );
+    assert!(matches!(
+        parser.parse_complete_ctl_formula("x = 99999999999999999999"),
+        Err(CtlParseError::SyntaxError { offset: 0, .. })
+    ));
+    // chains of minus signs are bounded as parentheses are
+    let kripke = counters();
+    assert_eq!(
+        get_sat_set(&kripke, &parse(&format!("{}x > 0", "-".repeat(10)))),
+        get_sat_set(&kripke, &parse("x > 0"))
+    );
+    assert!(
+        parser
+            .parse_complete_ctl_formula(&format!("{}x > 0", "-".repeat(200_000)))
+            .is_err()
+    );
+    // and so are chains of arithmetic operators
+    assert_eq!(
+        get_sat_set(&kripke, &parse(&format!("x{} > 0", " + 1".repeat(60)))),
+        get_sat_set(&kripke, &parse("x > -60"))
+    );
+    for chain in [" + 1", " * x", " - (1"] {
+        let input = format!("x{} < 3", chain.repeat(200_000));
+        assert!(parser.parse_complete_ctl_formula(&input).is_err());
+    }
+}
+
+#[test]
+fn comparisons_are_printed_back() {
+    let parser = ExpressionParser::new(VARIABLES);
+    for input in [
+        "AG (count >= 3 & mode = Idle => x + y < 10)",
+        "x - (y - 1) * -(x + 2) != x % 2",
+        "ready = true | EX y = -5",
+    ] {
+        let printed = parser.print_ctl_formula(&parse(input));
+        assert_eq!(parse(&printed), parse(input), "{}", printed);
+    }
+    assert_eq!(
+        parser.print_ctl_formula(&parse("(x + y) * (x - 1) > -x")),
+        "(x + y) * (x - 1) > -x"
+    );
+}
+
+/// Atoms mixing comparisons with tests of the parity of an expression,
+/// as in `even(x + 1)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum MixedAtom {
+    Comparison(Comparison),
+    Even(Expr),
+}
+
+impl AtomicProposition<Counters> for MixedAtom {
+    fn is_satisfied_on_state_domain(&self, state: &Counters) -> bool {
+        match self {
+            MixedAtom::Comparison(comparison) => comparison.evaluate(state),
+            MixedAtom::Even(expr) => {
+                matches!(expr.evaluate(state), Some(Value::Int(i)) if i % 2 == 0)
+            }
+        }
+    }
+}
+
+struct MixedParser {
+    variables: HashSet<String>,
+}
+
+impl CtlFormulaParser<MixedAtom> for MixedParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<MixedAtom>, E> {
+        let even = nom::sequence::delimited(
+            nom::bytes::complete::tag("even("),
+            expression(&self.variables),
+            nom::bytes::complete::tag(")"),
+        );
+        nom::Parser::parse(
+            &mut nom::branch::alt((
+                nom::combinator::map(even, MixedAtom::Even),
+                nom::combinator::map(comparison(&self.variables), MixedAtom::Comparison),
+            )),
+            input,
+        )
+        .map(|(rest, atom)| (rest, CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(atom))))
+    }
+}
+
+#[test]
+fn expressions_in_other_parsers() {
+    let parser = MixedParser {
+        variables: VARIABLES.into_iter().map(String::from).collect(),
+    };
+    let kripke = counters();
+    let sat = |input| get_sat_set(&kripke, &parser.parse_complete_ctl_formula(input).unwrap());
+    assert_eq!(sat("even(x + y + 1) & count > 0"), hash_set! {1, 3});
+    assert_eq!(sat("EX even(-x)"), hash_set! {1, 2, 3});
+    assert!(parser.parse_complete_ctl_formula("even(Idle + 1)").is_err());
+}
@@ -0,0 +1,91 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! The naive and frontier fixpoint strategies of [CtlModelChecker] :
+//! both must agree with the explicit-state oracle, on the whole
+//! structure as well as restricted to the reachable states.
+
+use std::collections::HashSet;
+
+use citreelo::fixpoint::FixpointStrategy;
+use citreelo::solve::CtlModelChecker;
+
+mod common;
+
+use common::asserts::assert_matches_oracle;
+use common::generators::{all_operator_pair_formulas, random_formulas, random_total_kripke};
+use common::model::TestAtomicProp;
+use common::parser::parse;
+use common::zoo::{all_total_models, chain, cycle};
+
+const STRATEGIES: [FixpointStrategy; 2] = [FixpointStrategy::Naive, FixpointStrategy::Frontier];
+
+#[test]
+fn naive_is_the_default_strategy() {
+    let kripke = chain(3);
+    let checker = CtlModelChecker::<_, TestAtomicProp>::new(&kripke);
+    assert_eq!(checker.fixpoint_strategy(), FixpointStrategy::Naive);
+    let checker = checker.with_fixpoint_strategy(FixpointStrategy::Frontier);
+    assert_eq!(checker.fixpoint_strategy(), FixpointStrategy::Frontier);
+}
+
+#[test]
+fn both_strategies_match_the_oracle_on_zoo_models() {
+    let formulas = all_operator_pair_formulas();
+    for (name, kripke) in all_total_models() {
+        for strategy in STRATEGIES {
+            let checker = CtlModelChecker::new(&kripke)
+                .with_cache_capacity(0)
+                .with_fixpoint_strategy(strategy);
+            for phi in &formulas {
+                assert_matches_oracle(name, &checker, phi);
+            }
+        }
+    }
+}
+
+#[test]
+fn both_strategies_agree_on_long_chains_and_cycles() {
+    // fixpoints needing one iteration per state, with several frontiers
+    let formulas = [
+        "EF q", "AF q", "A[p U q]", "E[p U q]", "AG p", "EG p", "AG EF p", "EG !q",
+    ]
+    .map(parse);
+    for kripke in [chain(33), cycle(33)] {
+        let naive = CtlModelChecker::new(&kripke).with_fixpoint_strategy(FixpointStrategy::Naive);
+        let frontier =
+            CtlModelChecker::new(&kripke).with_fixpoint_strategy(FixpointStrategy::Frontier);
+        for phi in &formulas {
+            assert_eq!(naive.get_sat_set(phi), frontier.get_sat_set(phi));
+        }
+    }
+}
+
+#[test]
+fn both_strategies_agree_on_random_models_restricted_to_reachable_states() {
+    for seed in 0..8u64 {
+        let kripke = random_total_kripke(seed, 5 + seed as usize, 2);
+        let initial_states = HashSet::from([0]);
+        let checkers = STRATEGIES.map(|strategy| {
+            CtlModelChecker::restricted_to_reachable(&kripke, &initial_states)
+                .unwrap()
+                .with_fixpoint_strategy(strategy)
+        });
+        for phi in random_formulas(0xF207 + seed, 30, 3) {
+            assert_eq!(checkers[0].get_sat_set(&phi), checkers[1].get_sat_set(&phi));
+        }
+    }
+}
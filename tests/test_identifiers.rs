@@ -0,0 +1,190 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the ready-made identifier-based atom parser.
+
+use std::collections::HashMap;
+
+use citreelo::parser::identifiers::IdentifierParser;
+use citreelo::parser::syntax::CtlSyntax;
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+
+mod common;
+
+use common::model::TestAtomicProp;
+use common::parser::parse;
+
+fn atoms() -> HashMap<String, TestAtomicProp> {
+    HashMap::from([
+        ("idle".to_string(), TestAtomicProp::P),
+        ("proc1.busy".to_string(), TestAtomicProp::Q),
+        ("x > 0".to_string(), TestAtomicProp::R),
+        ("say \"hi\"".to_string(), TestAtomicProp::R),
+    ])
+}
+
+fn unknown(offset: usize, name: &str) -> CtlParseError {
+    CtlParseError::UnknownAtom {
+        offset,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn names_are_resolved_through_a_map() {
+    let parser = IdentifierParser::new(atoms());
+    let parse_names = |input| parser.parse_complete_ctl_formula(input).unwrap();
+    assert_eq!(
+        parse_names("AG (idle => EF proc1.busy)"),
+        parse("AG (p => EF q)")
+    );
+    assert_eq!(parse_names("\"x > 0\" & !idle"), parse("r & !p"));
+    assert_eq!(parse_names(r#"AX "say \"hi\"""#), parse("AX r"));
+    assert_eq!(parse_names("A[idle U\"x > 0\"]"), parse("A[p U r]"));
+}
+
+#[test]
+fn names_are_resolved_through_a_closure() {
+    let parser = IdentifierParser::new(|name: &str| match name.strip_prefix("sig_") {
+        Some("a") => Some(TestAtomicProp::P),
+        Some("b") => Some(TestAtomicProp::Q),
+        _ => None,
+    })
+    .with_syntax(CtlSyntax::CStyle);
+    assert_eq!(
+        parser.parse_complete_ctl_formula("sig_a && !sig_b -> EX sig_a"),
+        Ok(parse("p & !q => EX p"))
+    );
+    assert_eq!(
+        parser.parse_complete_ctl_formula("sig_a || sig_c"),
+        Err(unknown(9, "sig_c"))
+    );
+}
+
+#[test]
+fn unknown_names_are_reported_with_their_position() {
+    let parser = IdentifierParser::new(atoms());
+    assert_eq!(
+        parser.parse_complete_ctl_formula("AG (idle & busy)"),
+        Err(unknown(11, "busy"))
+    );
+    // names are read whole : `idler` is not `idle` followed by `r`
+    assert_eq!(
+        parser.parse_complete_ctl_formula("idler"),
+        Err(unknown(0, "idler"))
+    );
+    assert_eq!(
+        parser.parse_complete_ctl_formula(r#"EF "y \\ 0""#),
+        Err(unknown(3, r"y \ 0"))
+    );
+    // a separator not followed by an identifier ends the name
+    assert!(matches!(
+        parser.parse_complete_ctl_formula("idle."),
+        Err(CtlParseError::TrailingInput { offset: 4, .. })
+    ));
+    // malformed names remain syntax errors
+    assert!(matches!(
+        parser.parse_complete_ctl_formula("idle & \"x > 0"),
+        Err(CtlParseError::SyntaxError { offset: 7, .. })
+    ));
+    let diagnostics = parser
+        .parse_ctl_formula_with_diagnostics("busy & idle\n  | proc2.busy")
+        .unwrap_err();
+    let summary: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.length, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (1, 1, 4, "unknown atomic proposition `busy`"),
+            (2, 5, 10, "unknown atomic proposition `proc2.busy`"),
+        ]
+    );
+}
+
+#[test]
+fn configurable_names() {
+    let primed = IdentifierParser::new(HashMap::from([
+        ("$x".to_string(), TestAtomicProp::P),
+        ("$x'".to_string(), TestAtomicProp::Q),
+        ("proc1".to_string(), TestAtomicProp::R),
+    ]))
+    .with_chars(|c| c == '$', |c| c.is_alphanumeric() || c == '\'')
+    .with_separator(None)
+    .with_quote(None);
+    assert_eq!(
+        primed.parse_complete_ctl_formula("$x => AX $x'"),
+        Ok(parse("p => AX q"))
+    );
+    assert!(matches!(
+        primed.parse_complete_ctl_formula("x"),
+        Err(CtlParseError::SyntaxError { offset: 0, .. })
+    ));
+    assert!(primed.parse_complete_ctl_formula("\"$x\"").is_err());
+    let flat = IdentifierParser::new(atoms()).with_separator(None);
+    assert!(matches!(
+        flat.parse_complete_ctl_formula("proc1.busy"),
+        Err(CtlParseError::UnknownAtom { offset: 0, .. })
+    ));
+}
+
+#[test]
+fn names_and_named_formulae() {
+    let parser = IdentifierParser::new(atoms()).with_let_bindings(true);
+    // let bindings shadow atomic propositions
+    assert_eq!(
+        parser.parse_complete_ctl_formula("let idle = proc1.busy in EF idle"),
+        Ok(parse("EF q"))
+    );
+    assert_eq!(
+        parser.parse_complete_ctl_formula("let ok = idle in ok & proc1.busy"),
+        Ok(parse("p & q"))
+    );
+    // an unknown name may be recovered from by an enclosing combinator
+    assert!(matches!(
+        parser.parse_ctl_formula::<nom::error::Error<&str>>("busy"),
+        Err(nom::Err::Error(_))
+    ));
+}
+
+#[test]
+fn let_and_in_name_atoms_unless_bindings_are_enabled() {
+    let mut atoms = atoms();
+    atoms.insert("let".to_string(), TestAtomicProp::P);
+    atoms.insert("in".to_string(), TestAtomicProp::Q);
+    let parser = IdentifierParser::new(atoms);
+    for (input, expected) in [
+        ("let", "p"),
+        ("AG let", "AG p"),
+        ("idle & let", "p & p"),
+        ("let => in", "p => q"),
+    ] {
+        assert_eq!(
+            parser.parse_complete_ctl_formula(input),
+            Ok(parse(expected))
+        );
+    }
+    let parser = parser.with_let_bindings(true);
+    assert!(matches!(
+        parser.parse_complete_ctl_formula("AG let"),
+        Err(CtlParseError::SyntaxError { .. })
+    ));
+    assert_eq!(
+        parser.parse_complete_ctl_formula("let x = idle in x"),
+        Ok(parse("p"))
+    );
+}
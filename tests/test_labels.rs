@@ -0,0 +1,81 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the ready-made domain of sets of proposition names.
+
+use citreelo::kripke::{KripkeStructure, KripkeStructureBuildError};
+use citreelo::labels::{Label, LabelInterner, LabelSet, label_parser, labelled_kripke};
+use citreelo::parser::CtlFormulaParser;
+use citreelo::solve::get_sat_set;
+use map_macro::hash_set;
+
+/// the 3-state example of the README, with string labels
+fn readme_ex1() -> KripkeStructure<LabelSet> {
+    labelled_kripke([
+        (vec!["p"], vec![1, 2]),
+        (vec!["q"], vec![1]),
+        (vec!["p", "q"], vec![0]),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn formulae_over_names_are_checked() {
+    let kripke = readme_ex1();
+    let parser = label_parser();
+    let sat = |input| get_sat_set(&kripke, &parser.parse_complete_ctl_formula(input).unwrap());
+    assert_eq!(sat("p"), hash_set! {0, 2});
+    assert_eq!(sat("!q"), hash_set! {0});
+    assert_eq!(sat("EX (p & !q)"), hash_set! {2});
+    assert_eq!(sat("AX q"), hash_set! {0, 1});
+    assert_eq!(sat("AG (p => EF q)"), hash_set! {0, 1, 2});
+    // names labelling no state hold nowhere
+    assert_eq!(sat("EF unknown | \"x > 0\""), hash_set! {});
+    // any name, including those of the keywords of let bindings
+    assert_eq!(sat("EF let | in"), hash_set! {});
+}
+
+#[test]
+fn label_sets() {
+    let mut interner = LabelInterner::new();
+    let labels = interner.label_set(["busy", "grant", "busy"]);
+    assert_eq!(labels.len(), 2);
+    assert!(labels.contains("grant") && !labels.contains("idle"));
+    assert_eq!(labels.to_string(), "{busy, grant}");
+    assert_eq!(LabelSet::new().to_string(), "{}");
+    // interned labels share their name
+    let busy = interner.intern("busy");
+    let first = labels.iter().next().unwrap();
+    assert_eq!(busy, *first);
+    assert_eq!(busy.name().as_ptr(), first.name().as_ptr());
+    // labels are compared by name
+    assert_eq!(Label::new("busy"), busy);
+    let mut other = LabelSet::new();
+    assert!(other.insert(Label::new("grant")));
+    assert!(!other.insert(interner.intern("grant")));
+}
+
+#[test]
+fn labelled_structures_are_validated() {
+    assert_eq!(
+        labelled_kripke([(vec!["p"], vec![1])]).err(),
+        Some(KripkeStructureBuildError::OutOfRangeTransitionTarget {
+            origin_state_id: 0,
+            target_state_id: 1,
+            num_states: 1
+        })
+    );
+}
@@ -0,0 +1,279 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of named formulae : `let` bindings, definitions, and the
+//! errors in their uses.
+
+use citreelo::ctl::CTLFormula;
+use citreelo::parser::macros::{CtlDefinitions, MAX_EXPANSION_DEPTH};
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+use citreelo::spec::{SpecErrorKind, SpecLocation, parse_spec_str};
+
+mod common;
+
+use common::model::TestAtomicProp;
+use common::parser::{CtlConcreteParser, parse};
+
+/// The test parser, with `let` bindings enabled.
+struct LetParser {}
+
+impl CtlFormulaParser<TestAtomicProp> for LetParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        CtlConcreteParser {}.parse_atomic_proposition(input)
+    }
+
+    fn let_bindings(&self) -> bool {
+        true
+    }
+}
+
+fn parse_let_complete(input: &str) -> Result<CTLFormula<TestAtomicProp>, CtlParseError> {
+    LetParser {}.parse_complete_ctl_formula(input)
+}
+
+/// Parses a whole formula with `let` bindings, panicking on failure.
+fn parse_let(input: &str) -> CTLFormula<TestAtomicProp> {
+    parse_let_complete(input).unwrap_or_else(|e| panic!("failed to parse '{}': {}", input, e))
+}
+
+fn with_definitions(
+    definitions: &CtlDefinitions,
+    input: &str,
+) -> Result<CTLFormula<TestAtomicProp>, CtlParseError> {
+    CtlConcreteParser {}.parse_complete_ctl_formula_with_definitions(definitions, input)
+}
+
+#[test]
+fn let_bindings_are_expanded() {
+    assert_eq!(
+        parse_let("let ok = p & !q in AG (ok => EF ok)"),
+        parse("AG (p & !q => EF (p & !q))")
+    );
+    // the scope of a let extends as far right as possible
+    assert_eq!(parse_let("p & let x = q in x | r"), parse("p & (q | r)"));
+    assert_eq!(parse_let("(let x = q in x) | r"), parse("q | r"));
+    assert_eq!(parse_let("!let x = q in x & r"), parse("!(q & r)"));
+    // bindings nest, shadow each other and the atomic propositions
+    assert_eq!(
+        parse_let("let x = p in let x = x & q in AX x"),
+        parse("AX (p & q)")
+    );
+    assert_eq!(parse_let("let p = q in p | r"), parse("q | r"));
+    assert_eq!(
+        parse_let("let x = let y = p in y | q in A[x U x]"),
+        parse("A[p | q U p | q]")
+    );
+}
+
+#[test]
+fn parameterized_bindings() {
+    assert_eq!(
+        parse_let("let resp(a, b) = AG (a => AF b) in resp(p, q) & resp(q | r, !p)"),
+        parse("AG (p => AF q) & AG (q | r => AF !p)")
+    );
+    assert_eq!(parse_let("let t() = true in t() | t"), parse("true | true"));
+    // arguments are formulae : the body is not expanded textually
+    assert_eq!(
+        parse_let("let neg(a) = !a in neg(p & q)"),
+        parse("!(p & q)")
+    );
+    // names are resolved where they are written
+    assert_eq!(
+        parse_let("let x = p in let f(a) = a & x in let x = q in f(x)"),
+        parse("q & p")
+    );
+    assert_eq!(
+        parse_let("let f(a) = AX a in let g(a) = f(f(a)) in g(g(p))"),
+        parse("AX AX AX AX p")
+    );
+}
+
+#[test]
+fn definitions_may_be_used_in_any_order() {
+    let mut definitions = CtlDefinitions::new();
+    definitions.define("live", &["a"], "resp(true, a)");
+    definitions.define("resp", &["a", "b"], "AG (a => AF b)");
+    definitions.define("ok", &[], "p & !q");
+    assert_eq!(definitions.len(), 3);
+    assert_eq!(
+        with_definitions(&definitions, "live(ok) & let ok = r in resp(ok, q)"),
+        Ok(parse("AG (true => AF (p & !q)) & AG (r => AF q)"))
+    );
+    assert!(with_definitions(&CtlDefinitions::new(), "ok").is_err());
+    // the combinator-style parser reads let bindings as well
+    let (rem, phi) = LetParser {}
+        .parse_ctl_formula::<nom::error::Error<&str>>("let x = p in AX x) q")
+        .unwrap();
+    assert_eq!((rem, phi), (") q", parse("AX p")));
+}
+
+#[test]
+fn errors_in_uses() {
+    assert_eq!(
+        parse_let_complete("p & f(q)"),
+        Err(CtlParseError::UndefinedName {
+            offset: 4,
+            name: "f".to_string()
+        })
+    );
+    assert_eq!(
+        parse_let_complete("let f(a) = AX a in q | f(p, q)"),
+        Err(CtlParseError::WrongArgumentCount {
+            offset: 23,
+            name: "f".to_string(),
+            expected: 1,
+            found: 2
+        })
+    );
+    // lets are not recursive : `x` is not in scope in its own body
+    assert!(parse_let_complete("let x = AX x in x").is_err());
+    for malformed in [
+        "let x p in x",
+        "let = p in p",
+        "let x = p",
+        "let f(a = p in p",
+    ] {
+        assert!(parse_let_complete(malformed).is_err(), "{}", malformed);
+    }
+    // an error in the body of a binding is reported where it is
+    assert_eq!(
+        parse_let_complete("let x = p & in x"),
+        Err(CtlParseError::SyntaxError {
+            offset: 12,
+            near: "in x".to_string()
+        })
+    );
+    // an error in an argument too, even if the parameter is unused
+    assert!(matches!(
+        parse_let_complete("let f(a, b) = a in f(p, q &)"),
+        Err(CtlParseError::SyntaxError { offset: 27, .. })
+    ));
+}
+
+#[test]
+fn errors_in_definitions() {
+    let mut definitions = CtlDefinitions::new();
+    definitions.define("a", &[], "p & b");
+    definitions.define("b", &[], "EF a");
+    definitions.define("bad", &[], "p &");
+    definitions.define("uses_bad", &[], "AX bad");
+    assert_eq!(
+        with_definitions(&definitions, "q | a"),
+        Err(CtlParseError::CyclicDefinition {
+            offset: 4,
+            cycle: vec!["a".to_string(), "b".to_string(), "a".to_string()]
+        })
+    );
+    assert_eq!(
+        with_definitions(&definitions, "q | uses_bad"),
+        Err(CtlParseError::InvalidDefinition {
+            offset: 4,
+            name: "bad".to_string()
+        })
+    );
+    // expansions nested too deeply
+    let chain: String = (0..=MAX_EXPANSION_DEPTH)
+        .map(|i| format!("let x{} = AX x{} in ", i + 1, i))
+        .collect();
+    let input = format!("let x0 = p in {}x{}", chain, MAX_EXPANSION_DEPTH + 1);
+    assert!(matches!(
+        parse_let_complete(&input),
+        Err(CtlParseError::ExpansionTooDeep { limit, .. }) if limit == MAX_EXPANSION_DEPTH
+    ));
+}
+
+#[test]
+fn diagnostics_for_named_formulae() {
+    let diagnostics = LetParser {}
+        .parse_ctl_formula_with_diagnostics("let x = p & in x & f(q) & let g(a) = a in g(p, q)")
+        .unwrap_err();
+    let messages: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.column, diagnostic.message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            (
+                13,
+                "expected an atomic proposition, a boolean constant, a negation, \
+                 a temporal operator or `(`, found `in`"
+            ),
+            (20, "`f` is not defined"),
+            (43, "`g` takes 1 argument(s) but 2 were given"),
+        ]
+    );
+    let diagnostics = LetParser {}
+        .parse_ctl_formula_with_diagnostics("let x = p & q")
+        .unwrap_err();
+    assert_eq!(diagnostics[0].expected, vec!["`in`", "an operator"]);
+}
+
+#[test]
+fn spec_file_definitions() {
+    let parser = CtlConcreteParser {};
+    let specs = parse_spec_str(
+        &parser,
+        "spec resp-pq expect true : resp(p, ok);\n\
+         define resp(a, b) = AG (a => AF b);\n\
+         define ok = q & !r;\n\
+         spec local : let x = ok in EF x;",
+    )
+    .unwrap();
+    assert_eq!(specs[0].formula, parse("AG (p => AF (q & !r))"));
+    assert_eq!(specs[1].formula, parse("EF (q & !r)"));
+    let errors = parse_spec_str(
+        &parser,
+        "define a = b;\n\
+         define b = AX a;\n\
+         define ok = p &;\n\
+         define ok = q;\n\
+         define (x) = x;\n\
+         spec s : foo(ok);",
+    )
+    .unwrap_err();
+    let summary: Vec<_> = errors
+        .iter()
+        .map(|e| (e.location.line, e.location.column, e.to_string()))
+        .collect();
+    assert_eq!(
+        summary[0],
+        (
+            1,
+            12,
+            "1:12 : `a` is defined in terms of itself : a -> b -> a".to_string()
+        )
+    );
+    assert_eq!(summary[1].0, 2);
+    assert_eq!((summary[2].0, summary[2].1), (3, 16));
+    assert_eq!(
+        errors[3].kind,
+        SpecErrorKind::DuplicateDefinition {
+            name: "ok".to_string(),
+            previous: SpecLocation {
+                path: None,
+                line: 3,
+                column: 8
+            }
+        }
+    );
+    assert_eq!(summary[4].2, "5:8 : expected a name, found `(`");
+    assert_eq!(summary[5].2, "6:10 : `foo` is not defined");
+    assert_eq!(errors.len(), 6);
+}
@@ -0,0 +1,85 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the multi-threaded checking.
+
+use std::collections::HashSet;
+
+use citreelo::solve::CtlModelChecker;
+
+mod common;
+
+use common::generators::{random_formulas, random_total_kripke};
+use common::model::{TestAtomicProp, TestDomainOfAp};
+use common::oracle::oracle_sat_set;
+use common::parser::parse;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn checkers_are_send_and_sync() {
+    assert_send_sync::<CtlModelChecker<'static, TestDomainOfAp, TestAtomicProp>>();
+}
+
+#[test]
+fn parallel_batches_match_sequential_ones() {
+    let formulas = random_formulas(48, 60, 5);
+    for seed in 0..4 {
+        let kripke = random_total_kripke(seed, 10, 3);
+        let initial_states = HashSet::from([1, 2]);
+        let sequential = CtlModelChecker::new(&kripke)
+            .with_cache_capacity(0)
+            .check_all(&initial_states, &formulas)
+            .unwrap();
+        for num_threads in [0, 1, 3, 8] {
+            for capacity in [0, 10_000] {
+                let checker = CtlModelChecker::new(&kripke).with_cache_capacity(capacity);
+                // twice, so that the second round is answered from the cache
+                for _ in 0..2 {
+                    let parallel = checker
+                        .check_all_in_parallel(&initial_states, &formulas, num_threads)
+                        .unwrap();
+                    assert_eq!(parallel, sequential);
+                }
+            }
+        }
+        // the sub-formulae of a single formula are independent as well
+        let conjunction = parse("AG EF p & E[q U r] & (AF q | EG !r)");
+        let verdicts = CtlModelChecker::new(&kripke)
+            .check_all_in_parallel(&initial_states, std::slice::from_ref(&conjunction), 4)
+            .unwrap();
+        assert_eq!(verdicts[0].sat_set, oracle_sat_set(&kripke, &conjunction));
+    }
+}
+
+#[test]
+fn one_checker_is_shared_between_threads() {
+    let kripke = random_total_kripke(7, 12, 3);
+    let checker = CtlModelChecker::new(&kripke);
+    let formulas = random_formulas(49, 40, 4);
+    std::thread::scope(|scope| {
+        for chunk in formulas.chunks(10) {
+            let (checker, kripke) = (&checker, &kripke);
+            scope.spawn(move || {
+                for formula in chunk {
+                    let expected = oracle_sat_set(kripke, formula);
+                    assert_eq!(checker.get_sat_set(formula), expected, "{:?}", formula);
+                }
+            });
+        }
+    });
+    assert!(checker.cache_len() > 0);
+}
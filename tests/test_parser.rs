@@ -20,7 +20,7 @@ limitations under the License.
 //! No model checking happens here.
 
 use citreelo::ctl::{BinaryCTLOperator, CTLFormula, CTLFormulaLeaf, UnaryCTLOperator};
-use citreelo::parser::CtlParseError;
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
 
 use BinaryCTLOperator::*;
 use UnaryCTLOperator::*;
@@ -31,7 +31,7 @@ use common::generators::{
     all_operator_pair_formulas, all_single_operator_formulas, formula_to_string, random_formulas,
 };
 use common::model::TestAtomicProp;
-use common::parser::{parse, parse_complete, parse_partial};
+use common::parser::{CtlConcreteParser, parse, parse_complete, parse_partial};
 
 // small AST builders to keep expectations readable
 fn p() -> CTLFormula<TestAtomicProp> {
@@ -150,6 +150,39 @@ fn parentheses_override_precedence() {
     assert_eq!(parse("!(p & q)"), un(Not, bin(And, p(), q())));
 }
 
+/// The test parser, reading `(p)` as the atom `r`, if
+/// [CtlFormulaParser::parenthesized_atoms] is enabled.
+struct ParenthesizedAtomParser {
+    enabled: bool,
+}
+
+impl CtlFormulaParser<TestAtomicProp> for ParenthesizedAtomParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        match input.strip_prefix("(p)") {
+            Some(rest) => Ok((rest, r())),
+            None => CtlConcreteParser {}.parse_atomic_proposition(input),
+        }
+    }
+
+    fn parenthesized_atoms(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[test]
+fn atoms_starting_with_a_parenthesis_are_opt_in() {
+    let parse_with = |enabled| {
+        ParenthesizedAtomParser { enabled }
+            .parse_complete_ctl_formula("(p) & AX (p)")
+            .unwrap()
+    };
+    assert_eq!(parse_with(false), bin(And, p(), un(AX, p())));
+    assert_eq!(parse_with(true), bin(And, r(), un(AX, r())));
+}
+
 #[test]
 fn tolerates_whitespace() {
     assert_eq!(parse("  AX   p  "), un(AX, p()));
@@ -0,0 +1,83 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Checkers whose transition relation is partitioned by blocks of
+//! source states : whatever the block size (a single state, blocks not
+//! dividing the number of states, a single block), they must agree with
+//! the explicit-state oracle and with the monolithic relation.
+
+use std::collections::HashSet;
+
+use citreelo::bdd::TransitionPartitioning;
+use citreelo::solve::CtlModelChecker;
+
+mod common;
+
+use common::asserts::assert_matches_oracle;
+use common::generators::{all_operator_pair_formulas, random_formulas, random_total_kripke};
+use common::model::TestAtomicProp;
+use common::zoo::{all_total_models, chain};
+
+const BLOCK_SIZES: [usize; 5] = [0, 1, 2, 3, 64];
+
+#[test]
+fn partitioned_checkers_match_the_oracle_on_zoo_models() {
+    let formulas = all_operator_pair_formulas();
+    for (name, kripke) in all_total_models() {
+        // blocks of a single state, and blocks not dividing the
+        // number of states
+        for block_size in [1, 3] {
+            let checker = CtlModelChecker::new_partitioned(
+                &kripke,
+                TransitionPartitioning::SourceBlocks { block_size },
+            )
+            .with_cache_capacity(0);
+            for phi in &formulas {
+                assert_matches_oracle(name, &checker, phi);
+            }
+        }
+    }
+}
+
+#[test]
+fn partitioned_and_monolithic_checkers_agree_on_random_models() {
+    for seed in 0..8u64 {
+        let kripke = random_total_kripke(seed, 3 + 2 * seed as usize, 3);
+        let monolithic = CtlModelChecker::new(&kripke);
+        let partitioned = CtlModelChecker::new_partitioned(
+            &kripke,
+            TransitionPartitioning::SourceBlocks { block_size: 4 },
+        );
+        for phi in random_formulas(0xB10C + seed, 30, 3) {
+            assert_eq!(monolithic.get_sat_set(&phi), partitioned.get_sat_set(&phi));
+        }
+    }
+}
+
+#[test]
+fn partitioned_checkers_compute_the_reachable_states() {
+    // chain(8) : s_i -> s_{i+1}, the last state looping on itself
+    let kripke = chain(8);
+    for block_size in BLOCK_SIZES {
+        let checker = CtlModelChecker::<_, TestAtomicProp>::restricted_to_reachable_partitioned(
+            &kripke,
+            &HashSet::from([5]),
+            TransitionPartitioning::SourceBlocks { block_size },
+        )
+        .unwrap();
+        assert_eq!(checker.reachable_states(), HashSet::from([5, 6, 7]));
+    }
+}
@@ -0,0 +1,95 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the atomic propositions given as named closures.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use citreelo::parser::CtlFormulaParser;
+use citreelo::parser::identifiers::IdentifierParser;
+use citreelo::predicates::{Predicate, PredicatePrinter};
+use citreelo::printer::CtlFormulaPrinter;
+use citreelo::solve::get_sat_set;
+use citreelo_macros::ctl;
+
+mod common;
+
+use common::model::{TestAtomicProp, TestDomainOfAp};
+use common::parser::parse;
+use common::zoo::readme_ex1;
+
+fn holds(ap: TestAtomicProp) -> Predicate<TestDomainOfAp> {
+    let name = format!("{:?}", ap).to_lowercase();
+    Predicate::new(&name, move |domain: &TestDomainOfAp| {
+        domain.atoms.contains(&ap)
+    })
+}
+
+#[test]
+fn predicates_are_checked_as_atoms() {
+    let kripke = readme_ex1();
+    let (p, q) = (holds(TestAtomicProp::P), holds(TestAtomicProp::Q));
+    let both = Predicate::new("p and q", |domain: &TestDomainOfAp| domain.atoms.len() == 2);
+    for (formula, expected) in [
+        (ctl!(EX(p.clone() & !q.clone())), "EX (p & !q)"),
+        (ctl!(AG (p.clone() => EF q.clone())), "AG (p => EF q)"),
+        (ctl!(E[p.clone() U both.clone()]), "E[p U (p & q)]"),
+    ] {
+        assert_eq!(
+            get_sat_set(&kripke, &formula),
+            get_sat_set(&kripke, &parse(expected))
+        );
+    }
+    assert_eq!(
+        PredicatePrinter.print_ctl_formula(&ctl!(E[p U both])),
+        "E[p U \"p and q\"]"
+    );
+}
+
+#[test]
+fn clones_are_evaluated_once() {
+    let kripke = readme_ex1();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let p = Predicate::new("p", move |domain: &TestDomainOfAp| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        domain.atoms.contains(&TestAtomicProp::P)
+    });
+    // a distinct predicate of the same name
+    let other_p = holds(TestAtomicProp::P);
+    assert_eq!(p, p.clone());
+    assert_ne!(p, other_p);
+    get_sat_set(&kripke, &ctl!(p.clone() & AX p.clone() | EG p));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn printed_predicates_parse_back() {
+    let predicates: HashMap<String, Predicate<TestDomainOfAp>> = [
+        holds(TestAtomicProp::P),
+        Predicate::new("AX", |_: &TestDomainOfAp| true),
+        Predicate::new("queue \"long\"", |_: &TestDomainOfAp| false),
+    ]
+    .into_iter()
+    .map(|predicate| (predicate.name().to_string(), predicate))
+    .collect();
+    let parser = IdentifierParser::new(predicates);
+    let input = "AX \"AX\" | EF (p & !\"queue \\\"long\\\"\")";
+    let formula = parser.parse_complete_ctl_formula(input).unwrap();
+    assert_eq!(PredicatePrinter.print_ctl_formula(&formula), input);
+}
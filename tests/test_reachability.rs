@@ -0,0 +1,114 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the checkers restricted to the states reachable from
+//! initial states.
+
+use std::collections::HashSet;
+
+use citreelo::kripke::KripkeStructure;
+use citreelo::solve::{CtlModelChecker, CtlModelCheckingError};
+
+mod common;
+
+use common::generators::{random_formulas, random_total_kripke};
+use common::model::{TestAtomicProp, TestDomainOfAp};
+use common::oracle::oracle_sat_set;
+use common::parser::parse;
+use common::zoo::{chain, readme_ex1};
+
+/// The states reachable from `initial_states`, by explicit search.
+fn reachable_from(
+    kripke: &KripkeStructure<TestDomainOfAp>,
+    initial_states: &HashSet<usize>,
+) -> HashSet<usize> {
+    let mut reachable = initial_states.clone();
+    let mut pending: Vec<usize> = initial_states.iter().copied().collect();
+    while let Some(st_id) = pending.pop() {
+        for target in &kripke.states()[st_id].outgoing_transitions_targets {
+            if reachable.insert(*target) {
+                pending.push(*target);
+            }
+        }
+    }
+    reachable
+}
+
+#[test]
+fn restricted_sat_sets_match_oracle_on_reachable_states() {
+    let formulas = random_formulas(45, 60, 4);
+    for seed in 0..12 {
+        let kripke = random_total_kripke(seed, 9, 2);
+        let initial_states = HashSet::from([seed as usize % 9]);
+        let reachable = reachable_from(&kripke, &initial_states);
+        let checker = CtlModelChecker::restricted_to_reachable(&kripke, &initial_states).unwrap();
+        assert_eq!(checker.reachable_states(), reachable);
+        for formula in &formulas {
+            let expected: HashSet<usize> = oracle_sat_set(&kripke, formula)
+                .intersection(&reachable)
+                .copied()
+                .collect();
+            assert_eq!(checker.get_sat_set(formula), expected, "{:?}", formula);
+            let holds = checker
+                .is_ctl_formula_sat(&initial_states, formula)
+                .unwrap();
+            assert_eq!(holds, initial_states.is_subset(&expected));
+        }
+    }
+}
+
+#[test]
+fn unreachable_states_are_left_out() {
+    // s0 -> s1 -> ... -> s4 (self-loop) : from s2, only s2..s4 remain
+    let kripke = chain(5);
+    let checker = CtlModelChecker::restricted_to_reachable(&kripke, &HashSet::from([2])).unwrap();
+    assert_eq!(checker.reachable_states(), HashSet::from([2, 3, 4]));
+    assert_eq!(
+        checker.get_sat_set(&parse("true")),
+        HashSet::from([2, 3, 4])
+    );
+    assert_eq!(checker.get_sat_set(&parse("!q")), HashSet::from([2, 3]));
+    assert_eq!(checker.get_sat_set(&parse("AG p")), HashSet::new());
+    assert_eq!(
+        checker.get_sat_set(&parse("p => AX q")),
+        HashSet::from([3, 4])
+    );
+    let unrestricted = CtlModelChecker::<_, TestAtomicProp>::new(&kripke);
+    assert_eq!(unrestricted.reachable_states().len(), 5);
+}
+
+#[test]
+fn initial_states_must_be_reachable() {
+    let kripke = chain(5);
+    let checker = CtlModelChecker::restricted_to_reachable(&kripke, &HashSet::from([3])).unwrap();
+    assert_eq!(
+        checker.is_ctl_formula_sat(&HashSet::from([4, 1, 0]), &parse("true")),
+        Err(CtlModelCheckingError::UnreachableInitialState {
+            initial_state_id: 0
+        })
+    );
+    assert_eq!(
+        CtlModelChecker::<_, TestAtomicProp>::restricted_to_reachable(
+            &readme_ex1(),
+            &HashSet::from([3])
+        )
+        .err(),
+        Some(CtlModelCheckingError::OutOfRangeInitialState {
+            initial_state_id: 3,
+            num_states: 3
+        })
+    );
+}
@@ -0,0 +1,184 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the model-independent satisfiability / validity checker.
+//!
+//! Every model it produces is checked with the model checker itself,
+//! and every "unsatisfiable" verdict is cross-checked against the zoo:
+//! an unsatisfiable formula must have an empty satisfaction set on
+//! every model.
+
+use citreelo::ctl::{BinaryCTLOperator, CTLFormula, CTLFormulaLeaf};
+use citreelo::satisfiability::{
+    CtlSatisfiabilityVerdict, CtlSatisfyingModel, CtlValidityVerdict, check_ctl_satisfiability,
+    check_ctl_validity,
+};
+use citreelo::solve::CtlModelChecker;
+
+mod common;
+
+use common::generators::{all_single_operator_formulas, formula_to_string, random_formulas};
+use common::model::TestAtomicProp;
+use common::parser::parse;
+use common::zoo::all_total_models;
+
+/// Checks the verdict of the satisfiability checker on `phi`, and
+/// returns it : satisfiable verdicts must come with a model of `phi`,
+/// unsatisfiable ones must agree with every zoo model.
+fn checked_satisfiability(phi: &CTLFormula<TestAtomicProp>) -> bool {
+    match check_ctl_satisfiability(phi) {
+        CtlSatisfiabilityVerdict::Satisfiable(model) => {
+            let checker = CtlModelChecker::new(&model.kripke);
+            assert!(
+                checker
+                    .get_sat_set(&CtlSatisfyingModel::lift(phi))
+                    .contains(&model.initial_state),
+                "the model built for '{}' does not satisfy it",
+                formula_to_string(phi)
+            );
+            true
+        }
+        CtlSatisfiabilityVerdict::Unsatisfiable => {
+            for (name, kripke) in all_total_models() {
+                assert!(
+                    CtlModelChecker::new(&kripke).get_sat_set(phi).is_empty(),
+                    "'{}' was found unsatisfiable but holds somewhere on model '{}'",
+                    formula_to_string(phi),
+                    name
+                );
+            }
+            false
+        }
+    }
+}
+
+#[test]
+fn satisfiable_formulas() {
+    for formula_str in [
+        "p",
+        "!p",
+        "p & !q",
+        "AG p",
+        "EF p & AG !q",
+        "A[p U q]",
+        "AG EF p & AG EF !p",
+        "EG p & EF !p",
+        "AG (p => AF q) & AG (q => AF !q)",
+        "AX p & EX q",
+        "E[p U q] & A[p U r]",
+    ] {
+        assert!(
+            checked_satisfiability(&parse(formula_str)),
+            "'{}' should be satisfiable",
+            formula_str
+        );
+    }
+}
+
+#[test]
+fn unsatisfiable_formulas() {
+    for formula_str in [
+        "false",
+        "p & !p",
+        "EX false",
+        "AG p & EF !p",
+        "AX p & EX !p",
+        "EG p & AF !p",
+        "A[p U q] & AG !q",
+        "E[p U q] & AG !q",
+        "AF p & EG !p",
+        "AG (p => AX !p) & p & AX p",
+    ] {
+        assert!(
+            !checked_satisfiability(&parse(formula_str)),
+            "'{}' should be unsatisfiable",
+            formula_str
+        );
+    }
+}
+
+#[test]
+fn valid_and_invalid_formulas() {
+    for formula_str in [
+        "true",
+        "p | !p",
+        "EX true",
+        "AG p => p",
+        "AX p <=> !EX !p",
+        "EF p <=> E[true U p]",
+        "AG (p => q) & AG p => AG q",
+        "A[p U q] => AF q",
+        "AG EF p => EF p",
+    ] {
+        assert!(
+            check_ctl_validity(&parse(formula_str)).is_valid(),
+            "'{}' should be valid",
+            formula_str
+        );
+    }
+    for formula_str in ["p", "AF p", "EF p => AF p", "AG (p | q) => AG p | AG q"] {
+        let phi = parse(formula_str);
+        match check_ctl_validity(&phi) {
+            CtlValidityVerdict::Valid => panic!("'{}' should not be valid", formula_str),
+            CtlValidityVerdict::NotValid(counter_model) => {
+                let checker = CtlModelChecker::new(&counter_model.kripke);
+                assert!(
+                    !checker
+                        .get_sat_set(&CtlSatisfyingModel::lift(&phi))
+                        .contains(&counter_model.initial_state),
+                    "the counter-model built for '{}' satisfies it",
+                    formula_str
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn models_of_single_operator_formulas() {
+    for phi in all_single_operator_formulas() {
+        checked_satisfiability(&phi);
+    }
+}
+
+#[test]
+fn models_of_random_formulas() {
+    for phi in random_formulas(0x5A7, 150, 3) {
+        checked_satisfiability(&phi);
+    }
+}
+
+#[test]
+#[should_panic(expected = "more than the 32767 the BDD variables can encode")]
+fn closures_beyond_the_bdd_variables_are_rejected() {
+    // a balanced conjunction of 32768 distinct atoms
+    let mut layer: Vec<CTLFormula<usize>> = (0..32768)
+        .map(|atom| CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(atom)))
+        .collect();
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| {
+                CTLFormula::Binary(
+                    BinaryCTLOperator::And,
+                    Box::new(pair[0].clone()),
+                    Box::new(pair[1].clone()),
+                )
+            })
+            .collect();
+    }
+    check_ctl_satisfiability(&layer[0]);
+}
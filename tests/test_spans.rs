@@ -0,0 +1,155 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the spanned parse mode : the text of every sub-formula.
+
+use citreelo::ctl::CTLFormula;
+use citreelo::parser::CtlFormulaParser;
+use citreelo::solve::CtlModelChecker;
+use map_macro::hash_set;
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::model::TestAtomicProp;
+use common::parser::CtlConcreteParser;
+use common::zoo::chain;
+
+/// The test parser, with `let` bindings enabled.
+struct LetParser {}
+
+impl CtlFormulaParser<TestAtomicProp> for LetParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        CtlConcreteParser {}.parse_atomic_proposition(input)
+    }
+
+    fn let_bindings(&self) -> bool {
+        true
+    }
+}
+
+/// the text of the sub-formulae of `input`, by position, in pre-order
+fn texts(input: &str) -> Vec<(Vec<usize>, &str)> {
+    let parser = LetParser {};
+    let (formula, spans) = parser.parse_complete_ctl_formula_with_spans(input).unwrap();
+    assert_eq!(formula, parser.parse_complete_ctl_formula(input).unwrap());
+    spans
+        .iter()
+        .map(|(position, span)| (position, &input[span]))
+        .collect()
+}
+
+#[test]
+fn spans_follow_the_precedences() {
+    assert_eq!(
+        texts(" p & q | !r "),
+        vec![
+            (vec![], "p & q | !r"),
+            (vec![0], "p & q"),
+            (vec![0, 0], "p"),
+            (vec![0, 1], "q"),
+            (vec![1], "!r"),
+            (vec![1, 0], "r"),
+        ]
+    );
+    assert_eq!(
+        texts("AX (p) & A[ q U (r) ]"),
+        vec![
+            (vec![], "AX (p) & A[ q U (r) ]"),
+            (vec![0], "AX (p)"),
+            (vec![0, 0], "(p)"),
+            (vec![1], "A[ q U (r) ]"),
+            (vec![1, 0], "q"),
+            (vec![1, 1], "(r)"),
+        ]
+    );
+    let (_, spans) = CtlConcreteParser {}
+        .parse_complete_ctl_formula_with_spans("EX p")
+        .unwrap();
+    assert_eq!(spans.span_at(&[0]), Some(3..4));
+    assert_eq!(spans.span_at(&[0, 0]), None);
+    assert_eq!(spans.span_at(&[1]), None);
+}
+
+#[test]
+fn spans_of_named_formulae() {
+    let input = "let ok = p & !q in AG (ok => EF ok)";
+    let spans: Vec<_> = texts(input).into_iter().take(6).collect();
+    assert_eq!(
+        spans,
+        vec![
+            (vec![], input),
+            (vec![0], "(ok => EF ok)"),
+            (vec![0, 0], "ok"),
+            (vec![0, 0, 0], "p"),
+            (vec![0, 0, 1], "!q"),
+            (vec![0, 0, 1, 0], "q"),
+        ]
+    );
+    // uses of parameters keep the text of the arguments
+    assert_eq!(
+        texts("let f(a) = AX a in f(p | q) & f(r)"),
+        vec![
+            (vec![], "let f(a) = AX a in f(p | q) & f(r)"),
+            (vec![0], "f(p | q)"),
+            (vec![0, 0], "p | q"),
+            (vec![0, 0, 0], "p"),
+            (vec![0, 0, 1], "q"),
+            (vec![1], "f(r)"),
+            (vec![1, 0], "r"),
+        ]
+    );
+}
+
+#[test]
+fn spans_are_within_the_input_and_nested() {
+    for formula in random_formulas(0x5ba5, 50, 6) {
+        let input = formula_to_string(&formula);
+        let (_, spans) = CtlConcreteParser {}
+            .parse_complete_ctl_formula_with_spans(&input)
+            .unwrap();
+        for (position, span) in spans.iter() {
+            assert!(span.end <= input.len(), "{}", input);
+            if let Some((_, parent)) = position.split_last() {
+                let parent = spans.span_at(parent).unwrap();
+                assert!(parent.start <= span.start && span.end <= parent.end);
+            }
+        }
+    }
+}
+
+#[test]
+fn vacuous_occurrences_are_located() {
+    let input = "AG (p => AF q)";
+    let (formula, spans) = CtlConcreteParser {}
+        .parse_complete_ctl_formula_with_spans(input)
+        .unwrap();
+    let kripke = chain(4);
+    let report = CtlModelChecker::new(&kripke)
+        .check_vacuity(&hash_set! {0}, &formula)
+        .unwrap()
+        .unwrap();
+    let vacuous: Vec<_> = report
+        .occurrences
+        .iter()
+        .filter(|occurrence| !occurrence.affects_verdict)
+        .map(|occurrence| &input[spans.span_at(&occurrence.occurrence.position).unwrap()])
+        .collect();
+    assert_eq!(vacuous, vec!["p"]);
+}
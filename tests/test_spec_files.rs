@@ -0,0 +1,283 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of specification files : their syntax, includes, error
+//! reporting, and the batch runner.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use citreelo::ctl::{BinaryCTLOperator, CTLFormula, CTLFormulaLeaf, UnaryCTLOperator};
+use citreelo::labels::{Label, label_parser};
+use citreelo::solve::{CtlModelChecker, CtlModelCheckingError};
+use citreelo::spec::{
+    NamedSpec, SpecError, SpecErrorKind, SpecLocation, parse_spec_file, parse_spec_str,
+};
+use map_macro::hash_set;
+
+mod common;
+
+use common::model::TestAtomicProp;
+use common::parser::{CtlConcreteParser, parse};
+use common::zoo::readme_ex1;
+
+fn parse_specs(source: &str) -> Vec<NamedSpec<TestAtomicProp>> {
+    match parse_spec_str(&CtlConcreteParser {}, source) {
+        Ok(specs) => specs,
+        Err(errors) => panic!("could not parse {:?} : {:?}", source, errors),
+    }
+}
+
+fn spec_errors(source: &str) -> Vec<SpecError> {
+    parse_spec_str(&CtlConcreteParser {}, source).unwrap_err()
+}
+
+fn at(line: usize, column: usize) -> SpecLocation {
+    SpecLocation {
+        path: None,
+        line,
+        column,
+    }
+}
+
+/// A fresh directory holding the given files.
+fn spec_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("spec_files")
+        .join(test);
+    let _ = fs::remove_dir_all(&dir);
+    for (name, contents) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn entries_comments_and_expected_verdicts() {
+    let source = "// properties of the readme example\n\
+                  spec start: p;\n\
+                  /* a block\n   comment */ spec next expect true : AX q;\n\
+                  spec never-p expect false:\n    AG /* inline */ p // trailing\n    ;\n";
+    let specs = parse_specs(source);
+    let summary: Vec<_> = specs
+        .iter()
+        .map(|spec| (spec.name.as_str(), spec.expected, spec.location.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("start", None, at(2, 6)),
+            ("next", Some(true), at(4, 20)),
+            ("never-p", Some(false), at(5, 6)),
+        ]
+    );
+    assert_eq!(specs[2].formula, parse("AG p"));
+    assert!(parse_specs("  // nothing but comments\n/* */").is_empty());
+}
+
+#[test]
+fn includes_are_relative_to_the_including_file_and_read_once() {
+    let dir = spec_dir(
+        "includes",
+        &[
+            (
+                "main.ctl",
+                "include \"lib/shared.ctl\";\ninclude \"common.ctl\";\nspec main: EF (p & q);",
+            ),
+            (
+                "lib/shared.ctl",
+                "include \"../common.ctl\";\nspec shared: AX q;",
+            ),
+            ("common.ctl", "spec common: p | q;"),
+        ],
+    );
+    let specs = parse_spec_file(&CtlConcreteParser {}, dir.join("main.ctl")).unwrap();
+    let names: Vec<_> = specs.iter().map(|spec| spec.name.as_str()).collect();
+    assert_eq!(names, vec!["common", "shared", "main"]);
+    assert_eq!(
+        specs[1].location,
+        SpecLocation {
+            path: Some(dir.join("lib/shared.ctl")),
+            line: 2,
+            column: 6
+        }
+    );
+}
+
+#[test]
+fn include_errors() {
+    let dir = spec_dir(
+        "include_errors",
+        &[
+            ("a.ctl", "spec a: p;\ninclude \"b.ctl\";"),
+            ("b.ctl", "include \"a.ctl\";\n  include \"missing.ctl\";"),
+        ],
+    );
+    let errors = parse_spec_file(&CtlConcreteParser {}, dir.join("a.ctl")).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].kind,
+        SpecErrorKind::IncludeCycle {
+            path: dir.join("a.ctl")
+        }
+    );
+    assert_eq!(
+        errors[0].location,
+        SpecLocation {
+            path: Some(dir.join("b.ctl")),
+            line: 1,
+            column: 9
+        }
+    );
+    assert!(
+        matches!(&errors[1].kind, SpecErrorKind::Io { path, .. } if path.ends_with("missing.ctl"))
+    );
+    assert_eq!(
+        (errors[1].location.line, errors[1].location.column),
+        (2, 11)
+    );
+    assert!(parse_spec_file(&CtlConcreteParser {}, dir.join("none.ctl")).is_err());
+}
+
+#[test]
+fn all_errors_are_reported() {
+    let errors = spec_errors(
+        "spec a: p;\n\
+         spec b expect maybe: q;\n\
+         spek c: p;\n\
+         spec d: AG (p -> q);\n\
+         spec a: q;\n\
+         spec e p;\n\
+         spec f: p",
+    );
+    let summary: Vec<_> = errors
+        .iter()
+        .map(|e| (e.location.line, e.location.column))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![(2, 15), (3, 1), (4, 15), (5, 6), (6, 8), (7, 10)]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "2:15 : expected `true` or `false`, found `maybe`"
+    );
+    let SpecErrorKind::Formula(diagnostic) = &errors[2].kind else {
+        panic!("{:?}", errors[2])
+    };
+    assert!(diagnostic.hint.as_ref().unwrap().contains("`=>`"));
+    assert_eq!(
+        errors[3].kind,
+        SpecErrorKind::DuplicateName {
+            name: "a".to_string(),
+            previous: at(1, 6)
+        }
+    );
+    assert_eq!(
+        errors[4].to_string(),
+        "6:8 : expected `expect` or `:`, found `p`"
+    );
+    assert_eq!(spec_errors("/* unterminated")[0].location, at(1, 1));
+}
+
+#[test]
+fn formula_errors_are_located_in_the_file() {
+    // the comment before the faulty token holds multi-byte characters
+    let errors = spec_errors("spec x :\n  /* φ ψ */ p & zz;");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location, at(2, 17));
+    let SpecErrorKind::Formula(diagnostic) = &errors[0].kind else {
+        panic!("{:?}", errors[0])
+    };
+    assert_eq!((diagnostic.line, diagnostic.column), (2, 17));
+}
+
+#[test]
+fn quoted_names_may_hold_terminators_and_comment_markers() {
+    let specs = parse_spec_str(
+        &label_parser(),
+        "spec s : AG \"a;b\" ;\nspec t : EF \"a//b\" & \"/*c*/\";",
+    )
+    .unwrap();
+    let names: Vec<_> = specs.iter().map(|spec| spec.name.as_str()).collect();
+    assert_eq!(names, vec!["s", "t"]);
+    let atom = |name: &str| CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(Label::new(name)));
+    assert_eq!(
+        specs[0].formula,
+        CTLFormula::Unary(UnaryCTLOperator::AG, Box::new(atom("a;b")))
+    );
+    assert_eq!(
+        specs[1].formula,
+        CTLFormula::Binary(
+            BinaryCTLOperator::And,
+            Box::new(CTLFormula::Unary(
+                UnaryCTLOperator::EF,
+                Box::new(atom("a//b"))
+            )),
+            Box::new(atom("/*c*/")),
+        )
+    );
+}
+
+#[test]
+fn runner_reports_mismatches() {
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    let specs = parse_specs(
+        "spec start expect true: p;\n\
+         spec next expect true: AX q;\n\
+         spec always-p expect true: AG p;\n\
+         spec not-af expect false: AF !p;\n\
+         spec reach-pq: EF (p & q);\n\
+         spec wrong expect false: EX q;",
+    );
+    let report = checker.check_specs(&hash_set! {0}, &specs).unwrap();
+    let verdicts: Vec<_> = report
+        .verdicts
+        .iter()
+        .map(|verdict| (verdict.name.as_str(), verdict.holds, verdict.is_mismatch()))
+        .collect();
+    assert_eq!(
+        verdicts,
+        vec![
+            ("start", true, false),
+            ("next", true, false),
+            ("always-p", false, true),
+            ("not-af", false, false),
+            ("reach-pq", true, false),
+            ("wrong", true, true),
+        ]
+    );
+    assert_eq!(report.verdicts[2].failing_state, Some(0));
+    assert!(!report.is_success());
+    assert_eq!(
+        report.to_string(),
+        "3:6 : `always-p` fails on state 0, but was expected to hold\n\
+         6:6 : `wrong` holds, but was expected to fail\n\
+         6 specifications checked, 2 mismatches"
+    );
+    let report = checker.check_specs(&hash_set! {0, 1}, &specs).unwrap();
+    assert_eq!(report.verdicts[0].failing_state, Some(1));
+    assert_eq!(
+        checker.check_specs(&hash_set! {3}, &specs),
+        Err(CtlModelCheckingError::OutOfRangeInitialState {
+            initial_state_id: 3,
+            num_states: 3
+        })
+    );
+}
@@ -0,0 +1,164 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the leaves embedding precomputed sets of states.
+
+use std::collections::HashSet;
+
+use citreelo::ctl::{BinaryCTLOperator, CTLFormula, CTLFormulaLeaf, StateSet, UnaryCTLOperator};
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+use citreelo::satisfiability::check_ctl_satisfiability;
+use citreelo::solve::{CtlModelChecker, get_sat_set};
+
+mod common;
+
+use common::generators::formula_to_string;
+use common::model::TestAtomicProp;
+use common::oracle::oracle_sat_set;
+use common::parser::{CtlConcreteParser, parse};
+use common::zoo::{chain, readme_ex1};
+
+/// The test parser, with an atom named `{0}` standing for `r`, which
+/// state set literals shadow when they are enabled.
+struct StateSetParser {
+    enabled: bool,
+}
+
+impl CtlFormulaParser<TestAtomicProp> for StateSetParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        match input.strip_prefix("{0}") {
+            Some(rest) => Ok((rest, parse("r"))),
+            None => CtlConcreteParser {}.parse_atomic_proposition(input),
+        }
+    }
+
+    fn state_set_literals(&self) -> bool {
+        self.enabled
+    }
+}
+
+fn parse_complete(input: &str) -> Result<CTLFormula<TestAtomicProp>, CtlParseError> {
+    StateSetParser { enabled: true }.parse_complete_ctl_formula(input)
+}
+
+fn states(ids: &[usize]) -> CTLFormula<TestAtomicProp> {
+    CTLFormula::Leaf(CTLFormulaLeaf::States(ids.iter().copied().collect()))
+}
+
+#[test]
+fn sat_set_feeds_next_query() {
+    let kripke = chain(5);
+    let checker = CtlModelChecker::new(&kripke);
+    let first = checker.get_sat_set(&parse("AX q"));
+    assert_eq!(first, HashSet::from([3, 4]));
+    let next = CTLFormula::Binary(
+        BinaryCTLOperator::And,
+        Box::new(parse("p")),
+        Box::new(CTLFormula::Unary(
+            UnaryCTLOperator::EX,
+            Box::new(CTLFormula::Leaf(CTLFormulaLeaf::States(StateSet::from(
+                &first,
+            )))),
+        )),
+    );
+    assert_eq!(checker.get_sat_set(&next), HashSet::from([2, 3]));
+    assert_eq!(checker.get_sat_set(&next), oracle_sat_set(&kripke, &next));
+}
+
+#[test]
+fn out_of_range_ids_are_ignored() {
+    let kripke = readme_ex1();
+    assert_eq!(
+        get_sat_set(&kripke, &states(&[0, 2, 7])),
+        HashSet::from([0, 2])
+    );
+    assert_eq!(get_sat_set(&kripke, &states(&[])), HashSet::new());
+    let formula = CTLFormula::Unary(UnaryCTLOperator::EF, Box::new(states(&[1, 9])));
+    assert_eq!(get_sat_set(&kripke, &formula), HashSet::from([0, 1, 2]));
+    assert_eq!(
+        get_sat_set(&kripke, &formula),
+        oracle_sat_set(&kripke, &formula)
+    );
+}
+
+#[test]
+fn printed_state_sets_parse_back() {
+    let formula = CTLFormula::Unary(UnaryCTLOperator::AG, Box::new(states(&[2, 0])));
+    let text = formula_to_string(&formula);
+    assert_eq!(text, "AG {0, 2}");
+    assert_eq!(parse_complete(&text), Ok(formula));
+    assert_eq!(
+        parse_complete("E[p U {}]"),
+        Ok(CTLFormula::Binary(
+            BinaryCTLOperator::EU,
+            Box::new(parse("p")),
+            Box::new(states(&[]))
+        ))
+    );
+    assert!(parse_complete("AG {0, x}").is_err());
+}
+
+#[test]
+fn state_set_literals_are_opt_in() {
+    assert_eq!(
+        parse_complete("AX {0}"),
+        Ok(CTLFormula::Unary(
+            UnaryCTLOperator::AX,
+            Box::new(states(&[0]))
+        ))
+    );
+    let disabled = StateSetParser { enabled: false };
+    assert_eq!(
+        disabled.parse_complete_ctl_formula("AX {0}"),
+        Ok(parse("AX r"))
+    );
+    assert!(disabled.parse_complete_ctl_formula("{1}").is_err());
+}
+
+#[test]
+fn state_sets_are_independent_symbols() {
+    let contradiction = CTLFormula::Binary(
+        BinaryCTLOperator::And,
+        Box::new(states(&[0])),
+        Box::new(CTLFormula::Unary(
+            UnaryCTLOperator::Not,
+            Box::new(states(&[0])),
+        )),
+    );
+    assert!(!check_ctl_satisfiability(&contradiction).is_satisfiable());
+    let distinct = CTLFormula::Binary(
+        BinaryCTLOperator::And,
+        Box::new(states(&[0])),
+        Box::new(CTLFormula::Unary(
+            UnaryCTLOperator::Not,
+            Box::new(states(&[1])),
+        )),
+    );
+    assert!(check_ctl_satisfiability(&distinct).is_satisfiable());
+}
+
+#[test]
+fn state_sets_are_collected_leaves() {
+    let phi = parse_complete("AG ({0, 2} | p) & EX {0, 2} & !{}").unwrap();
+    let leaves = phi.collect_leaves();
+    assert_eq!(leaves.atoms.len(), 1);
+    assert_eq!(leaves.state_sets.len(), 2);
+    assert!(leaves.state_sets.contains(&states(&[2, 0])));
+    assert!(leaves.state_sets.contains(&states(&[])));
+}
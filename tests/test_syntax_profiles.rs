@@ -0,0 +1,180 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the syntax profiles : each profile must read its own
+//! spellings, reject the foreign ones, and parse back what the printer
+//! writes with it.
+
+use citreelo::ctl::{CTLFormula, CTLFormulaLeaf, UnaryCTLOperator};
+use citreelo::parser::CtlFormulaParser;
+use citreelo::parser::syntax::CtlSyntax;
+use citreelo::printer::CtlFormulaPrinter;
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::model::TestAtomicProp;
+use common::parser::{CtlConcreteParser, parse};
+
+/// The test grammar's atoms, with the given spellings for the rest.
+struct ProfileParser(CtlSyntax);
+
+impl CtlFormulaParser<TestAtomicProp> for ProfileParser {
+    fn parse_atomic_proposition<'a, E: nom::error::ParseError<&'a str>>(
+        &self,
+        input: &'a str,
+    ) -> nom::IResult<&'a str, CTLFormula<TestAtomicProp>, E> {
+        CtlConcreteParser {}.parse_atomic_proposition(input)
+    }
+
+    fn syntax(&self) -> CtlSyntax {
+        self.0
+    }
+}
+
+struct ProfilePrinter(CtlSyntax);
+
+impl CtlFormulaPrinter<TestAtomicProp> for ProfilePrinter {
+    fn print_atomic_proposition(&self, ap: &TestAtomicProp) -> String {
+        match ap {
+            TestAtomicProp::P => "p",
+            TestAtomicProp::Q => "q",
+            TestAtomicProp::R => "r",
+        }
+        .to_string()
+    }
+
+    fn syntax(&self) -> CtlSyntax {
+        self.0
+    }
+}
+
+fn parse_with(syntax: CtlSyntax, input: &str) -> CTLFormula<TestAtomicProp> {
+    match ProfileParser(syntax).parse_complete_ctl_formula(input) {
+        Ok(phi) => phi,
+        Err(e) => panic!("could not parse {:?} with {:?} : {}", input, syntax, e),
+    }
+}
+
+fn rejects(syntax: CtlSyntax, input: &str) -> bool {
+    ProfileParser(syntax)
+        .parse_complete_ctl_formula(input)
+        .is_err()
+}
+
+#[test]
+fn the_default_profile_is_the_historical_syntax() {
+    assert_eq!(CtlConcreteParser {}.syntax(), CtlSyntax::Citreelo);
+    for phi in random_formulas(0x5A7, 100, 4) {
+        assert_eq!(
+            ProfilePrinter(CtlSyntax::Citreelo).print_ctl_formula(&phi),
+            formula_to_string(&phi)
+        );
+    }
+}
+
+#[test]
+fn every_profile_parses_back_its_printed_formulae() {
+    for syntax in CtlSyntax::ALL {
+        for phi in random_formulas(0x5A8, 100, 4) {
+            let printed = ProfilePrinter(syntax).print_ctl_formula(&phi);
+            assert_eq!(parse_with(syntax, &printed), phi, "{:?}", printed);
+        }
+    }
+}
+
+#[test]
+fn unicode_profile() {
+    let unicode = |input| parse_with(CtlSyntax::Unicode, input);
+    assert_eq!(unicode("AG(p → EF q)"), parse("AG (p => EF q)"));
+    assert_eq!(unicode("¬p ∧ q ∨ r ↔ ⊤"), parse("!p & q | r <=> true"));
+    assert_eq!(unicode("p ⇒ q ⇔ ⊥"), parse("p => q <=> false"));
+    assert_eq!(
+        ProfilePrinter(CtlSyntax::Unicode).print_ctl_formula(&parse("!(p & q) => A[p U false]")),
+        "¬(p ∧ q) → A[p U ⊥]"
+    );
+    assert!(rejects(CtlSyntax::Unicode, "!p"));
+    assert!(rejects(CtlSyntax::Unicode, "p & q"));
+}
+
+#[test]
+fn c_style_profile() {
+    let c_style = |input| parse_with(CtlSyntax::CStyle, input);
+    assert_eq!(c_style("!(p && q) || ~r -> q"), parse("!(p & q) | !r => q"));
+    assert_eq!(c_style("p <-> EX q"), parse("p <=> EX q"));
+    assert!(rejects(CtlSyntax::CStyle, "p & q"));
+    assert!(rejects(CtlSyntax::CStyle, "p => q"));
+}
+
+#[test]
+fn nusmv_profile() {
+    let nusmv = |input| parse_with(CtlSyntax::NuSmv, input);
+    assert_eq!(nusmv("A [ p U q ] -> TRUE"), parse("A[p U q] => true"));
+    assert_eq!(nusmv("p xnor q"), parse("p <=> q"));
+    assert_eq!(nusmv("p xor q"), parse("!(p <=> q)"));
+    // xor and xnor bind as |
+    assert_eq!(nusmv("p & q xor r"), parse("!(p & q <=> r)"));
+    assert_eq!(nusmv("p xor q | r"), parse("!(p <=> q) | r"));
+    assert_eq!(
+        nusmv("p | q xnor r <-> FALSE"),
+        parse("(p | q <=> r) <=> false")
+    );
+    assert!(rejects(CtlSyntax::NuSmv, "true"));
+    assert!(rejects(CtlSyntax::NuSmv, "p => q"));
+    assert!(rejects(CtlSyntax::Citreelo, "p xor q"));
+}
+
+#[test]
+fn permissive_profile_mixes_spellings() {
+    let permissive = |input| parse_with(CtlSyntax::Permissive, input);
+    assert_eq!(
+        permissive("¬p && q ∨ ~r -> TRUE <=> q ⇒ ⊥"),
+        parse("!p & q | !r => true <=> q => false")
+    );
+    assert_eq!(permissive("p || q | r"), parse("p | q | r"));
+    assert_eq!(
+        permissive("p xor q"),
+        CTLFormula::Unary(UnaryCTLOperator::Not, Box::new(parse("p <=> q")))
+    );
+    assert_eq!(permissive("⊤"), CTLFormula::Leaf(CTLFormulaLeaf::True));
+    for rejected in ["p & & q", "p -> ", "~"] {
+        assert!(rejects(CtlSyntax::Permissive, rejected), "{}", rejected);
+    }
+}
+
+#[test]
+fn the_default_profile_rejects_foreign_spellings() {
+    for input in ["p && q", "p -> q", "p <-> q", "¬p", "~p", "p ∧ q", "TRUE"] {
+        assert!(rejects(CtlSyntax::Citreelo, input), "{}", input);
+    }
+}
+
+#[test]
+fn diagnostics_suggest_the_spelling_of_the_profile() {
+    let hint = |syntax, input| {
+        let diagnostics = ProfileParser(syntax)
+            .parse_ctl_formula_with_diagnostics(input)
+            .unwrap_err();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        diagnostics[0].hint.clone().unwrap_or_default()
+    };
+    assert!(hint(CtlSyntax::CStyle, "p & q").contains("`&&`"));
+    assert!(hint(CtlSyntax::CStyle, "p => q").contains("`->`"));
+    assert!(hint(CtlSyntax::NuSmv, "p <=> q").contains("`<->`"));
+    assert!(hint(CtlSyntax::Unicode, "!p ∧ q").contains("`¬`"));
+    assert!(hint(CtlSyntax::Citreelo, "p || q").contains("`|`"));
+    assert!(hint(CtlSyntax::Citreelo, "~p").contains("`!`"));
+}
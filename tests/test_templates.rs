@@ -0,0 +1,170 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the formula templates quantified over ranges of indices.
+
+use std::collections::HashMap;
+
+use citreelo::ctl::{CTLFormula, CTLFormulaLeaf};
+use citreelo::labels::{AnyLabel, Label};
+use citreelo::parser::identifiers::IdentifierParser;
+use citreelo::parser::{CtlFormulaParser, CtlParseError};
+use citreelo::solve::CtlModelChecker;
+use citreelo::templates::{CtlTemplate, CtlTemplateError, Quantifier, parse_ctl_template};
+use map_macro::hash_set;
+
+/// reads indexed names such as `req[0]`
+fn indexed_parser() -> IdentifierParser<AnyLabel> {
+    IdentifierParser::new(AnyLabel).with_chars(
+        |c| c.is_alphabetic() || c == '_',
+        |c| c.is_alphanumeric() || c == '_' || c == '[' || c == ']',
+    )
+}
+
+fn template(input: &str) -> Result<CtlTemplate<Label>, CtlTemplateError> {
+    let constants = HashMap::from([("N".to_string(), 3)]);
+    parse_ctl_template(&indexed_parser(), input, &constants)
+}
+
+fn texts(input: &str) -> Vec<String> {
+    template(input)
+        .unwrap()
+        .instances
+        .into_iter()
+        .map(|instance| instance.text)
+        .collect()
+}
+
+#[test]
+fn templates_expand_into_instances() {
+    let forall = template("forall i in 0..N: AG (req[i] => AF grant[i])").unwrap();
+    assert_eq!(forall.quantifiers[0].quantifier, Quantifier::ForAll);
+    assert_eq!(forall.quantifiers[0].variable, "i");
+    let parse = |input| indexed_parser().parse_complete_ctl_formula(input).unwrap();
+    assert_eq!(
+        forall.formula,
+        parse(
+            "(AG (req[0] => AF grant[0]) & AG (req[1] => AF grant[1])) \
+             & AG (req[2] => AF grant[2])"
+        )
+    );
+    assert_eq!(forall.instances[1].indices, vec![1]);
+    assert_eq!(
+        texts("forall i in 0..N: forall j in i+1..N: AG !(crit[i] & crit[j])"),
+        vec![
+            "AG !(crit[0] & crit[1])",
+            "AG !(crit[0] & crit[2])",
+            "AG !(crit[1] & crit[2])",
+        ]
+    );
+    // untils are not indices ; indices are taken modulo in `0..n`
+    assert_eq!(
+        texts("exists i in 1..=2: A[tok[(i - 2) % N] U tok[i][i * 2] ]"),
+        vec!["A[tok[2] U tok[1][2] ]", "A[tok[0] U tok[2][4] ]"]
+    );
+    let exists = template("exists k in 0..2: EF leader[k]").unwrap();
+    assert_eq!(exists.formula, parse("EF leader[0] | EF leader[1]"));
+    // empty ranges
+    let empty = |input| template(input).unwrap().formula;
+    assert_eq!(
+        empty("forall i in N..0: p[i]"),
+        CTLFormula::Leaf(CTLFormulaLeaf::True)
+    );
+    assert_eq!(
+        empty("exists i in 0..0: p[i]"),
+        CTLFormula::Leaf(CTLFormulaLeaf::False)
+    );
+    // a formula without quantifier is its only instance
+    assert_eq!(texts("AG p[N - 1]"), vec!["AG p[2]"]);
+}
+
+#[test]
+fn instances_have_their_own_verdicts() {
+    // grant[1] never holds
+    let kripke = citreelo::labels::labelled_kripke([
+        (vec!["req[0]", "req[1]"], vec![1]),
+        (vec!["grant[0]"], vec![2]),
+        (vec!["req[1]"], vec![0]),
+    ])
+    .unwrap();
+    let checker = CtlModelChecker::new(&kripke);
+    let verdict = checker
+        .check_template(
+            &hash_set! {0},
+            &template("forall i in 0..2: AG (req[i] => AF grant[i])").unwrap(),
+        )
+        .unwrap();
+    assert!(!verdict.holds);
+    let failing: Vec<(&str, Option<usize>)> = verdict
+        .failing_instances()
+        .map(|instance| (instance.text.as_str(), instance.failing_state))
+        .collect();
+    assert_eq!(failing, vec![("AG (req[1] => AF grant[1])", Some(0))]);
+    assert!(verdict.instances[0].holds);
+    let verdict = checker
+        .check_template(
+            &hash_set! {0},
+            &template("exists i in 0..2: AG (req[i] => AF grant[i])").unwrap(),
+        )
+        .unwrap();
+    assert!(verdict.holds);
+    assert_eq!(verdict.failing_instances().count(), 1);
+}
+
+#[test]
+fn template_errors_are_located() {
+    assert_eq!(
+        template("forall i 0..N: p[i]"),
+        Err(CtlTemplateError::SyntaxError {
+            offset: 9,
+            near: "0..N: p[i]".to_string()
+        })
+    );
+    assert_eq!(
+        template("forall i in 0..M: p[i]"),
+        Err(CtlTemplateError::UndefinedIndex {
+            offset: 15,
+            name: "M".to_string()
+        })
+    );
+    // indices are checked even over empty ranges
+    assert_eq!(
+        template("forall i in 0..0: p[j]"),
+        Err(CtlTemplateError::UndefinedIndex {
+            offset: 20,
+            name: "j".to_string()
+        })
+    );
+    assert_eq!(
+        template("forall i in 0..N: p[N / i]"),
+        Err(CtlTemplateError::InvalidIndex { offset: 20 })
+    );
+    assert_eq!(
+        template("forall i in 0..N: p[i] &"),
+        Err(CtlTemplateError::InvalidInstance {
+            indices: vec![0],
+            text: "p[0] &".to_string(),
+            error: CtlParseError::SyntaxError {
+                offset: 6,
+                near: "".to_string()
+            }
+        })
+    );
+    assert!(matches!(
+        template("forall i in 0..1000: forall j in 0..1000: p[i]"),
+        Err(CtlTemplateError::TooManyInstances { .. })
+    ));
+}
@@ -0,0 +1,170 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of the generic traversal API on formulae : folds, atom mapping
+//! and sub-formula iteration.
+
+use citreelo::ctl::{
+    BinaryCTLOperator, CTLFormula, CTLFormulaFolder, CTLFormulaLeaf, UnaryCTLOperator,
+};
+
+mod common;
+
+use common::generators::{formula_to_string, random_formulas};
+use common::model::TestAtomicProp::{self, *};
+use common::parser::parse;
+
+/// Counts the nodes and the depth of a formula.
+struct SizeAndDepth;
+
+impl<AP> CTLFormulaFolder<AP> for SizeAndDepth {
+    type Output = (usize, usize);
+
+    fn fold_leaf(&mut self, _leaf: &CTLFormulaLeaf<AP>) -> (usize, usize) {
+        (1, 1)
+    }
+
+    fn fold_unary(&mut self, _op: &UnaryCTLOperator, sub: (usize, usize)) -> (usize, usize) {
+        (sub.0 + 1, sub.1 + 1)
+    }
+
+    fn fold_binary(
+        &mut self,
+        _op: &BinaryCTLOperator,
+        left: (usize, usize),
+        right: (usize, usize),
+    ) -> (usize, usize) {
+        (left.0 + right.0 + 1, left.1.max(right.1) + 1)
+    }
+}
+
+/// Records the order in which nodes are folded.
+struct FoldOrder(Vec<String>);
+
+impl CTLFormulaFolder<TestAtomicProp> for FoldOrder {
+    type Output = ();
+
+    fn fold_leaf(&mut self, leaf: &CTLFormulaLeaf<TestAtomicProp>) {
+        self.0
+            .push(formula_to_string(&CTLFormula::Leaf(leaf.clone())));
+    }
+
+    fn fold_unary(&mut self, op: &UnaryCTLOperator, _sub: ()) {
+        self.0.push(format!("{:?}", op));
+    }
+
+    fn fold_binary(&mut self, op: &BinaryCTLOperator, _left: (), _right: ()) {
+        self.0.push(format!("{:?}", op));
+    }
+}
+
+#[test]
+fn fold_is_bottom_up_and_left_to_right() {
+    let phi = parse("AG (p => E[q U !r])");
+    assert_eq!(phi.fold(&mut SizeAndDepth), (7, 5));
+    let mut order = FoldOrder(Vec::new());
+    phi.fold(&mut order);
+    assert_eq!(order.0, vec!["p", "q", "r", "Not", "EU", "Imply", "AG"]);
+}
+
+#[test]
+fn fold_handles_deep_formulas() {
+    let mut phi = parse("p");
+    for _ in 0..100_000 {
+        phi = CTLFormula::Unary(UnaryCTLOperator::EX, Box::new(phi));
+    }
+    assert_eq!(phi.fold(&mut SizeAndDepth), (100_001, 100_001));
+    assert_eq!(phi.subformulas().count(), 100_001);
+    // formulae are dropped recursively : unwind without recursion
+    let mut current = phi;
+    while let CTLFormula::Unary(_, sub) = current {
+        current = *sub;
+    }
+}
+
+#[test]
+fn map_atoms_preserves_structure() {
+    let phi = parse("AG (p => A[q U r]) & EF !p");
+    let swapped = phi.map_atoms(|ap| match ap {
+        P => Q,
+        Q => P,
+        R => R,
+    });
+    assert_eq!(swapped, parse("AG (q => A[p U r]) & EF !q"));
+    let names = phi.map_atoms(|ap| format!("{:?}", ap));
+    assert_eq!(
+        names
+            .subformulas()
+            .filter(|sub| matches!(sub, CTLFormula::Leaf(CTLFormulaLeaf::AtomicProp(_))))
+            .count(),
+        4
+    );
+    for phi in random_formulas(0x3A9, 50, 4) {
+        assert_eq!(phi.map_atoms(|ap| ap.clone()), phi);
+    }
+}
+
+#[test]
+fn try_map_atoms_stops_at_first_error() {
+    let phi = parse("p & (q | r) & q");
+    let mut visited = Vec::new();
+    let result = phi.try_map_atoms(|ap| {
+        visited.push(ap.clone());
+        match ap {
+            Q => Err(format!("{:?} is not allowed", ap)),
+            _ => Ok(ap.clone()),
+        }
+    });
+    assert_eq!(result, Err("Q is not allowed".to_string()));
+    assert_eq!(visited, vec![P, Q]);
+    assert_eq!(
+        phi.try_map_atoms(|ap| Ok::<_, ()>(ap.clone())),
+        Ok(phi.clone())
+    );
+}
+
+#[test]
+fn subformulas_are_in_pre_order_with_positions() {
+    let phi = parse("AG (p => E[q U r])");
+    let subs: Vec<_> = phi.subformulas().map(formula_to_string).collect();
+    assert_eq!(subs.len(), 6);
+    assert_eq!(subs[0], formula_to_string(&phi));
+    assert_eq!(&subs[2..], &["p", "E[q U r]", "q", "r"]);
+    let positions: Vec<_> = phi
+        .positioned_subformulas()
+        .map(|(position, _)| position)
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            vec![],
+            vec![0],
+            vec![0, 0],
+            vec![0, 1],
+            vec![0, 1, 0],
+            vec![0, 1, 1]
+        ]
+    );
+    for phi in random_formulas(0x7E5, 50, 4) {
+        for (position, sub) in phi.positioned_subformulas() {
+            assert_eq!(phi.subformula_at(&position), Some(sub));
+        }
+        assert!(
+            phi.subformulas()
+                .eq(phi.positioned_subformulas().map(|(_, sub)| sub))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+/*
+Copyright 2025 Erwan Mahe (github.com/erwanM974)
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Tests of vacuity detection: hand-computed reports on small models,
+//! and the defining property of a vacuous occurrence checked on random
+//! formulae over the zoo — replacing it by *any* leaf keeps the
+//! formula satisfied (for occurrences that are not under `<=>`).
+
+use citreelo::ctl::{CTLFormula, CTLFormulaLeaf};
+use citreelo::solve::CtlModelChecker;
+use citreelo::vacuity::Polarity;
+use map_macro::hash_set;
+
+mod common;
+
+use common::generators::{formula_to_string, leaves, random_formulas};
+use common::model::TestAtomicProp::*;
+use common::parser::parse;
+use common::zoo::{all_total_models, chain, readme_ex1};
+
+#[test]
+fn request_never_issued_is_vacuous_in_grant() {
+    // on chain4, r never holds : the response property holds whatever q
+    let kripke = chain(4);
+    let checker = CtlModelChecker::new(&kripke);
+    let report = checker
+        .check_vacuity(&hash_set! {0}, &parse("AG (r => AF q)"))
+        .unwrap()
+        .expect("the property holds");
+    assert_eq!(report.occurrences.len(), 2);
+    let r_occ = &report.occurrences[0];
+    assert_eq!(r_occ.occurrence.atom, R);
+    assert_eq!(r_occ.occurrence.position, vec![0, 0]);
+    assert_eq!(r_occ.occurrence.polarity, Polarity::Negative);
+    let q_occ = &report.occurrences[1];
+    assert_eq!(q_occ.occurrence.atom, Q);
+    assert_eq!(q_occ.occurrence.position, vec![0, 1, 0]);
+    assert_eq!(q_occ.occurrence.polarity, Polarity::Positive);
+    assert!(!q_occ.affects_verdict);
+    assert_eq!(q_occ.interesting_witness, None);
+    assert!(report.is_vacuous());
+}
+
+#[test]
+fn non_vacuous_occurrence_has_an_interesting_witness() {
+    // on readme_ex1 from s0, AG (p => AF q) : without q, AG !p fails on
+    // s0 ; without p, AG AF q still holds
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    let report = checker
+        .check_vacuity(&hash_set! {0}, &parse("AG (p => AF q)"))
+        .unwrap()
+        .unwrap();
+    let p_occ = &report.occurrences[0];
+    assert_eq!(p_occ.occurrence.atom, P);
+    assert!(!p_occ.affects_verdict);
+    let q_occ = &report.occurrences[1];
+    assert_eq!(q_occ.occurrence.atom, Q);
+    assert!(q_occ.affects_verdict);
+    assert_eq!(q_occ.interesting_witness, Some(0));
+    assert_eq!(
+        report
+            .vacuous_occurrences()
+            .map(|occ| &occ.atom)
+            .collect::<Vec<_>>(),
+        vec![&P]
+    );
+}
+
+#[test]
+fn polarities() {
+    let phi = parse("!p & (q => r) & (p <=> AX !q)");
+    let polarities: Vec<_> = phi
+        .atom_occurrences()
+        .into_iter()
+        .map(|occ| (occ.atom, occ.polarity))
+        .collect();
+    assert_eq!(
+        polarities,
+        vec![
+            (P, Polarity::Negative),
+            (Q, Polarity::Negative),
+            (R, Polarity::Positive),
+            (P, Polarity::Mixed),
+            (Q, Polarity::Mixed),
+        ]
+    );
+}
+
+#[test]
+fn failing_formula_has_no_report() {
+    let kripke = readme_ex1();
+    let checker = CtlModelChecker::new(&kripke);
+    assert_eq!(
+        checker.check_vacuity(&hash_set! {0}, &parse("AG p")),
+        Ok(None)
+    );
+    assert!(checker.check_vacuity(&hash_set! {5}, &parse("p")).is_err());
+}
+
+#[test]
+fn vacuous_occurrences_can_be_replaced_by_anything() {
+    for (name, kripke) in all_total_models() {
+        let checker = CtlModelChecker::new(&kripke);
+        let initial = hash_set! {0};
+        let seed = 0x7AC + name.bytes().map(u64::from).sum::<u64>();
+        for phi in random_formulas(seed, 30, 3) {
+            let Some(report) = checker.check_vacuity(&initial, &phi).unwrap() else {
+                continue;
+            };
+            // under <=> there is no monotonicity : the property only
+            // holds for occurrences of a definite polarity
+            for occ in report
+                .vacuous_occurrences()
+                .filter(|occ| occ.polarity != Polarity::Mixed)
+            {
+                for replacement in leaves() {
+                    let mutated = phi.replace_at(&occ.position, replacement.clone()).unwrap();
+                    assert!(
+                        checker.is_ctl_formula_sat(&initial, &mutated).unwrap(),
+                        "'{}' is reported vacuous in the occurrence at {:?} on model '{}', \
+                         but fails once it is replaced by '{}'",
+                        formula_to_string(&phi),
+                        occ.position,
+                        name,
+                        formula_to_string(&replacement)
+                    );
+                }
+            }
+            for occ in report.occurrences.iter().filter(|occ| occ.affects_verdict) {
+                let witness = occ.interesting_witness.unwrap();
+                assert!(initial.contains(&witness));
+            }
+        }
+    }
+}
+
+#[test]
+fn positions_address_subformulas() {
+    let phi = parse("AG (p => E[q U r])");
+    assert_eq!(phi.subformula_at(&[]), Some(&phi));
+    assert_eq!(phi.subformula_at(&[0, 1, 1]), Some(&parse("r")));
+    assert_eq!(phi.subformula_at(&[0, 2]), None);
+    assert_eq!(phi.subformula_at(&[0, 0, 0]), None);
+    assert_eq!(
+        phi.replace_at(&[0, 1, 0], CTLFormula::Leaf(CTLFormulaLeaf::True)),
+        Some(parse("AG (p => E[true U r])"))
+    );
+    assert_eq!(phi.replace_at(&[1], parse("p")), None);
+}